@@ -0,0 +1,147 @@
+use std::{
+    fmt,
+    sync::{LazyLock, OnceLock},
+};
+
+use crate::outbound;
+use crate::settings;
+use crate::tools::default_api_url;
+
+/// Header carrying the pinned Lago API version on outgoing requests.
+pub const API_VERSION_HEADER: &str = "X-Lago-Api-Version";
+
+/// First Lago release exposing the endpoints each tool relies on.
+///
+/// Tools missing from this list work with every supported Lago version.
+const TOOL_MIN_VERSIONS: &[(&str, LagoVersion)] = &[
+    ("preview_invoice", LagoVersion(1, 15, 0)),
+    ("report_upcoming_renewals", LagoVersion(1, 15, 0)),
+    ("list_payments", LagoVersion(1, 18, 0)),
+    ("get_payment", LagoVersion(1, 18, 0)),
+    ("list_customer_payments", LagoVersion(1, 18, 0)),
+    ("create_payment", LagoVersion(1, 18, 0)),
+    ("get_dso", LagoVersion(1, 18, 0)),
+    ("get_activity_log", LagoVersion(1, 20, 0)),
+    ("list_activity_logs", LagoVersion(1, 20, 0)),
+    ("get_api_log", LagoVersion(1, 23, 0)),
+    ("list_api_logs", LagoVersion(1, 23, 0)),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LagoVersion(u32, u32, u32);
+
+impl LagoVersion {
+    /// Parse versions such as `v1.21.0` or `1.21`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().trim_start_matches('v').split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().unwrap_or("0").parse().ok()?;
+        let patch = parts
+            .next()
+            .map(|patch| patch.split(['-', '+']).next().unwrap_or(patch))
+            .unwrap_or("0")
+            .parse()
+            .ok()?;
+
+        Some(Self(major, minor, patch))
+    }
+}
+
+impl fmt::Display for LagoVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+static PINNED_VERSION: LazyLock<Option<String>> = LazyLock::new(|| {
+    settings::var("LAGO_API_VERSION").filter(|version| !version.trim().is_empty())
+});
+
+static TARGET_VERSION: OnceLock<LagoVersion> = OnceLock::new();
+
+/// The Lago API version pinned through `LAGO_API_VERSION`, sent with every raw API request.
+pub fn pinned_version() -> Option<&'static str> {
+    PINNED_VERSION.as_deref()
+}
+
+/// Resolve the Lago version the tools are checked against.
+///
+/// The version of the connected instance is read from its public `/health` endpoint. A
+/// pinned version takes precedence, with a warning when the instance is older than the pin.
+pub async fn init() {
+    let pinned = pinned_version().and_then(|version| {
+        let parsed = LagoVersion::parse(version);
+        if parsed.is_none() {
+            tracing::warn!(version, "Ignoring invalid LAGO_API_VERSION");
+        }
+        parsed
+    });
+    let detected = detect_instance_version().await;
+
+    if let (Some(pinned), Some(detected)) = (pinned, detected)
+        && detected < pinned
+    {
+        tracing::warn!(
+            %pinned,
+            %detected,
+            "The connected Lago instance is older than the pinned API version"
+        );
+    }
+
+    let Some(target) = pinned.or(detected) else {
+        return;
+    };
+    let _ = TARGET_VERSION.set(target);
+
+    let unsupported: Vec<&str> = TOOL_MIN_VERSIONS
+        .iter()
+        .filter(|(_, min_version)| target < *min_version)
+        .map(|(tool_name, _)| *tool_name)
+        .collect();
+
+    if unsupported.is_empty() {
+        tracing::info!(version = %target, "Lago API version resolved");
+    } else {
+        tracing::warn!(
+            version = %target,
+            tools = ?unsupported,
+            "Some tools rely on endpoints this Lago version doesn't support and are disabled"
+        );
+    }
+}
+
+/// Why a tool is disabled for the resolved Lago version, if it is.
+pub fn unsupported_reason(tool_name: &str) -> Option<String> {
+    let target = TARGET_VERSION.get()?;
+    let (_, min_version) = TOOL_MIN_VERSIONS
+        .iter()
+        .find(|(name, _)| *name == tool_name)?;
+
+    (target < min_version).then(|| {
+        format!("Disabled: {tool_name} requires Lago {min_version} or later, the connected instance is {target}")
+    })
+}
+
+async fn detect_instance_version() -> Option<LagoVersion> {
+    let base_url = default_api_url();
+    let health_url = format!(
+        "{}/health",
+        base_url.trim_end_matches('/').trim_end_matches("/api/v1")
+    );
+
+    let request = outbound::with_headers(reqwest::Client::new().get(&health_url));
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::warn!(%health_url, error = %e, "Failed to detect the Lago version");
+            return None;
+        }
+    };
+    let health: serde_json::Value = response.json().await.ok()?;
+    let version = health["version"].as_str().and_then(LagoVersion::parse);
+
+    if version.is_none() {
+        tracing::warn!(%health_url, "The Lago instance did not report its version");
+    }
+    version
+}
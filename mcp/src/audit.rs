@@ -0,0 +1,192 @@
+use std::{
+    cell::RefCell,
+    fs::{File, OpenOptions},
+    future::Future,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use chrono::Utc;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::redaction;
+use crate::settings;
+
+/// Socket of the local syslog daemon.
+#[cfg(unix)]
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Syslog priority of audit records: facility `log audit` (13), severity `info` (6).
+#[cfg(unix)]
+const SYSLOG_PRIORITY: u8 = 13 * 8 + 6;
+
+/// Hash chaining the first record of a new trail.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Where audit records are written.
+enum Sink {
+    File(File),
+    #[cfg(unix)]
+    Syslog(std::os::unix::net::UnixDatagram),
+}
+
+struct AuditTrail {
+    sink: Sink,
+    /// Hash of the last record written, chained into the next one.
+    last_hash: String,
+}
+
+/// Audit trail from `LAGO_MCP_AUDIT_LOG`: a file path, or `syslog`. Disabled when unset.
+static TRAIL: LazyLock<Option<Mutex<AuditTrail>>> = LazyLock::new(|| {
+    let target = settings::var("LAGO_MCP_AUDIT_LOG")?;
+    let target = target.trim();
+    if target.is_empty() {
+        return None;
+    }
+
+    let opened = if target.eq_ignore_ascii_case("syslog") {
+        open_syslog().map(|sink| AuditTrail {
+            sink,
+            last_hash: GENESIS_HASH.to_string(),
+        })
+    } else {
+        let path = PathBuf::from(target);
+        let last_hash = last_hash(&path);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map(|file| AuditTrail {
+                sink: Sink::File(file),
+                last_hash,
+            })
+    };
+
+    match opened {
+        Ok(trail) => {
+            tracing::info!(target, "Audit log enabled");
+            Some(Mutex::new(trail))
+        }
+        Err(e) => {
+            tracing::error!(target, error = %e, "Failed to open the audit log, tool calls are not audited");
+            None
+        }
+    }
+});
+
+/// Connect to the local syslog daemon.
+#[cfg(unix)]
+fn open_syslog() -> std::io::Result<Sink> {
+    let socket = std::os::unix::net::UnixDatagram::unbound()?;
+    socket.connect(SYSLOG_SOCKET)?;
+    Ok(Sink::Syslog(socket))
+}
+
+#[cfg(not(unix))]
+fn open_syslog() -> std::io::Result<Sink> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "syslog audit logs are only supported on Unix",
+    ))
+}
+
+/// Hash of the last record of an existing audit file, to continue its chain.
+fn last_hash(path: &PathBuf) -> String {
+    let Ok(file) = File::open(path) else {
+        return GENESIS_HASH.to_string();
+    };
+
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .last()
+        .and_then(|line| serde_json::from_str::<Value>(&line).ok())
+        .and_then(|record| record["hash"].as_str().map(str::to_string))
+        .unwrap_or_else(|| GENESIS_HASH.to_string())
+}
+
+pub fn enabled() -> bool {
+    TRAIL.is_some()
+}
+
+tokio::task_local! {
+    static LAGO_REQUEST_IDS: RefCell<Vec<String>>;
+}
+
+/// Run a tool call, collecting the ids of the Lago requests it makes.
+pub async fn scope<F: Future>(call: F) -> (F::Output, Vec<String>) {
+    LAGO_REQUEST_IDS
+        .scope(RefCell::new(Vec::new()), async {
+            let output = call.await;
+            let request_ids = LAGO_REQUEST_IDS.with(|ids| ids.take());
+            (output, request_ids)
+        })
+        .await
+}
+
+/// Note the id Lago gave to a request of the current tool call.
+pub fn record_lago_request_id(request_id: &str) {
+    let _ = LAGO_REQUEST_IDS.try_with(|ids| ids.borrow_mut().push(request_id.to_string()));
+}
+
+/// A tool call, as recorded in the audit trail.
+pub struct AuditEntry<'a> {
+    pub session_id: Option<&'a str>,
+    pub subject: Option<&'a str>,
+    pub tool_name: &'a str,
+    pub arguments: Option<Map<String, Value>>,
+    /// `success`, `error`, or `rejected` when a guard refused the call.
+    pub outcome: &'static str,
+    pub error: Option<String>,
+    pub duration: Duration,
+    pub lago_request_ids: Vec<String>,
+}
+
+/// Append a tool call to the audit trail.
+///
+/// Arguments and errors are redacted. Each record carries the hash of the previous one and
+/// its own hash, so that removed or edited records break the chain.
+pub fn record(entry: AuditEntry<'_>) {
+    let Some(trail) = TRAIL.as_ref() else {
+        return;
+    };
+
+    let mut arguments = entry.arguments.map(Value::Object).unwrap_or(Value::Null);
+    redaction::redact_value(&mut arguments);
+
+    let mut trail = trail.lock().unwrap_or_else(|e| e.into_inner());
+    let mut record = serde_json::json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "session_id": entry.session_id,
+        "subject": entry.subject,
+        "tool": entry.tool_name,
+        "arguments": arguments,
+        "outcome": entry.outcome,
+        "error": entry.error.as_deref().map(redaction::redact_text),
+        "duration_ms": entry.duration.as_millis() as u64,
+        "lago_request_ids": entry.lago_request_ids,
+        "prev_hash": trail.last_hash,
+    });
+    let hash = format!("{:x}", Sha256::digest(record.to_string().as_bytes()));
+    record["hash"] = Value::String(hash.clone());
+
+    let line = record.to_string();
+    let result = match &mut trail.sink {
+        Sink::File(file) => writeln!(file, "{line}").and_then(|_| file.flush()),
+        #[cfg(unix)]
+        Sink::Syslog(socket) => socket
+            .send(format!("<{SYSLOG_PRIORITY}>lago-mcp-server: {line}").as_bytes())
+            .map(|_| ()),
+    };
+
+    match result {
+        Ok(()) => trail.last_hash = hash,
+        Err(e) => {
+            tracing::error!(tool = entry.tool_name, error = %e, "Failed to write audit record")
+        }
+    }
+}
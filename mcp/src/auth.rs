@@ -0,0 +1,352 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Request, State},
+    http::{StatusCode, header::AUTHORIZATION, header::WWW_AUTHENTICATE},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation, decode, decode_header,
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet},
+};
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+use lago_mcp_server::principal::Principal;
+
+/// Minimum delay between two JWKS refreshes triggered by unknown key ids.
+const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// OpenID Connect settings. JWT validation is enabled when an issuer is configured.
+#[derive(Clone, Debug, clap::Args)]
+pub struct OidcArgs {
+    /// Issuer of the accepted access tokens (enables JWT authentication on /mcp)
+    #[arg(long, env = "LAGO_MCP_OIDC_ISSUER")]
+    pub oidc_issuer: Option<String>,
+    /// Expected audience of the access tokens
+    #[arg(long, env = "LAGO_MCP_OIDC_AUDIENCE")]
+    pub oidc_audience: Option<String>,
+    /// JWKS URL; discovered from the issuer's OpenID configuration when not set
+    #[arg(long, env = "LAGO_MCP_OIDC_JWKS_URL")]
+    pub oidc_jwks_url: Option<String>,
+    /// Claim holding the tenant of the caller
+    #[arg(long, env = "LAGO_MCP_OIDC_TENANT_CLAIM", default_value = "tenant")]
+    pub oidc_tenant_claim: String,
+    /// Claim holding the roles of the caller (array or space-separated string)
+    #[arg(long, env = "LAGO_MCP_OIDC_ROLES_CLAIM", default_value = "roles")]
+    pub oidc_roles_claim: String,
+    /// Role required to use tools that modify Lago data
+    #[arg(long, env = "LAGO_MCP_OIDC_WRITE_ROLE", default_value = "lago:write")]
+    pub oidc_write_role: String,
+    /// JSON object mapping tenants to their Lago API key, e.g. {"acme": "lago_key"}
+    #[arg(long, env = "LAGO_MCP_TENANT_API_KEYS")]
+    pub tenant_api_keys: Option<String>,
+}
+
+/// Static bearer tokens accepted on the `/mcp` route, for deployments without an identity
+/// provider.
+#[derive(Clone, Debug, clap::Args)]
+pub struct BearerTokenArgs {
+    /// Bearer tokens accepted on the MCP endpoint, comma-separated (enables authentication on /mcp)
+    #[arg(
+        long,
+        env = "LAGO_MCP_BEARER_TOKENS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    pub bearer_tokens: Vec<String>,
+}
+
+/// Build the authenticator of the `/mcp` route, or `None` when neither static tokens nor an
+/// OIDC issuer are configured.
+pub async fn authenticator(
+    tokens: &BearerTokenArgs,
+    oidc: &OidcArgs,
+) -> Result<Option<Arc<BearerAuthenticator>>> {
+    let tokens: Vec<String> = tokens
+        .bearer_tokens
+        .iter()
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+        .collect();
+    let jwt = oidc.authenticator().await?;
+
+    if tokens.is_empty() && jwt.is_none() {
+        return Ok(None);
+    }
+    if !tokens.is_empty() {
+        tracing::info!(tokens = tokens.len(), "Bearer token authentication enabled");
+    }
+
+    Ok(Some(Arc::new(BearerAuthenticator { tokens, jwt })))
+}
+
+/// Accepts the configured static tokens and, when an issuer is set, valid JWTs.
+pub struct BearerAuthenticator {
+    tokens: Vec<String>,
+    jwt: Option<JwtAuthenticator>,
+}
+
+impl BearerAuthenticator {
+    async fn authenticate(&self, token: &str) -> Result<Principal, String> {
+        if let Some(index) = self
+            .tokens
+            .iter()
+            .position(|known| constant_time_eq(known.as_bytes(), token.as_bytes()))
+        {
+            return Ok(Principal {
+                subject: format!("bearer_token:{}", index + 1),
+                tenant: None,
+                roles: Vec::new(),
+                api_key: None,
+                can_write: true,
+            });
+        }
+
+        match &self.jwt {
+            Some(jwt) => jwt.authenticate(token).await,
+            None => Err("invalid bearer token".to_string()),
+        }
+    }
+}
+
+/// Compare two secrets in a time that doesn't depend on where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+impl OidcArgs {
+    /// Build the JWT authenticator, or `None` when no issuer is configured.
+    async fn authenticator(&self) -> Result<Option<JwtAuthenticator>> {
+        let Some(issuer) = &self.oidc_issuer else {
+            return Ok(None);
+        };
+
+        let http_client = reqwest::Client::new();
+        let jwks_url = match &self.oidc_jwks_url {
+            Some(jwks_url) => jwks_url.clone(),
+            None => discover_jwks_url(&http_client, issuer).await?,
+        };
+        let tenant_api_keys = match &self.tenant_api_keys {
+            Some(json) => Some(
+                serde_json::from_str::<HashMap<String, String>>(json)
+                    .context("LAGO_MCP_TENANT_API_KEYS must be a JSON object of strings")?,
+            ),
+            None => None,
+        };
+
+        let jwks = fetch_jwks(&http_client, &jwks_url).await?;
+        tracing::info!(%issuer, %jwks_url, "JWT authentication enabled");
+
+        Ok(Some(JwtAuthenticator {
+            issuer: issuer.clone(),
+            audience: self.oidc_audience.clone(),
+            jwks_url,
+            jwks: RwLock::new((jwks, Instant::now())),
+            http_client,
+            tenant_claim: self.oidc_tenant_claim.clone(),
+            roles_claim: self.oidc_roles_claim.clone(),
+            write_role: self.oidc_write_role.clone(),
+            tenant_api_keys,
+        }))
+    }
+}
+
+struct JwtAuthenticator {
+    issuer: String,
+    audience: Option<String>,
+    jwks_url: String,
+    jwks: RwLock<(JwkSet, Instant)>,
+    http_client: reqwest::Client,
+    tenant_claim: String,
+    roles_claim: String,
+    write_role: String,
+    tenant_api_keys: Option<HashMap<String, String>>,
+}
+
+impl JwtAuthenticator {
+    async fn authenticate(&self, token: &str) -> Result<Principal, String> {
+        let header = decode_header(token).map_err(|e| format!("invalid token header: {e}"))?;
+        let key_id = header.kid.ok_or("token has no key id")?;
+        let (decoding_key, algorithm) = self.decoding_key(&key_id).await?;
+
+        // The algorithm comes from the trusted key, never from the token.
+        if header.alg != algorithm {
+            return Err(format!(
+                "token algorithm {:?} does not match key algorithm {algorithm:?}",
+                header.alg
+            ));
+        }
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let claims = decode::<Value>(token, &decoding_key, &validation)
+            .map_err(|e| format!("invalid token: {e}"))?
+            .claims;
+
+        let subject = claims["sub"].as_str().unwrap_or_default().to_string();
+        let tenant = claims[&self.tenant_claim].as_str().map(str::to_string);
+        let roles: Vec<String> = match &claims[&self.roles_claim] {
+            Value::Array(roles) => roles
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            Value::String(roles) => roles.split_whitespace().map(str::to_string).collect(),
+            _ => Vec::new(),
+        };
+
+        let api_key = match &self.tenant_api_keys {
+            Some(tenant_api_keys) => {
+                let tenant = tenant.as_deref().ok_or("token has no tenant claim")?;
+                let api_key = tenant_api_keys
+                    .get(tenant)
+                    .ok_or_else(|| format!("no Lago credentials for tenant {tenant}"))?;
+                Some(api_key.clone())
+            }
+            None => None,
+        };
+
+        Ok(Principal {
+            subject,
+            tenant,
+            can_write: roles.contains(&self.write_role),
+            roles,
+            api_key,
+        })
+    }
+
+    /// Find the key for `key_id` and its algorithm, refreshing the JWKS once when the key is
+    /// unknown.
+    async fn decoding_key(&self, key_id: &str) -> Result<(DecodingKey, Algorithm), String> {
+        {
+            let jwks = self.jwks.read().await;
+            if let Some(jwk) = jwks.0.find(key_id) {
+                return key_and_algorithm(jwk);
+            }
+            if jwks.1.elapsed() < JWKS_REFRESH_INTERVAL {
+                return Err(format!("unknown key id {key_id}"));
+            }
+        }
+
+        let refreshed = fetch_jwks(&self.http_client, &self.jwks_url)
+            .await
+            .map_err(|e| format!("failed to refresh JWKS: {e}"))?;
+        let mut jwks = self.jwks.write().await;
+        *jwks = (refreshed, Instant::now());
+
+        let jwk = jwks
+            .0
+            .find(key_id)
+            .ok_or_else(|| format!("unknown key id {key_id}"))?;
+        key_and_algorithm(jwk)
+    }
+}
+
+/// The decoding key of `jwk` and the algorithm it signs with: its `alg` when set, otherwise the
+/// one implied by its key type.
+fn key_and_algorithm(jwk: &Jwk) -> Result<(DecodingKey, Algorithm), String> {
+    let algorithm = match (jwk.common.key_algorithm, &jwk.algorithm) {
+        (Some(key_algorithm), _) => key_algorithm
+            .to_string()
+            .parse()
+            .map_err(|_| format!("unsupported key algorithm {key_algorithm}"))?,
+        (None, AlgorithmParameters::RSA(_)) => Algorithm::RS256,
+        (None, AlgorithmParameters::EllipticCurve(params)) => match params.curve {
+            EllipticCurve::P256 => Algorithm::ES256,
+            EllipticCurve::P384 => Algorithm::ES384,
+            _ => return Err("unsupported elliptic curve".to_string()),
+        },
+        (None, AlgorithmParameters::OctetKeyPair(_)) => Algorithm::EdDSA,
+        (None, AlgorithmParameters::OctetKey(_)) => {
+            return Err("key has no algorithm".to_string());
+        }
+    };
+    let decoding_key = DecodingKey::from_jwk(jwk).map_err(|e| format!("invalid key: {e}"))?;
+    Ok((decoding_key, algorithm))
+}
+
+/// Reject requests without a valid bearer token and attach the caller's [`Principal`].
+pub async fn require_bearer(
+    State(authenticator): State<Arc<BearerAuthenticator>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    let Some(token) = token else {
+        return unauthorized("missing bearer token");
+    };
+
+    match authenticator.authenticate(&token).await {
+        Ok(principal) => {
+            tracing::debug!(subject = %principal.subject, tenant = ?principal.tenant, "Authenticated request");
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        Err(reason) => {
+            tracing::warn!(%reason, "Rejected request with invalid access token");
+            unauthorized(&reason)
+        }
+    }
+}
+
+fn unauthorized(reason: &str) -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(WWW_AUTHENTICATE, "Bearer")],
+        format!("Unauthorized: {reason}"),
+    )
+        .into_response()
+}
+
+async fn discover_jwks_url(http_client: &reqwest::Client, issuer: &str) -> Result<String> {
+    let discovery_url = format!(
+        "{}/.well-known/openid-configuration",
+        issuer.trim_end_matches('/')
+    );
+    let configuration: Value = http_client
+        .get(&discovery_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    configuration["jwks_uri"]
+        .as_str()
+        .map(str::to_string)
+        .with_context(|| format!("{discovery_url} has no jwks_uri"))
+}
+
+async fn fetch_jwks(http_client: &reqwest::Client, jwks_url: &str) -> Result<JwkSet> {
+    let jwks = http_client
+        .get(jwks_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("{jwks_url} did not return a valid JWKS"))?;
+
+    Ok(jwks)
+}
@@ -0,0 +1,107 @@
+use std::{
+    cell::Cell,
+    future::Future,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+use crate::settings;
+
+/// Retries of one request rate limited by Lago before giving up.
+const MAX_RETRIES: u32 = 3;
+
+/// Longest wait before retrying when `LAGO_MCP_RATE_LIMIT_MAX_WAIT_SECS` is unset.
+const DEFAULT_MAX_WAIT_SECS: u64 = 10;
+
+/// Longest wait before retrying a request rate limited by Lago, from
+/// `LAGO_MCP_RATE_LIMIT_MAX_WAIT_SECS`. `0` never waits.
+static MAX_WAIT: LazyLock<Duration> = LazyLock::new(|| {
+    let Some(value) = settings::var("LAGO_MCP_RATE_LIMIT_MAX_WAIT_SECS") else {
+        return Duration::from_secs(DEFAULT_MAX_WAIT_SECS);
+    };
+    let secs = value.trim().parse().unwrap_or_else(|_| {
+        tracing::warn!(value, "Ignoring invalid LAGO_MCP_RATE_LIMIT_MAX_WAIT_SECS");
+        DEFAULT_MAX_WAIT_SECS
+    });
+    Duration::from_secs(secs)
+});
+
+/// Rate limiting of the current tool call by Lago, when it gave up retrying.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimited {
+    /// Delay Lago asked for, when it sent one.
+    pub retry_after: Option<Duration>,
+}
+
+tokio::task_local! {
+    static CALL_DEADLINE: Instant;
+    static RATE_LIMITED: Cell<Option<RateLimited>>;
+}
+
+/// Run a tool call that must finish by `deadline`, noting whether Lago rate limited it.
+pub async fn scope<F: Future>(deadline: Instant, call: F) -> (F::Output, Option<RateLimited>) {
+    CALL_DEADLINE
+        .scope(
+            deadline,
+            RATE_LIMITED.scope(Cell::new(None), async {
+                let output = call.await;
+                (output, RATE_LIMITED.with(Cell::take))
+            }),
+        )
+        .await
+}
+
+/// Delay asked for by a `Retry-After` header, in seconds or as an HTTP date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Wait before retrying a request Lago answered with `429 Too Many Requests`.
+///
+/// Returns false, without waiting, when the request was retried enough, when Lago asks for a
+/// longer wait than allowed, or when retrying would outlast the tool call's timeout. The
+/// call is then noted as rate limited.
+pub async fn wait_to_retry(retry_after: Option<Duration>, retries: u32) -> bool {
+    let delay = retry_after.unwrap_or(Duration::from_secs(1 << retries));
+    let within_deadline = CALL_DEADLINE
+        .try_with(|deadline| Instant::now() + delay < *deadline)
+        .unwrap_or(true);
+
+    if retries >= MAX_RETRIES || delay > *MAX_WAIT || !within_deadline {
+        let _ = RATE_LIMITED.try_with(|rate_limited| {
+            rate_limited.set(Some(RateLimited { retry_after }));
+        });
+        return false;
+    }
+
+    tracing::warn!(
+        delay_secs = delay.as_secs_f64(),
+        retries,
+        "Rate limited by Lago, retrying"
+    );
+    tokio::time::sleep(delay).await;
+    true
+}
+
+/// JSON error returned for a call that failed because Lago rate limited it.
+pub fn rate_limited_error(message: &str, rate_limited: RateLimited) -> String {
+    let error = serde_json::json!({
+        "error": "rate_limited",
+        "message": message,
+        "retry_after_secs": rate_limited.retry_after.map(|delay| delay.as_secs()),
+    });
+    serde_json::to_string_pretty(&error).unwrap_or_default()
+}
@@ -0,0 +1,60 @@
+use std::sync::{Arc, RwLock};
+
+use rmcp::{
+    Peer, RoleServer,
+    model::{LoggingLevel, LoggingMessageNotificationParam},
+};
+use serde_json::Value;
+
+/// Name of the logger in the messages sent to the client.
+const LOGGER: &str = "lago-mcp-server";
+
+fn severity(level: LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
+    }
+}
+
+/// Server diagnostics forwarded to the client of a session as MCP log notifications.
+///
+/// Nothing is sent until the client picks a minimum level with `logging/setLevel`.
+#[derive(Clone, Default)]
+pub struct ClientLogger {
+    level: Arc<RwLock<Option<LoggingLevel>>>,
+}
+
+impl ClientLogger {
+    pub fn set_level(&self, level: LoggingLevel) {
+        *self.level.write().unwrap_or_else(|e| e.into_inner()) = Some(level);
+    }
+
+    fn enabled(&self, level: LoggingLevel) -> bool {
+        self.level
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|minimum| severity(level) >= severity(minimum))
+    }
+
+    /// Send `data` to the client if `level` is at or above the level it asked for.
+    pub async fn log(&self, peer: &Peer<RoleServer>, level: LoggingLevel, data: Value) {
+        if !self.enabled(level) {
+            return;
+        }
+
+        let notification = LoggingMessageNotificationParam {
+            level,
+            logger: Some(LOGGER.to_string()),
+            data,
+        };
+        if let Err(e) = peer.notify_logging_message(notification).await {
+            tracing::debug!(error = %e, "Failed to send log message to the client");
+        }
+    }
+}
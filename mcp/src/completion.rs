@@ -0,0 +1,185 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use rmcp::{RoleServer, model::CompletionInfo, service::RequestContext};
+
+use crate::stats;
+use crate::tools::{FETCH_ALL_MAX_RECORDS, LagoApiConfig, get_lago_api_config, lago_get_all};
+
+/// How long a fetched list of values is served before being fetched again.
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Most values returned for one completion request, as allowed by MCP.
+const MAX_VALUES: usize = 100;
+
+/// A Lago list whose values complete an argument.
+struct CompletionSource {
+    /// Lago list endpoint.
+    path: &'static str,
+    /// Key of the items in the endpoint's response.
+    key: &'static str,
+    /// Field of the items holding the values.
+    field: &'static str,
+}
+
+/// Completable arguments, by name.
+const SOURCES: &[(&str, CompletionSource)] = &[
+    (
+        "plan_code",
+        CompletionSource {
+            path: "/plans",
+            key: "plans",
+            field: "code",
+        },
+    ),
+    (
+        "billable_metric_code",
+        CompletionSource {
+            path: "/billable_metrics",
+            key: "billable_metrics",
+            field: "code",
+        },
+    ),
+    (
+        "coupon_code",
+        CompletionSource {
+            path: "/coupons",
+            key: "coupons",
+            field: "code",
+        },
+    ),
+    (
+        "add_on_code",
+        CompletionSource {
+            path: "/add_ons",
+            key: "add_ons",
+            field: "code",
+        },
+    ),
+    (
+        "external_customer_id",
+        CompletionSource {
+            path: "/customers",
+            key: "customers",
+            field: "external_id",
+        },
+    ),
+    (
+        "customer_external_id",
+        CompletionSource {
+            path: "/customers",
+            key: "customers",
+            field: "external_id",
+        },
+    ),
+];
+
+/// Values fetched per (Lago API URL, API key, endpoint), shared by every session.
+type CacheKey = (String, String, &'static str);
+/// When the values were fetched, and the values.
+type CacheEntry = (Instant, Vec<String>);
+
+static CACHE: LazyLock<Mutex<HashMap<CacheKey, CacheEntry>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Argument completion for Lago codes and IDs, served from a cached catalog.
+#[derive(Clone)]
+pub struct Completions {
+    http_client: reqwest::Client,
+}
+
+impl Completions {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Values of `argument` starting with `prefix` (case-insensitively). Arguments without
+    /// a known source complete to nothing.
+    pub async fn complete(
+        &self,
+        argument: &str,
+        prefix: &str,
+        context: &RequestContext<RoleServer>,
+    ) -> CompletionInfo {
+        let Some((_, source)) = SOURCES.iter().find(|(name, _)| *name == argument) else {
+            return completion_info(Vec::new());
+        };
+        let config = match get_lago_api_config(context).await {
+            Ok(config) => config,
+            Err(_) => return completion_info(Vec::new()),
+        };
+
+        let prefix = prefix.to_lowercase();
+        let values = self
+            .values(&config, source)
+            .await
+            .into_iter()
+            .filter(|value| value.to_lowercase().starts_with(&prefix))
+            .collect();
+
+        completion_info(values)
+    }
+
+    async fn values(&self, config: &LagoApiConfig, source: &CompletionSource) -> Vec<String> {
+        let cache_key = (config.base_url.clone(), config.api_key.clone(), source.path);
+        {
+            let cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((_, values)) = cache
+                .get(&cache_key)
+                .filter(|(fetched_at, _)| fetched_at.elapsed() < CACHE_TTL)
+            {
+                stats::record_cache_lookup("completion", true);
+                return values.clone();
+            }
+        }
+        stats::record_cache_lookup("completion", false);
+
+        let items = match lago_get_all(
+            &self.http_client,
+            config,
+            source.path,
+            &[],
+            source.key,
+            FETCH_ALL_MAX_RECORDS,
+        )
+        .await
+        {
+            Ok((items, _)) => items,
+            Err(e) => {
+                tracing::warn!(path = source.path, error = %e, "Failed to fetch completion values");
+                return Vec::new();
+            }
+        };
+
+        let mut values: Vec<String> = items
+            .iter()
+            .filter_map(|item| item[source.field].as_str())
+            .map(str::to_string)
+            .collect();
+        values.sort();
+        values.dedup();
+
+        CACHE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(cache_key, (Instant::now(), values.clone()));
+
+        values
+    }
+}
+
+fn completion_info(mut values: Vec<String>) -> CompletionInfo {
+    let total = values.len();
+    values.truncate(MAX_VALUES);
+
+    CompletionInfo {
+        has_more: Some(total > values.len()),
+        total: Some(total as u32),
+        values,
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::Command;
+use toml::{Table, Value};
+
+/// Settings that can be read from the configuration file, as (section.key, environment variable).
+///
+/// The file only provides defaults: a variable already set in the environment, or the matching
+/// command-line flag, takes precedence over it.
+const SETTINGS: &[(&str, &str)] = &[
+    ("lago.api_key", "LAGO_API_KEY"),
+    ("lago.api_url", "LAGO_API_URL"),
+    ("lago.api_version", "LAGO_API_VERSION"),
+    ("lago.extra_headers", "LAGO_MCP_EXTRA_HEADERS"),
+    (
+        "lago.rate_limit_max_wait_secs",
+        "LAGO_MCP_RATE_LIMIT_MAX_WAIT_SECS",
+    ),
+    (
+        "lago.max_concurrent_requests",
+        "LAGO_MCP_MAX_CONCURRENT_REQUESTS",
+    ),
+    ("http.host", "LAGO_MCP_HOST"),
+    ("http.port", "LAGO_MCP_PORT"),
+    ("http.stateless", "LAGO_MCP_STATELESS"),
+    ("http.paths", "LAGO_MCP_PATHS"),
+    ("http.health_path", "LAGO_MCP_HEALTH_PATH"),
+    ("http.session_store", "LAGO_MCP_SESSION_STORE"),
+    ("http.redis_url", "REDIS_URL"),
+    ("http.session_ttl_secs", "LAGO_MCP_SESSION_TTL_SECS"),
+    ("http.max_body_bytes", "LAGO_MCP_MAX_BODY_BYTES"),
+    ("http.max_json_depth", "LAGO_MCP_MAX_JSON_DEPTH"),
+    ("http.allowed_ips", "LAGO_MCP_ALLOWED_IPS"),
+    ("http.trust_forwarded_for", "LAGO_MCP_TRUST_FORWARDED_FOR"),
+    ("http.rate_limit_per_ip", "LAGO_MCP_RATE_LIMIT_PER_IP"),
+    (
+        "http.rate_limit_per_api_key",
+        "LAGO_MCP_RATE_LIMIT_PER_API_KEY",
+    ),
+    ("auth.bearer_tokens", "LAGO_MCP_BEARER_TOKENS"),
+    ("cors.allowed_origins", "LAGO_MCP_CORS_ALLOWED_ORIGINS"),
+    ("cors.allowed_headers", "LAGO_MCP_CORS_ALLOWED_HEADERS"),
+    ("cors.allowed_methods", "LAGO_MCP_CORS_ALLOWED_METHODS"),
+    ("oidc.issuer", "LAGO_MCP_OIDC_ISSUER"),
+    ("oidc.audience", "LAGO_MCP_OIDC_AUDIENCE"),
+    ("oidc.jwks_url", "LAGO_MCP_OIDC_JWKS_URL"),
+    ("oidc.tenant_claim", "LAGO_MCP_OIDC_TENANT_CLAIM"),
+    ("oidc.roles_claim", "LAGO_MCP_OIDC_ROLES_CLAIM"),
+    ("oidc.write_role", "LAGO_MCP_OIDC_WRITE_ROLE"),
+    ("oidc.tenant_api_keys", "LAGO_MCP_TENANT_API_KEYS"),
+    ("tools.features", "LAGO_MCP_FEATURES"),
+    ("sandbox.enabled", "LAGO_MCP_SANDBOX"),
+    ("sandbox.fixtures", "LAGO_MCP_SANDBOX_FIXTURES"),
+    ("sandbox.record", "LAGO_MCP_RECORD"),
+    ("sandbox.replay", "LAGO_MCP_REPLAY"),
+    ("tools.display_timezone", "LAGO_MCP_DISPLAY_TIMEZONE"),
+    ("tools.timeout_secs", "LAGO_MCP_TOOL_TIMEOUT_SECS"),
+    ("tools.timeouts", "LAGO_MCP_TOOL_TIMEOUTS"),
+    ("quota.calls_per_hour", "LAGO_MCP_QUOTA_CALLS_PER_HOUR"),
+    ("quota.calls_per_day", "LAGO_MCP_QUOTA_CALLS_PER_DAY"),
+    (
+        "quota.write_calls_per_hour",
+        "LAGO_MCP_QUOTA_WRITE_CALLS_PER_HOUR",
+    ),
+    (
+        "quota.write_calls_per_day",
+        "LAGO_MCP_QUOTA_WRITE_CALLS_PER_DAY",
+    ),
+    ("event_queue.batch_size", "LAGO_MCP_EVENT_BATCH_SIZE"),
+    (
+        "event_queue.flush_interval_secs",
+        "LAGO_MCP_EVENT_FLUSH_INTERVAL_SECS",
+    ),
+    ("event_queue.max_queued", "LAGO_MCP_EVENT_QUEUE_MAX"),
+    (
+        "event_queue.dedup_capacity",
+        "LAGO_MCP_EVENT_DEDUP_CAPACITY",
+    ),
+    ("spending.caps", "LAGO_MCP_SPENDING_CAPS"),
+    ("spending.state_path", "LAGO_MCP_SPENDING_STATE_PATH"),
+    (
+        "spending.confirm_above_cents",
+        "LAGO_MCP_CONFIRM_ABOVE_CENTS",
+    ),
+    ("logging.level", "RUST_LOG"),
+    ("logging.audit_log", "LAGO_MCP_AUDIT_LOG"),
+    ("logging.debug_sample_rate", "LAGO_MCP_DEBUG_SAMPLE_RATE"),
+];
+
+/// A loaded configuration file.
+pub struct LoadedConfig {
+    pub path: PathBuf,
+    /// Values of the file, by environment variable.
+    pub values: HashMap<&'static str, String>,
+    /// Settings taken from the file, by name.
+    pub applied: Vec<&'static str>,
+    /// Keys of the file that are not known settings.
+    pub unknown_keys: Vec<String>,
+}
+
+/// Path of the configuration file, from `--config` or `LAGO_MCP_CONFIG`.
+///
+/// Read ahead of the command line, whose flags take their defaults from the file.
+pub fn path() -> Option<PathBuf> {
+    let mut args = env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--config=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    env::var_os("LAGO_MCP_CONFIG").map(PathBuf::from)
+}
+
+/// Load a configuration file.
+pub fn load(path: &Path) -> Result<LoadedConfig> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read configuration file {}", path.display()))?;
+    let table: Table = contents
+        .parse()
+        .with_context(|| format!("Invalid configuration file {}", path.display()))?;
+
+    let mut loaded = LoadedConfig {
+        path: path.to_path_buf(),
+        values: HashMap::new(),
+        applied: Vec::new(),
+        unknown_keys: Vec::new(),
+    };
+
+    for (key, value) in flatten(&table) {
+        let Some((name, variable)) = SETTINGS.iter().find(|(name, _)| *name == key) else {
+            loaded.unknown_keys.push(key);
+            continue;
+        };
+
+        let value = env_value(value)
+            .with_context(|| format!("Invalid value for {key} in {}", path.display()))?;
+        loaded.values.insert(variable, value);
+        if env::var_os(variable).is_none() {
+            loaded.applied.push(name);
+        }
+    }
+
+    Ok(loaded)
+}
+
+/// Flatten the file into `section.key` pairs. Tables nested deeper, such as
+/// `oidc.tenant_api_keys`, are kept whole.
+fn flatten(table: &Table) -> Vec<(String, &Value)> {
+    let mut settings = Vec::new();
+
+    for (section, value) in table {
+        match value {
+            Value::Table(keys) => {
+                for (key, value) in keys {
+                    settings.push((format!("{section}.{key}"), value));
+                }
+            }
+            value => settings.push((section.clone(), value)),
+        }
+    }
+
+    settings
+}
+
+/// Render a setting the way its environment variable expects it: arrays are comma-separated
+/// lists and tables are JSON objects.
+fn env_value(value: &Value) -> Result<String> {
+    Ok(match value {
+        Value::String(string) => string.clone(),
+        Value::Integer(integer) => integer.to_string(),
+        Value::Float(float) => float.to_string(),
+        Value::Boolean(boolean) => boolean.to_string(),
+        Value::Datetime(datetime) => datetime.to_string(),
+        Value::Array(items) => items
+            .iter()
+            .map(env_value)
+            .collect::<Result<Vec<_>>>()?
+            .join(","),
+        Value::Table(table) => serde_json::to_string(table)?,
+    })
+}
+
+impl LoadedConfig {
+    /// Use the file's values as the defaults of the flags reading the same environment
+    /// variables, so that flags and the environment still take precedence.
+    pub fn apply_defaults(&self, mut command: Command) -> Command {
+        let defaults: Vec<(String, String)> = command
+            .get_arguments()
+            .filter_map(|arg| {
+                let value = self.values.get(arg.get_env()?.to_str()?)?;
+                Some((arg.get_id().to_string(), value.clone()))
+            })
+            .collect();
+        for (id, value) in defaults {
+            command = command.mut_arg(id, |arg| arg.default_value(value));
+        }
+
+        let subcommands: Vec<String> = command
+            .get_subcommands()
+            .map(|subcommand| subcommand.get_name().to_string())
+            .collect();
+        for name in subcommands {
+            command = command.mut_subcommand(name, |subcommand| self.apply_defaults(subcommand));
+        }
+
+        command
+    }
+
+    /// Report what was taken from the file, once logging is set up.
+    pub fn log(&self) {
+        tracing::info!(
+            path = %self.path.display(),
+            settings = ?self.applied,
+            "Loaded configuration file"
+        );
+        for key in &self.unknown_keys {
+            tracing::warn!(path = %self.path.display(), key, "Ignoring unknown configuration key");
+        }
+    }
+}
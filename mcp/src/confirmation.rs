@@ -0,0 +1,79 @@
+use std::sync::LazyLock;
+
+use rmcp::{Peer, RoleServer};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::settings;
+use crate::spending;
+
+/// Amount in cents above which a money-moving call needs the user's confirmation, from
+/// `LAGO_MCP_CONFIRM_ABOVE_CENTS`. Calls are never confirmed when unset.
+static CONFIRM_ABOVE_CENTS: LazyLock<Option<i64>> = LazyLock::new(|| {
+    let value = settings::var("LAGO_MCP_CONFIRM_ABOVE_CENTS")?;
+    let parsed = value.trim().parse().ok();
+    if parsed.is_none() {
+        tracing::warn!(value, "Ignoring invalid LAGO_MCP_CONFIRM_ABOVE_CENTS");
+    }
+    parsed
+});
+
+pub fn enabled() -> bool {
+    CONFIRM_ABOVE_CENTS.is_some()
+}
+
+/// Answer of the user to a confirmation request.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct WriteConfirmation {
+    /// Run the call as shown.
+    pub confirm: bool,
+}
+
+rmcp::elicit_safe!(WriteConfirmation);
+
+/// Ask the user, through MCP elicitation, to confirm a call moving more than the threshold.
+///
+/// Fails with guidance when the user declines, or when the client can't ask them.
+pub async fn confirm(
+    peer: &Peer<RoleServer>,
+    tool_name: &str,
+    arguments: Option<&Map<String, Value>>,
+    credit_cents: Option<f64>,
+) -> Result<(), String> {
+    let Some(threshold) = *CONFIRM_ABOVE_CENTS else {
+        return Ok(());
+    };
+    let amount =
+        match spending::amount_cents(tool_name, arguments.unwrap_or(&Map::new()), credit_cents) {
+            Some(Ok(amount)) if amount > threshold => amount,
+            Some(Err(reason)) => return Err(reason),
+            _ => return Ok(()),
+        };
+
+    let supports_elicitation = peer
+        .peer_info()
+        .is_some_and(|info| info.capabilities.elicitation.is_some());
+    if !supports_elicitation {
+        return Err(format!(
+            "{tool_name} moves {amount} cents, above the {threshold} cents that require the user's confirmation, and this client can't ask for it. Ask the user to run it from a client supporting MCP elicitation or to lower the amount."
+        ));
+    }
+
+    let payload = serde_json::to_string_pretty(&arguments).unwrap_or_default();
+    let message = format!(
+        "{tool_name} is about to send {amount} cents to Lago (confirmation required above {threshold} cents):\n{payload}"
+    );
+
+    match peer.elicit::<WriteConfirmation>(message).await {
+        Ok(Some(WriteConfirmation { confirm: true })) => {
+            tracing::info!(tool_name, amount, "Write confirmed by the user");
+            Ok(())
+        }
+        Ok(_) => Err(format!(
+            "The user did not confirm {tool_name}: the call was not made"
+        )),
+        Err(e) => Err(format!(
+            "The user did not confirm {tool_name} ({e}): the call was not made"
+        )),
+    }
+}
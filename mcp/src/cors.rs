@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// CORS settings of the `/mcp` route. Cross-origin requests are denied unless origins are set.
+#[derive(Clone, Debug, clap::Args)]
+pub struct CorsArgs {
+    /// Origins allowed to call the MCP endpoint from a browser, or "*" for any origin
+    #[arg(long, env = "LAGO_MCP_CORS_ALLOWED_ORIGINS", value_delimiter = ',')]
+    pub cors_allowed_origins: Vec<String>,
+    /// Request headers allowed in cross-origin requests
+    #[arg(
+        long,
+        env = "LAGO_MCP_CORS_ALLOWED_HEADERS",
+        value_delimiter = ',',
+        default_value = "content-type,accept,authorization,last-event-id,mcp-session-id,mcp-protocol-version,x-lago-api-key"
+    )]
+    pub cors_allowed_headers: Vec<String>,
+    /// Methods allowed in cross-origin requests
+    #[arg(
+        long,
+        env = "LAGO_MCP_CORS_ALLOWED_METHODS",
+        value_delimiter = ',',
+        default_value = "GET,POST,DELETE"
+    )]
+    pub cors_allowed_methods: Vec<String>,
+}
+
+impl CorsArgs {
+    /// Build the CORS layer, or `None` when no origin is allowed.
+    pub fn layer(&self) -> Result<Option<CorsLayer>> {
+        if self.cors_allowed_origins.is_empty() {
+            return Ok(None);
+        }
+
+        let allow_origin = if self.cors_allowed_origins.iter().any(|origin| origin == "*") {
+            AllowOrigin::any()
+        } else {
+            let origins = self
+                .cors_allowed_origins
+                .iter()
+                .map(|origin| {
+                    HeaderValue::from_str(origin.trim())
+                        .with_context(|| format!("Invalid CORS origin: {origin}"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            AllowOrigin::list(origins)
+        };
+
+        let headers = self
+            .cors_allowed_headers
+            .iter()
+            .map(|header| {
+                HeaderName::from_bytes(header.trim().as_bytes())
+                    .with_context(|| format!("Invalid CORS header: {header}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let methods = self
+            .cors_allowed_methods
+            .iter()
+            .map(|method| {
+                Method::from_bytes(method.trim().to_uppercase().as_bytes())
+                    .with_context(|| format!("Invalid CORS method: {method}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Some(
+            CorsLayer::new()
+                .allow_origin(allow_origin)
+                .allow_headers(headers)
+                .allow_methods(methods)
+                .expose_headers([HeaderName::from_static("mcp-session-id")]),
+        ))
+    }
+}
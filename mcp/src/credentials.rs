@@ -0,0 +1,80 @@
+use std::sync::{Arc, RwLock};
+
+use rmcp::model::InitializeRequestParam;
+use serde::{Deserialize, Serialize};
+
+use crate::redaction;
+use crate::sandbox;
+
+/// Key of the client's experimental capabilities that may carry Lago credentials, e.g.
+/// `{"lago": {"api_key": "...", "api_url": "https://api.getlago.com/api/v1"}}`.
+pub const EXPERIMENTAL_CAPABILITY: &str = "lago";
+
+/// Lago credentials set for one MCP session, used by its tool calls instead of the
+/// environment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCredentials {
+    pub api_key: String,
+    /// Lago API URL; the environment's (or the default region's) when unset.
+    pub api_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SetLagoCredentialsArgs {
+    /// Lago API key to use for the rest of the session. Leave empty to go back to the
+    /// server's configured key.
+    pub api_key: Option<String>,
+    /// Lago API URL for this key (e.g., "https://api.eu.getlago.com/api/v1"). Defaults to
+    /// the server's configured URL.
+    pub api_url: Option<String>,
+}
+
+/// Credentials of a session, shared by all its calls.
+#[derive(Clone, Default)]
+pub struct CredentialStore {
+    credentials: Arc<RwLock<Option<SessionCredentials>>>,
+}
+
+impl CredentialStore {
+    pub fn get(&self) -> Option<SessionCredentials> {
+        self.credentials
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set(&self, mut credentials: Option<SessionCredentials>) {
+        if let Some(credentials) = &mut credentials {
+            redaction::register_secret(&credentials.api_key);
+            // The sandbox must not be left for a real Lago instance.
+            if sandbox::enabled() {
+                credentials.api_url = None;
+            }
+        }
+        *self.credentials.write().unwrap_or_else(|e| e.into_inner()) = credentials;
+    }
+}
+
+/// Credentials passed by the client in its experimental capabilities when initializing.
+pub fn from_initialize(
+    request: &InitializeRequestParam,
+) -> Option<Result<SessionCredentials, String>> {
+    let capability = request
+        .capabilities
+        .experimental
+        .as_ref()?
+        .get(EXPERIMENTAL_CAPABILITY)?;
+
+    Some(
+        serde_json::from_value(serde_json::Value::Object(capability.clone()))
+            .map_err(|e| format!("Invalid {EXPERIMENTAL_CAPABILITY} experimental capability: {e}")),
+    )
+}
+
+/// Show enough of an API key to tell keys apart, without disclosing it.
+pub fn mask_api_key(api_key: &str) -> String {
+    let chars: Vec<char> = api_key.chars().collect();
+    let visible: String = chars[chars.len().saturating_sub(4)..].iter().collect();
+
+    format!("…{visible}")
+}
@@ -0,0 +1,122 @@
+use lago_mcp_server::{LagoHttpError, lago_api_config_from_env, lago_request, settings};
+
+struct Diagnostics {
+    failures: usize,
+}
+
+impl Diagnostics {
+    fn ok(&mut self, message: impl AsRef<str>) {
+        println!("[ok]   {}", message.as_ref());
+    }
+
+    fn warn(&mut self, message: impl AsRef<str>, hint: impl AsRef<str>) {
+        println!("[warn] {}", message.as_ref());
+        println!("       hint: {}", hint.as_ref());
+    }
+
+    fn fail(&mut self, message: impl AsRef<str>, hint: impl AsRef<str>) {
+        self.failures += 1;
+        println!("[fail] {}", message.as_ref());
+        println!("       hint: {}", hint.as_ref());
+    }
+}
+
+/// Check the server configuration and the connection to the Lago API.
+///
+/// Returns an error when at least one check failed, so the process exits with a non-zero code.
+pub async fn run() -> anyhow::Result<()> {
+    let mut diagnostics = Diagnostics { failures: 0 };
+
+    println!("Lago MCP server diagnostics");
+    println!();
+
+    match settings::var("LAGO_API_KEY") {
+        Some(api_key) if !api_key.trim().is_empty() => diagnostics.ok("LAGO_API_KEY is set"),
+        Some(_) => diagnostics.fail(
+            "LAGO_API_KEY is empty",
+            "set LAGO_API_KEY to an API key from Developers > API keys in the Lago app",
+        ),
+        None => diagnostics.fail(
+            "LAGO_API_KEY is not set",
+            "set LAGO_API_KEY, or make sure every HTTP client sends the X-LAGO-API-KEY header",
+        ),
+    }
+
+    match settings::var("LAGO_API_URL") {
+        Some(api_url) => diagnostics.ok(format!("LAGO_API_URL is set to {api_url}")),
+        None => diagnostics.warn(
+            "LAGO_API_URL is not set, the region endpoint is used",
+            "set LAGO_API_URL (e.g. https://api.getlago.com/api/v1) for self-hosted instances; it is required for X-LAGO-API-KEY header credentials",
+        ),
+    }
+
+    let config = match lago_api_config_from_env() {
+        Ok(config) => {
+            diagnostics.ok(format!("Lago API endpoint resolved to {}", config.base_url));
+            Some(config)
+        }
+        Err(e) => {
+            diagnostics.fail(
+                format!("Could not resolve the Lago API configuration: {e}"),
+                "check LAGO_API_KEY and LAGO_API_URL",
+            );
+            None
+        }
+    };
+
+    if let Some(config) = config {
+        let http_client = reqwest::Client::new();
+        let query = [("per_page", "1".to_string())];
+
+        match lago_request(
+            &http_client,
+            &config,
+            reqwest::Method::GET,
+            "/billable_metrics",
+            &query,
+            None,
+        )
+        .await
+        {
+            Ok(_) => {
+                diagnostics.ok("Lago API is reachable");
+                diagnostics.ok("API key is valid");
+            }
+            Err(LagoHttpError::Status { status, .. })
+                if status == reqwest::StatusCode::UNAUTHORIZED =>
+            {
+                diagnostics.ok("Lago API is reachable");
+                diagnostics.fail(
+                    "API key was rejected (HTTP 401)",
+                    "the key may be revoked or belong to another organization or environment",
+                );
+            }
+            Err(LagoHttpError::Status { status, body }) => {
+                diagnostics.ok("Lago API is reachable");
+                diagnostics.fail(
+                    format!("Unexpected response from the Lago API: HTTP {status}: {body}"),
+                    "check that LAGO_API_URL points to the API root and ends with /api/v1",
+                );
+            }
+            Err(LagoHttpError::InvalidJson(e)) => {
+                diagnostics.ok("Lago API is reachable");
+                diagnostics.fail(
+                    format!("Unexpected response from the Lago API: {e}"),
+                    "check that LAGO_API_URL points to the API root and ends with /api/v1",
+                );
+            }
+            Err(LagoHttpError::Transport(e)) => diagnostics.fail(
+                format!("Lago API is unreachable: {e}"),
+                "check LAGO_API_URL, DNS and outbound network access from this host",
+            ),
+        }
+    }
+
+    println!();
+    if diagnostics.failures > 0 {
+        anyhow::bail!("{} check(s) failed", diagnostics.failures);
+    }
+
+    println!("All checks passed");
+    Ok(())
+}
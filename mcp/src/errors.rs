@@ -0,0 +1,232 @@
+use std::{cell::RefCell, future::Future};
+
+use lago_types::error::LagoError;
+use rmcp::{ErrorData as McpError, model::CallToolResult};
+use serde_json::{Map, Value, json};
+
+use crate::tools::{error_result, is_write_tool};
+
+/// Kind of failure of a tool call, telling the agent whether and how to call again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Validation,
+    Auth,
+    NotFound,
+    RateLimited,
+    Network,
+    ServerError,
+    QuotaExceeded,
+    NotConfirmed,
+    Unsupported,
+    Error,
+}
+
+impl ErrorCode {
+    /// Code of an HTTP status returned by Lago.
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            400 | 409 | 422 => Self::Validation,
+            401 | 403 => Self::Auth,
+            404 => Self::NotFound,
+            429 => Self::RateLimited,
+            500.. => Self::ServerError,
+            _ => Self::Error,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Validation => "validation",
+            Self::Auth => "auth",
+            Self::NotFound => "not_found",
+            Self::RateLimited => "rate_limited",
+            Self::Network => "network",
+            Self::ServerError => "server_error",
+            Self::QuotaExceeded => "quota_exceeded",
+            Self::NotConfirmed => "not_confirmed",
+            Self::Unsupported => "unsupported",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Why the current tool call failed, noted where the failure happened.
+#[derive(Debug, Clone)]
+pub struct Failure {
+    code: ErrorCode,
+    /// Body of Lago's error response, when the failure is one.
+    lago_body: Option<String>,
+}
+
+tokio::task_local! {
+    static FAILURE: RefCell<Option<Failure>>;
+}
+
+/// Run a tool call, keeping the last failure noted during it.
+pub async fn scope<F: Future>(call: F) -> (F::Output, Option<Failure>) {
+    FAILURE
+        .scope(RefCell::new(None), async {
+            let output = call.await;
+            (output, FAILURE.with(RefCell::take))
+        })
+        .await
+}
+
+fn set(failure: Option<Failure>) {
+    let _ = FAILURE.try_with(|current| *current.borrow_mut() = failure);
+}
+
+/// Note a failure of the current tool call that didn't come from a Lago response.
+pub fn record(code: ErrorCode) {
+    set(Some(Failure {
+        code,
+        lago_body: None,
+    }));
+}
+
+/// Note a failed Lago response of the current tool call.
+pub fn record_lago_failure(status: u16, body: &str) {
+    set(Some(Failure {
+        code: ErrorCode::from_status(status),
+        lago_body: Some(body.to_string()),
+    }));
+}
+
+/// Forget the failures noted so far, before a request whose failure is noted from its
+/// error by [`record_lago_client_error`].
+pub fn forget() {
+    set(None);
+}
+
+/// Note the error of a `lago-client` request, unless the failure was already noted while
+/// sending it, e.g. Lago being unreachable behind the relay.
+pub fn record_lago_client_error(error: &LagoError) {
+    if FAILURE
+        .try_with(|current| current.borrow().is_some())
+        .unwrap_or(true)
+    {
+        return;
+    }
+    match error {
+        LagoError::Api { status, message } => record_lago_failure(*status, message),
+        LagoError::Unauthorized => record(ErrorCode::Auth),
+        LagoError::RateLimit => record(ErrorCode::RateLimited),
+        LagoError::Http(_) => record(ErrorCode::Network),
+        LagoError::Serialization(_) | LagoError::Configuration(_) => record(ErrorCode::Error),
+    }
+}
+
+/// Whether the same call may succeed when retried later, and what the agent should do.
+fn guidance(code: &str) -> (bool, &'static str) {
+    match code {
+        "validation" => (
+            false,
+            "Fix the arguments (see details for the fields Lago rejected) before calling again.",
+        ),
+        "auth" => (
+            false,
+            "The Lago credentials can't perform this call: ask the user to check the API key and its permissions.",
+        ),
+        "not_found" => (
+            false,
+            "Check the identifier, for instance with a list or find tool, before calling again.",
+        ),
+        "rate_limited" => (
+            true,
+            "Wait for retry_after_secs (or a few seconds) before retrying.",
+        ),
+        "network" => (true, "Lago could not be reached: retry shortly."),
+        "server_error" => (true, "Lago failed to handle the request: retry shortly."),
+        "timeout" => (
+            true,
+            "Retry, or narrow the request down. A write may have been applied: check before retrying it.",
+        ),
+        "not_confirmed" => (
+            false,
+            "The user did not approve the call: don't retry it without asking them.",
+        ),
+        "quota_exceeded" => (true, "Wait for the quota to reset before calling again."),
+        "spending_cap_exceeded" => (
+            false,
+            "The call would exceed a spending cap: ask the user before trying a smaller amount.",
+        ),
+        "unsupported" => (
+            false,
+            "The connected Lago instance is too old for this tool: use another tool.",
+        ),
+        _ => (
+            false,
+            "Read the message before deciding whether to call again.",
+        ),
+    }
+}
+
+/// The standard fields of a Lago error body (`status`, `error`, `code`, `error_details`).
+fn lago_details(body: &str) -> Value {
+    let Ok(Value::Object(body)) = serde_json::from_str::<Value>(body) else {
+        return Value::Null;
+    };
+    let details: Map<String, Value> = body
+        .into_iter()
+        .filter(|(key, _)| matches!(key.as_str(), "status" | "error" | "code" | "error_details"))
+        .collect();
+    Value::Object(details)
+}
+
+/// Turn the error of a failed tool call into a structured envelope:
+///
+/// ```json
+/// {"error": "not_found", "message": "...", "retryable": false, "hint": "...", "details": {...}}
+/// ```
+///
+/// The code is the one noted where the call failed. Failures noted nowhere are the tool
+/// rejecting its arguments before reaching Lago. Errors that are already structured
+/// (timeouts, spending caps...) keep their fields and get the missing ones.
+pub fn structure(
+    tool_name: &str,
+    result: Result<CallToolResult, McpError>,
+    failure: Option<Failure>,
+) -> Result<CallToolResult, McpError> {
+    let message = match &result {
+        Ok(call_result) if call_result.is_error == Some(true) => call_result
+            .content
+            .first()
+            .and_then(|content| content.as_text())
+            .map(|text| text.text.clone())
+            .unwrap_or_default(),
+        _ => return result,
+    };
+
+    let mut error = match serde_json::from_str::<Value>(&message) {
+        Ok(Value::Object(error)) if error.get("error").is_some_and(Value::is_string) => error,
+        _ => {
+            let code = failure
+                .as_ref()
+                .map_or(ErrorCode::Validation, |failure| failure.code);
+            let mut error = Map::new();
+            error.insert("error".to_string(), json!(code.as_str()));
+            error.insert("message".to_string(), json!(message));
+            error
+        }
+    };
+
+    let code = error["error"].as_str().unwrap_or_default().to_string();
+    let (mut retryable, hint) = guidance(&code);
+    // A write that timed out may have been applied: retrying it blindly could apply it twice.
+    if code == "timeout" && is_write_tool(tool_name) {
+        retryable = false;
+    }
+    error.entry("retryable").or_insert(json!(retryable));
+    error.entry("hint").or_insert(json!(hint));
+    error.entry("details").or_insert_with(|| {
+        failure
+            .as_ref()
+            .and_then(|failure| failure.lago_body.as_deref())
+            .map(lago_details)
+            .unwrap_or(Value::Null)
+    });
+
+    Ok(error_result(
+        serde_json::to_string_pretty(&error).unwrap_or(message),
+    ))
+}
@@ -0,0 +1,79 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    sync::{LazyLock, Mutex},
+};
+
+use crate::settings;
+use crate::tools::LagoApiConfig;
+
+/// Transaction IDs remembered per credential when `LAGO_MCP_EVENT_DEDUP_CAPACITY` is unset.
+const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Transaction IDs remembered per credential, from `LAGO_MCP_EVENT_DEDUP_CAPACITY`. `0` turns
+/// duplicate detection off.
+static CAPACITY: LazyLock<usize> = LazyLock::new(|| {
+    let Some(value) = settings::var("LAGO_MCP_EVENT_DEDUP_CAPACITY") else {
+        return DEFAULT_CAPACITY;
+    };
+    value.trim().parse().unwrap_or_else(|_| {
+        tracing::warn!(value, "Ignoring invalid LAGO_MCP_EVENT_DEDUP_CAPACITY");
+        DEFAULT_CAPACITY
+    })
+});
+
+/// Transaction IDs recently sent with one credential; the oldest are forgotten first.
+#[derive(Default)]
+struct SentTransactions {
+    ids: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+/// Hash of (base URL, API key) -> transaction IDs sent with that credential.
+static SENT: LazyLock<Mutex<HashMap<u64, SentTransactions>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn credential_key(config: &LagoApiConfig) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    config.base_url.hash(&mut hasher);
+    config.api_key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Record that an event is about to be sent, or fail when its transaction ID was already
+/// sent with the same credential.
+///
+/// The ID stays reserved unless [`release`]d, so that a call retried after timing out, whose
+/// event may have reached Lago, isn't sent twice.
+pub fn reserve(config: &LagoApiConfig, transaction_id: &str) -> Result<(), String> {
+    if *CAPACITY == 0 {
+        return Ok(());
+    }
+
+    let mut sent = SENT.lock().unwrap_or_else(|e| e.into_inner());
+    let transactions = sent.entry(credential_key(config)).or_default();
+    if transactions.ids.contains(transaction_id) {
+        return Err(format!(
+            "Duplicate transaction_id {transaction_id}: an event with this transaction_id was already sent, so it was not sent again. Use get_event to check it, or a new transaction_id for a different event"
+        ));
+    }
+
+    transactions.ids.insert(transaction_id.to_string());
+    transactions.order.push_back(transaction_id.to_string());
+    while transactions.order.len() > *CAPACITY {
+        if let Some(oldest) = transactions.order.pop_front() {
+            transactions.ids.remove(&oldest);
+        }
+    }
+
+    Ok(())
+}
+
+/// Forget the transaction ID of an event Lago rejected, so that it can be sent again.
+pub fn release(config: &LagoApiConfig, transaction_id: &str) {
+    let mut sent = SENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(transactions) = sent.get_mut(&credential_key(config)) {
+        transactions.ids.remove(transaction_id);
+        transactions.order.retain(|id| id != transaction_id);
+    }
+}
@@ -0,0 +1,359 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, Once},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::audit::{self, AuditEntry};
+use crate::event_dedup;
+use crate::progress;
+use crate::settings;
+use crate::tools::{LagoApiConfig, LagoHttpError, lago_request};
+
+/// Largest batch accepted by Lago's `/events/batch` endpoint.
+const MAX_BATCH_SIZE: usize = 100;
+
+/// Batching settings, read from the environment.
+struct QueueSettings {
+    /// Queued events triggering an immediate flush.
+    batch_size: usize,
+    /// Maximum time an event waits in the queue before being flushed.
+    flush_interval: Duration,
+    /// Events kept per credential before `queue_event` is refused.
+    max_queued: usize,
+}
+
+static SETTINGS: LazyLock<QueueSettings> = LazyLock::new(|| {
+    let setting = |name: &str, default: u64| {
+        let Some(value) = settings::var(name) else {
+            return default;
+        };
+        value.trim().parse().unwrap_or_else(|_| {
+            tracing::warn!(name, value, "Ignoring invalid event queue setting");
+            default
+        })
+    };
+
+    QueueSettings {
+        batch_size: (setting("LAGO_MCP_EVENT_BATCH_SIZE", MAX_BATCH_SIZE as u64) as usize)
+            .clamp(1, MAX_BATCH_SIZE),
+        flush_interval: Duration::from_secs(setting("LAGO_MCP_EVENT_FLUSH_INTERVAL_SECS", 5)),
+        max_queued: setting("LAGO_MCP_EVENT_QUEUE_MAX", 10_000) as usize,
+    }
+});
+
+/// Events waiting to be sent with one credential.
+#[derive(Default)]
+struct CredentialQueue {
+    events: Vec<Value>,
+    oldest: Option<Instant>,
+    flushed: u64,
+    /// Events of batches Lago rejected, which are not retried.
+    dropped: u64,
+    last_flush_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+/// (base URL, API key) -> queue
+type QueueKey = (String, String);
+
+static QUEUES: LazyLock<Mutex<HashMap<QueueKey, CredentialQueue>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Serializes flushes so that a batch is never sent twice.
+static FLUSH_LOCK: LazyLock<tokio::sync::Mutex<()>> = LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+static START_FLUSHER: Once = Once::new();
+
+fn queue_key(config: &LagoApiConfig) -> QueueKey {
+    (config.base_url.clone(), config.api_key.clone())
+}
+
+/// Add an event to the queue of `config`'s credential.
+///
+/// Flushes right away once a full batch is queued; otherwise the background flusher sends
+/// it within the flush interval. Returns the number of events queued for the credential.
+pub async fn enqueue(config: &LagoApiConfig, event: Value) -> Result<usize, String> {
+    START_FLUSHER.call_once(|| {
+        tokio::spawn(flush_periodically());
+    });
+
+    let queued = {
+        let mut queues = QUEUES.lock().unwrap_or_else(|e| e.into_inner());
+        let queue = queues.entry(queue_key(config)).or_default();
+
+        if queue.events.len() >= SETTINGS.max_queued {
+            return Err(format!(
+                "Event queue is full ({} events). Call flush_events and check queue_status for errors",
+                queue.events.len()
+            ));
+        }
+
+        queue.events.push(event);
+        queue.oldest.get_or_insert_with(Instant::now);
+        queue.events.len()
+    };
+
+    if queued >= SETTINGS.batch_size
+        && let Err(e) = flush(config).await
+    {
+        tracing::warn!(error = %e, "Failed to flush full event batch, keeping events queued");
+    }
+
+    Ok(queued)
+}
+
+/// Send every queued event of `config`'s credential, in batches. Returns the number sent.
+///
+/// Events of a batch that failed for a transient reason (Lago unreachable, rate limited or
+/// failing) stay at the front of the queue and are retried on the next flush. A batch Lago
+/// rejected would be rejected again, so it is dropped, logged and audited, and the next
+/// batches are still sent.
+pub async fn flush(config: &LagoApiConfig) -> Result<usize, String> {
+    let _guard = FLUSH_LOCK.lock().await;
+    let key = queue_key(config);
+    let mut sent = 0;
+
+    loop {
+        let batch: Vec<Value> = {
+            let mut queues = QUEUES.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(queue) = queues.get_mut(&key) else {
+                return Ok(sent);
+            };
+            let batch_len = queue.events.len().min(SETTINGS.batch_size);
+            queue.events.drain(..batch_len).collect()
+        };
+        if batch.is_empty() {
+            return Ok(sent);
+        }
+
+        let body = serde_json::json!({ "events": batch });
+        let started_at = Instant::now();
+        let result = lago_request(
+            &HTTP_CLIENT,
+            config,
+            reqwest::Method::POST,
+            "/events/batch",
+            &[],
+            Some(&body),
+        )
+        .await;
+
+        // The guard must be gone before the progress await, or the future is not `Send`.
+        let (total, rejection) = {
+            let mut queues = QUEUES.lock().unwrap_or_else(|e| e.into_inner());
+            let queue = queues.entry(key.clone()).or_default();
+            let rejection = match result {
+                Ok(_) => {
+                    sent += batch.len();
+                    queue.flushed += batch.len() as u64;
+                    queue.last_flush_at = Some(Utc::now());
+                    queue.last_error = None;
+                    if queue.events.is_empty() {
+                        queue.oldest = None;
+                    }
+                    None
+                }
+                Err(e) if is_transient(&e) => {
+                    let error_message = format!("Failed to send event batch: {e}");
+                    queue.events.splice(0..0, batch);
+                    queue.oldest = Some(Instant::now());
+                    queue.last_error = Some(error_message.clone());
+                    return Err(error_message);
+                }
+                Err(e) => {
+                    let error_message = format!(
+                        "Lago rejected a batch of {} events, dropped: {e}",
+                        batch.len()
+                    );
+                    queue.dropped += batch.len() as u64;
+                    queue.last_error = Some(error_message.clone());
+                    if queue.events.is_empty() {
+                        queue.oldest = None;
+                    }
+                    Some(error_message)
+                }
+            };
+            (sent + queue.events.len(), rejection)
+        };
+        if let Some(error_message) = rejection {
+            drop_batch(config, &batch, error_message, started_at.elapsed());
+        }
+        progress::report(sent, Some(total), format!("Sent {sent} queued events")).await;
+    }
+}
+
+/// Whether a batch that failed with `error` may be accepted when sent again.
+fn is_transient(error: &LagoHttpError) -> bool {
+    match error {
+        LagoHttpError::Status { status, .. } => status.as_u16() == 429 || status.is_server_error(),
+        LagoHttpError::Transport(_) => true,
+        LagoHttpError::InvalidJson(_) => false,
+    }
+}
+
+/// Give up on a batch Lago rejected: log it, note it in the audit trail with the transaction
+/// IDs of its events, and free those IDs so that corrected events can be queued again.
+fn drop_batch(config: &LagoApiConfig, batch: &[Value], error_message: String, duration: Duration) {
+    let transaction_ids: Vec<&str> = batch
+        .iter()
+        .filter_map(|event| event["transaction_id"].as_str())
+        .collect();
+    tracing::error!(events = batch.len(), ?transaction_ids, "{error_message}");
+
+    for transaction_id in &transaction_ids {
+        event_dedup::release(config, transaction_id);
+    }
+
+    let mut arguments = serde_json::Map::new();
+    arguments.insert(
+        "transaction_ids".to_string(),
+        serde_json::json!(transaction_ids),
+    );
+    audit::record(AuditEntry {
+        session_id: None,
+        subject: None,
+        tool_name: "event_queue",
+        arguments: Some(arguments),
+        outcome: "error",
+        error: Some(error_message),
+        duration,
+        lago_request_ids: Vec::new(),
+    });
+}
+
+/// Queue state of `config`'s credential, in the shape returned by `queue_status`.
+pub fn status(config: &LagoApiConfig) -> Value {
+    let queues = QUEUES.lock().unwrap_or_else(|e| e.into_inner());
+    let queue = queues.get(&queue_key(config));
+
+    serde_json::json!({
+        "queued": queue.map_or(0, |queue| queue.events.len()),
+        "oldest_queued_seconds": queue
+            .and_then(|queue| queue.oldest)
+            .map(|oldest| oldest.elapsed().as_secs()),
+        "flushed": queue.map_or(0, |queue| queue.flushed),
+        "dropped": queue.map_or(0, |queue| queue.dropped),
+        "last_flush_at": queue
+            .and_then(|queue| queue.last_flush_at)
+            .map(|at| at.to_rfc3339()),
+        "last_error": queue.and_then(|queue| queue.last_error.clone()),
+        "batch_size": SETTINGS.batch_size,
+        "flush_interval_secs": SETTINGS.flush_interval.as_secs(),
+        "max_queued": SETTINGS.max_queued,
+    })
+}
+
+/// Flush the queues whose oldest event has waited for the flush interval.
+async fn flush_periodically() {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        interval.tick().await;
+
+        let due: Vec<QueueKey> = {
+            let queues = QUEUES.lock().unwrap_or_else(|e| e.into_inner());
+            queues
+                .iter()
+                .filter(|(_, queue)| {
+                    queue
+                        .oldest
+                        .is_some_and(|oldest| oldest.elapsed() >= SETTINGS.flush_interval)
+                })
+                .map(|(key, _)| key.clone())
+                .collect()
+        };
+
+        for (base_url, api_key) in due {
+            let config = LagoApiConfig { api_key, base_url };
+            match flush(&config).await {
+                Ok(sent) => tracing::debug!(sent, "Flushed queued events"),
+                Err(e) => tracing::warn!(error = %e, "Failed to flush queued events"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
+
+    use super::*;
+
+    /// Lago stand-in accepting the batches whose events all have a known code, and keeping
+    /// the transaction IDs of the events it accepted.
+    async fn lago(accepted: Arc<Mutex<Vec<String>>>) -> String {
+        async fn batch(
+            State(accepted): State<Arc<Mutex<Vec<String>>>>,
+            Json(body): Json<Value>,
+        ) -> (StatusCode, Json<Value>) {
+            let events = body["events"].as_array().cloned().unwrap_or_default();
+            if events.iter().any(|event| event["code"] == "unknown") {
+                let error = serde_json::json!({
+                    "status": 422,
+                    "error": "Unprocessable Entity",
+                    "code": "validation_errors",
+                    "error_details": { "0": { "code": ["metric_not_found"] } },
+                });
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(error));
+            }
+            accepted.lock().unwrap().extend(
+                events
+                    .iter()
+                    .filter_map(|event| event["transaction_id"].as_str())
+                    .map(str::to_string),
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "events": events })),
+            )
+        }
+
+        let router = Router::new()
+            .route("/api/v1/events/batch", post(batch))
+            .with_state(accepted);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        format!("http://{address}/api/v1")
+    }
+
+    #[tokio::test]
+    async fn rejected_batches_are_dropped_without_blocking_the_next() {
+        let accepted = Arc::new(Mutex::new(Vec::new()));
+        let config = LagoApiConfig {
+            api_key: "rejected-batch-key".to_string(),
+            base_url: lago(accepted.clone()).await,
+        };
+        let event = |transaction_id: &str, code: &str| {
+            serde_json::json!({
+                "transaction_id": transaction_id,
+                "external_subscription_id": "sub_1",
+                "code": code,
+            })
+        };
+
+        enqueue(&config, event("tx_rejected", "unknown"))
+            .await
+            .unwrap();
+        assert_eq!(flush(&config).await, Ok(0));
+
+        enqueue(&config, event("tx_accepted", "api_calls"))
+            .await
+            .unwrap();
+        assert_eq!(flush(&config).await, Ok(1));
+
+        assert_eq!(*accepted.lock().unwrap(), ["tx_accepted"]);
+        let status = status(&config);
+        assert_eq!(status["queued"], 0);
+        assert_eq!(status["flushed"], 1);
+        assert_eq!(status["dropped"], 1);
+    }
+}
@@ -0,0 +1,91 @@
+use std::{collections::BTreeSet, sync::LazyLock};
+
+use rmcp::handler::server::router::tool::ToolRouter;
+
+use crate::settings;
+
+/// Tool families that can be switched on and off through `LAGO_MCP_FEATURES`.
+///
+/// Tools missing from this list are always available.
+const TOOL_FEATURES: &[(&str, &[&str])] = &[
+    (
+        "analytics",
+        &[
+            "report_upcoming_renewals",
+            "report_subscription_cohorts",
+            "report_top_metric_consumers",
+            "report_revenue_by_plan",
+            "estimate_customer_ltv",
+            "get_dso",
+            "report_expiring_coupons",
+            "get_invoiced_usage",
+            "get_overdue_balance",
+        ],
+    ),
+    (
+        "wallets",
+        &[
+            "report_low_wallets",
+            "create_wallet_transaction",
+            "list_wallet_transactions",
+        ],
+    ),
+    ("server_stats", &["get_server_stats"]),
+    (
+        "event_queue",
+        &["queue_event", "flush_events", "queue_status"],
+    ),
+];
+
+/// Features enabled when `LAGO_MCP_FEATURES` is not set.
+///
+/// New tool families start outside of this list and are opted into until they are stable.
+const DEFAULT_FEATURES: &[&str] = &["analytics", "wallets", "server_stats"];
+
+/// Features enabled for this process.
+///
+/// `LAGO_MCP_FEATURES` is a comma-separated list of feature names replacing the defaults;
+/// `all` enables every feature and an empty value disables them all.
+static ENABLED_FEATURES: LazyLock<BTreeSet<String>> = LazyLock::new(|| {
+    let Some(features) = settings::var("LAGO_MCP_FEATURES") else {
+        return DEFAULT_FEATURES.iter().map(|f| f.to_string()).collect();
+    };
+
+    let features: BTreeSet<String> = features
+        .split(',')
+        .map(|feature| feature.trim().to_lowercase())
+        .filter(|feature| !feature.is_empty())
+        .collect();
+
+    if features.contains("all") {
+        return TOOL_FEATURES
+            .iter()
+            .map(|(feature, _)| feature.to_string())
+            .collect();
+    }
+
+    for feature in &features {
+        if !TOOL_FEATURES.iter().any(|(name, _)| name == feature) {
+            tracing::warn!(feature, "Unknown feature in LAGO_MCP_FEATURES");
+        }
+    }
+
+    features
+});
+
+pub fn is_enabled(feature: &str) -> bool {
+    ENABLED_FEATURES.contains(feature)
+}
+
+/// Remove the tools of disabled features from the router.
+pub fn apply<S: Send + Sync + 'static>(tool_router: &mut ToolRouter<S>) {
+    for (feature, tool_names) in TOOL_FEATURES {
+        if is_enabled(feature) {
+            continue;
+        }
+
+        for tool_name in *tool_names {
+            tool_router.remove_route::<(), ()>(tool_name);
+        }
+    }
+}
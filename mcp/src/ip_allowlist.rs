@@ -0,0 +1,85 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use ipnet::IpNet;
+
+/// Client networks allowed to reach the `/mcp` route. Every client is allowed when empty.
+#[derive(Clone, Debug, clap::Args)]
+pub struct IpAllowlistArgs {
+    /// IP addresses or CIDR ranges allowed to call the MCP endpoint
+    #[arg(long, env = "LAGO_MCP_ALLOWED_IPS", value_delimiter = ',', value_parser = parse_ip_net)]
+    pub allowed_ips: Vec<IpNet>,
+    /// Use the last X-Forwarded-For entry as the client IP (only behind a trusted proxy)
+    #[arg(long, env = "LAGO_MCP_TRUST_FORWARDED_FOR")]
+    pub trust_forwarded_for: bool,
+}
+
+impl IpAllowlistArgs {
+    pub fn allowlist(&self) -> Option<Arc<Self>> {
+        if self.allowed_ips.is_empty() {
+            return None;
+        }
+
+        tracing::info!(allowed_ips = ?self.allowed_ips, "IP allowlist enabled");
+        Some(Arc::new(self.clone()))
+    }
+}
+
+/// IP of the client that sent `request`, taken from the last X-Forwarded-For entry when
+/// the proxy in front of the server is trusted.
+pub fn client_ip(peer: SocketAddr, request: &Request, trust_forwarded_for: bool) -> IpAddr {
+    if !trust_forwarded_for {
+        return peer.ip();
+    }
+
+    request
+        .headers()
+        .get("X-Forwarded-For")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok())
+        .unwrap_or(peer.ip())
+}
+
+fn parse_ip_net(value: &str) -> Result<IpNet, String> {
+    let value = value.trim();
+
+    value
+        .parse::<IpNet>()
+        .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("invalid IP address or CIDR range: {value}"))
+}
+
+/// Reject requests from clients outside the allowlist with `403`.
+pub async fn enforce_ip_allowlist(
+    State(allowlist): State<Arc<IpAllowlistArgs>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client_ip = client_ip(peer, &request, allowlist.trust_forwarded_for);
+
+    if !allowlist
+        .allowed_ips
+        .iter()
+        .any(|network| network.contains(&client_ip))
+    {
+        tracing::warn!(
+            %client_ip,
+            method = %request.method(),
+            path = %request.uri().path(),
+            "Denied request from an IP outside the allowlist"
+        );
+        return (StatusCode::FORBIDDEN, "Forbidden").into_response();
+    }
+
+    next.run(request).await
+}
@@ -1,2 +1,33 @@
+pub mod api_version;
+mod audit;
+mod backoff;
+mod client_log;
+mod completion;
+pub mod confirmation;
+mod credentials;
+mod errors;
+mod event_dedup;
+mod event_queue;
+pub mod features;
+mod money;
+mod outbound;
+pub mod principal;
+mod progress;
+mod quota;
+pub mod recording;
+pub mod redaction;
+mod relay;
+mod resources;
+pub mod sandbox;
 mod server;
+pub mod settings;
+mod shaping;
+mod spending;
+pub mod stats;
+mod timeout;
+mod timezone;
 mod tools;
+
+pub use credentials::EXPERIMENTAL_CAPABILITY;
+pub use server::LagoMcpServer;
+pub use tools::{LagoHttpError, lago_api_config_from_env, lago_request};
@@ -0,0 +1,103 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Request, State},
+    http::{StatusCode, header::CONTENT_LENGTH},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// Limits applied to the requests received on the `/mcp` route.
+#[derive(Clone, Copy, Debug, clap::Args)]
+pub struct RequestLimits {
+    /// Maximum size of a request body, in bytes
+    #[arg(long, env = "LAGO_MCP_MAX_BODY_BYTES", default_value_t = 1024 * 1024)]
+    pub max_body_bytes: usize,
+    /// Maximum nesting depth of the JSON objects and arrays in a request body
+    #[arg(long, env = "LAGO_MCP_MAX_JSON_DEPTH", default_value_t = 32)]
+    pub max_json_depth: usize,
+}
+
+/// Reject oversized bodies with `413` and too deeply nested JSON with `400`.
+///
+/// The body is buffered (up to the size limit) and handed back to the MCP service untouched.
+pub async fn enforce_request_limits(
+    State(limits): State<RequestLimits>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let declared_length = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+
+    if declared_length.is_some_and(|length| length > limits.max_body_bytes) {
+        return payload_too_large(limits.max_body_bytes);
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, limits.max_body_bytes).await {
+        Ok(bytes) => bytes,
+        Err(_) => return payload_too_large(limits.max_body_bytes),
+    };
+
+    if json_depth(&bytes) > limits.max_json_depth {
+        tracing::warn!(
+            max_json_depth = limits.max_json_depth,
+            "Rejected request with too deeply nested JSON"
+        );
+        return (
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Bad Request: JSON nesting exceeds the maximum depth of {}",
+                limits.max_json_depth
+            ),
+        )
+            .into_response();
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+fn payload_too_large(max_body_bytes: usize) -> Response {
+    tracing::warn!(max_body_bytes, "Rejected oversized request body");
+
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        format!("Payload Too Large: request body exceeds {max_body_bytes} bytes"),
+    )
+        .into_response()
+}
+
+/// Maximum nesting depth of objects and arrays in a JSON document, without parsing it.
+fn json_depth(bytes: &[u8]) -> usize {
+    let mut depth = 0usize;
+    let mut max_depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for &byte in bytes {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    max_depth
+}
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::Level;
+use tracing_subscriber::{
+    EnvFilter, Layer, filter::filter_fn, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+use lago_mcp_server::{redaction, settings};
+
+static VERBOSE_EVENTS: AtomicU64 = AtomicU64::new(0);
+
+/// Install the JSON logger on stderr.
+///
+/// Levels come from `RUST_LOG`, or `logging.level` in the configuration file, and default
+/// to INFO. Events at DEBUG level and below are sampled according to `LAGO_MCP_DEBUG_SAMPLE_RATE`
+/// (between 0 and 1, default 1), so verbose logging stays affordable in production. Secrets
+/// and customer PII are redacted from every event.
+pub fn init() {
+    let sample_rate = settings::var("LAGO_MCP_DEBUG_SAMPLE_RATE")
+        .and_then(|rate| rate.trim().parse::<f64>().ok())
+        .map(|rate| rate.clamp(0.0, 1.0))
+        .unwrap_or(1.0);
+    let keep_every = if sample_rate > 0.0 {
+        (1.0 / sample_rate).round() as u64
+    } else {
+        0
+    };
+
+    let sampling = filter_fn(move |metadata| {
+        if !metadata.is_event() || *metadata.level() < Level::DEBUG || keep_every == 1 {
+            return true;
+        }
+        keep_every > 0
+            && VERBOSE_EVENTS
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(keep_every)
+    });
+
+    tracing_subscriber::registry()
+        .with(filter())
+        .with(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_writer(redaction::RedactingStderr)
+                .with_filter(sampling),
+        )
+        .init();
+
+    if sample_rate < 1.0 {
+        tracing::info!(sample_rate, "Sampling DEBUG and TRACE events");
+    }
+}
+
+/// Level filter from the `RUST_LOG` setting, INFO when it is unset.
+fn filter() -> EnvFilter {
+    EnvFilter::builder()
+        .with_default_directive(Level::INFO.into())
+        .parse_lossy(settings::var("RUST_LOG").unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::level_filters::LevelFilter;
+
+    use super::*;
+    use crate::config;
+
+    #[test]
+    fn level_is_read_from_the_configuration_file() {
+        let path =
+            std::env::temp_dir().join(format!("lago-mcp-logging-{}.toml", std::process::id()));
+        std::fs::write(&path, "[logging]\nlevel = \"debug\"\n").unwrap();
+        let loaded = config::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        settings::init(loaded.values);
+
+        assert_eq!(filter().max_level_hint(), Some(LevelFilter::DEBUG));
+    }
+}
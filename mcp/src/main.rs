@@ -1,80 +1,409 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use rmcp::{
     ServiceExt,
     transport::{
         stdio,
-        streamable_http_server::{StreamableHttpService, session::local::LocalSessionManager},
+        streamable_http_server::{
+            SessionManager, StreamableHttpServerConfig, StreamableHttpService,
+            session::{local::LocalSessionManager, never::NeverSessionManager},
+        },
     },
 };
-use tracing_subscriber::EnvFilter;
+use std::sync::Arc;
 
-mod server;
-mod tools;
+mod auth;
+mod config;
+mod cors;
+mod doctor;
+mod ip_allowlist;
+mod limits;
+mod logging;
+mod rate_limit;
+#[cfg(feature = "redis-sessions")]
+mod session;
 
-use server::LagoMcpServer;
+use lago_mcp_server::{
+    LagoMcpServer, api_version, confirmation, features, recording, sandbox, settings, stats,
+};
+
+use auth::{BearerAuthenticator, BearerTokenArgs, OidcArgs};
+use cors::CorsArgs;
+use ip_allowlist::IpAllowlistArgs;
+use limits::RequestLimits;
+use rate_limit::{RateLimitArgs, RateLimiter};
 
 #[derive(Parser)]
 #[command(name = "lago-mcp-server")]
 #[command(about = "Lago MCP Server with support for stdio and SSE transports")]
 struct Cli {
+    /// TOML configuration file providing defaults for the settings not set in the
+    /// environment or on the command line
+    #[arg(long, global = true, env = "LAGO_MCP_CONFIG")]
+    config: Option<std::path::PathBuf>,
+    /// Serve tool calls from an in-memory fake Lago instead of a real instance, to try the
+    /// server without credentials or risk
+    #[arg(long, global = true, env = "LAGO_MCP_SANDBOX")]
+    sandbox: bool,
+    /// Directory of JSON fixtures seeding the sandbox, one `<collection>.json` file per
+    /// resource (e.g. `customers.json`)
+    #[arg(long, global = true, env = "LAGO_MCP_SANDBOX_FIXTURES")]
+    sandbox_fixtures: Option<std::path::PathBuf>,
+    /// Directory to record every Lago API request and response to
+    #[arg(
+        long,
+        global = true,
+        env = "LAGO_MCP_RECORD",
+        conflicts_with_all = ["replay", "sandbox"]
+    )]
+    record: Option<std::path::PathBuf>,
+    /// Directory of recorded Lago API interactions to answer requests from, instead of Lago
+    #[arg(
+        long,
+        global = true,
+        env = "LAGO_MCP_REPLAY",
+        conflicts_with = "sandbox"
+    )]
+    replay: Option<std::path::PathBuf>,
     #[command(subcommand)]
     command: Commands,
 }
 
+// Parsed once at startup, so the size of the `Sse` variant does not matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     Stdio,
+    /// Check the configuration and the connection to the Lago API
+    Doctor,
     Sse {
-        #[arg(short, long, default_value = "3000")]
+        #[arg(short, long, env = "LAGO_MCP_PORT", default_value = "3000")]
         port: u16,
-        #[arg(long, default_value = "127.0.0.1")]
+        #[arg(long, env = "LAGO_MCP_HOST", default_value = "127.0.0.1")]
         host: String,
+        /// Serve every request independently, without sessions, so that replicas behind a
+        /// load balancer need no sticky sessions
+        #[arg(long, env = "LAGO_MCP_STATELESS")]
+        stateless: bool,
+        #[command(flatten)]
+        mount: MountArgs,
+        #[command(flatten)]
+        session: SessionArgs,
+        #[command(flatten)]
+        limits: RequestLimits,
+        #[command(flatten)]
+        cors: CorsArgs,
+        #[command(flatten)]
+        bearer_tokens: BearerTokenArgs,
+        #[command(flatten)]
+        oidc: OidcArgs,
+        #[command(flatten)]
+        ip_allowlist: IpAllowlistArgs,
+        #[command(flatten)]
+        rate_limit: RateLimitArgs,
     },
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+enum SessionStore {
+    /// Keep sessions in memory; they are lost on restart.
+    Local,
+    /// Share sessions across replicas through Redis.
+    #[cfg(feature = "redis-sessions")]
+    Redis,
+}
+
+#[derive(clap::Args)]
+struct SessionArgs {
+    /// Where HTTP transport sessions are stored
+    #[arg(
+        long = "session-store",
+        env = "LAGO_MCP_SESSION_STORE",
+        value_enum,
+        default_value = "local"
+    )]
+    store: SessionStore,
+    /// Redis connection URL used by the redis session store
+    #[cfg(feature = "redis-sessions")]
+    #[arg(long, env = "REDIS_URL", default_value = "redis://127.0.0.1:6379")]
+    redis_url: String,
+    /// Seconds an idle session is kept in Redis
+    #[cfg(feature = "redis-sessions")]
+    #[arg(long, env = "LAGO_MCP_SESSION_TTL_SECS", default_value_t = session::DEFAULT_SESSION_TTL_SECS)]
+    session_ttl_secs: u64,
+}
+
+#[derive(clap::Args)]
+struct MountArgs {
+    /// Paths the MCP endpoint is served at, comma-separated; `/` serves it at the root, for
+    /// proxies that can't rewrite paths
+    #[arg(
+        long = "path",
+        env = "LAGO_MCP_PATHS",
+        value_delimiter = ',',
+        default_value = "/mcp"
+    )]
+    paths: Vec<String>,
+    /// Path of the health check endpoint
+    #[arg(long, env = "LAGO_MCP_HEALTH_PATH", default_value = "/health")]
+    health_path: String,
+}
+
+impl MountArgs {
+    /// Check the paths, without their trailing slashes, before the router is built: axum
+    /// panics on conflicting routes.
+    fn validate(mut self) -> Result<Self> {
+        let normalize = |path: &str| {
+            let path = path.trim().trim_end_matches('/');
+            if path.is_empty() {
+                return Ok("/".to_string());
+            }
+            if !path.starts_with('/') {
+                anyhow::bail!("Invalid path {path}: paths must start with /");
+            }
+            Ok(path.to_string())
+        };
+
+        self.health_path = normalize(&self.health_path)?;
+        if self.health_path == "/" {
+            anyhow::bail!("The health check can't be served at the root");
+        }
+        self.paths = self
+            .paths
+            .iter()
+            .map(|path| normalize(path))
+            .collect::<Result<_>>()?;
+
+        for path in &self.paths {
+            if *path == self.health_path {
+                anyhow::bail!("{path} is both an MCP path and the health check path");
+            }
+            if self.paths.iter().filter(|other| *other == path).count() > 1 {
+                anyhow::bail!("{path} is listed more than once in the MCP paths");
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Middleware applied to the MCP routes.
+struct HttpLayers {
+    limits: RequestLimits,
+    cors: Option<tower_http::cors::CorsLayer>,
+    auth: Option<Arc<BearerAuthenticator>>,
+    ip_allowlist: Option<Arc<IpAllowlistArgs>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+async fn serve_http<M: SessionManager>(
+    session_manager: M,
+    config: StreamableHttpServerConfig,
+    mount: &MountArgs,
+    layers: HttpLayers,
+    address: &str,
+) -> Result<()> {
+    let service =
+        StreamableHttpService::new(|| Ok(LagoMcpServer::new()), session_manager.into(), config);
+
+    let mut mcp_router = axum::Router::new();
+    for path in &mount.paths {
+        mcp_router = match path.as_str() {
+            // axum can't nest at the root: the service answers every other path instead.
+            "/" => mcp_router.fallback_service(service.clone()),
+            path => mcp_router.nest_service(path, service.clone()),
+        };
+    }
+    let mut mcp_router = mcp_router.layer(axum::middleware::from_fn_with_state(
+        layers.limits,
+        limits::enforce_request_limits,
+    ));
+    if let Some(authenticator) = layers.auth {
+        mcp_router = mcp_router.layer(axum::middleware::from_fn_with_state(
+            authenticator,
+            auth::require_bearer,
+        ));
+    }
+    if let Some(cors) = layers.cors {
+        mcp_router = mcp_router.layer(cors);
+    }
+    if let Some(rate_limiter) = layers.rate_limiter {
+        mcp_router = mcp_router.layer(axum::middleware::from_fn_with_state(
+            rate_limiter,
+            rate_limit::enforce_rate_limit,
+        ));
+    }
+    if let Some(ip_allowlist) = layers.ip_allowlist {
+        mcp_router = mcp_router.layer(axum::middleware::from_fn_with_state(
+            ip_allowlist,
+            ip_allowlist::enforce_ip_allowlist,
+        ));
+    }
+    let router = axum::Router::new()
+        .merge(mcp_router)
+        .route(&mount.health_path, axum::routing::get(|| async {}));
+    let tcp_listener = tokio::net::TcpListener::bind(address).await?;
+    let _ = axum::serve(
+        tcp_listener,
+        router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(async { tokio::signal::ctrl_c().await.unwrap() })
+    .await;
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .json()
-        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
-        .with_writer(std::io::stderr)
-        .init();
+    let loaded_config = config::path().map(|path| config::load(&path)).transpose()?;
+    let cli = match &loaded_config {
+        Some(loaded_config) => {
+            let matches = loaded_config.apply_defaults(Cli::command()).get_matches();
+            Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+        }
+        None => Cli::parse(),
+    };
+    if let Some(loaded_config) = &loaded_config {
+        settings::init(loaded_config.values.clone());
+    }
 
-    let cli = Cli::parse();
+    logging::init();
+    if let Some(loaded_config) = &loaded_config {
+        loaded_config.log();
+    }
+    stats::init();
+    if cli.sandbox {
+        sandbox::start(cli.sandbox_fixtures.as_deref()).await?;
+    }
+    if let Some(directory) = &cli.record {
+        recording::start(recording::Mode::Record(directory.clone())).await?;
+    } else if let Some(directory) = &cli.replay {
+        recording::start(recording::Mode::Replay(directory.clone())).await?;
+    }
 
     match cli.command {
         Commands::Stdio => {
             tracing::info!("Starting Lago MCP Server with stdio transport");
 
-            let service = LagoMcpServer::new()
+            if let Err(e) = lago_mcp_server::lago_api_config_from_env() {
+                tracing::warn!(
+                    "{e}: tool calls will fail. Run `lago-mcp-server doctor` for diagnostics"
+                );
+            }
+            api_version::init().await;
+
+            let service = LagoMcpServer::stdio()
                 .serve(stdio())
                 .await
                 .inspect_err(|e| tracing::error!("Failed to start server: {e:?}"))?;
 
             service.waiting().await?;
         }
-        Commands::Sse { port, host } => {
+        Commands::Doctor => doctor::run().await?,
+        Commands::Sse {
+            port,
+            host,
+            stateless,
+            mount,
+            session,
+            limits,
+            cors,
+            bearer_tokens,
+            oidc,
+            ip_allowlist,
+            rate_limit,
+        } => {
             tracing::info!(
                 "Starting Lago MCP Server with sse transport on {}:{}",
                 host,
                 port,
             );
 
-            let service = StreamableHttpService::new(
-                || Ok(LagoMcpServer::new()),
-                LocalSessionManager::default().into(),
-                Default::default(),
-            );
-
-            let router = axum::Router::new()
-                .nest_service("/mcp", service)
-                .route("/health", axum::routing::get(|| async {}));
             let address = format!("{host}:{port}");
-            let tcp_listener = tokio::net::TcpListener::bind(address).await?;
-            let _ = axum::serve(tcp_listener, router)
-                .with_graceful_shutdown(async { tokio::signal::ctrl_c().await.unwrap() })
-                .await;
+            let mount = mount.validate()?;
+            tracing::info!(paths = ?mount.paths, health_path = %mount.health_path, "Serving MCP");
+            let layers = HttpLayers {
+                limits,
+                cors: cors.layer()?,
+                auth: auth::authenticator(&bearer_tokens, &oidc).await?,
+                ip_allowlist: ip_allowlist.allowlist(),
+                rate_limiter: rate_limit.limiter(ip_allowlist.trust_forwarded_for),
+            };
+            let loopback = host == "localhost"
+                || host
+                    .parse::<std::net::IpAddr>()
+                    .is_ok_and(|ip| ip.is_loopback());
+            if layers.auth.is_none() && !loopback {
+                tracing::warn!(
+                    %host,
+                    "The MCP endpoint accepts unauthenticated requests: set LAGO_MCP_BEARER_TOKENS or LAGO_MCP_OIDC_ISSUER"
+                );
+            }
+            api_version::init().await;
+
+            if stateless {
+                // Each request is handled by a fresh server instance and credentials are read
+                // from the request headers, so no session may be created or looked up.
+                if session.store != SessionStore::Local {
+                    anyhow::bail!("--stateless requires the local session store");
+                }
+                // Queued events wait in the memory of the replica that received them, where
+                // the next request, served by any replica, can't flush or inspect them.
+                if features::is_enabled("event_queue") {
+                    anyhow::bail!(
+                        "--stateless can't be used with the event_queue feature: remove it from LAGO_MCP_FEATURES"
+                    );
+                }
+
+                tracing::info!("Running in stateless mode");
+                if confirmation::enabled() {
+                    tracing::warn!(
+                        "Write confirmation needs a session to ask the user: in stateless mode, calls above LAGO_MCP_CONFIRM_ABOVE_CENTS are refused"
+                    );
+                }
+                let config = StreamableHttpServerConfig {
+                    stateful_mode: false,
+                    ..Default::default()
+                };
+                serve_http(
+                    NeverSessionManager::default(),
+                    config,
+                    &mount,
+                    layers,
+                    &address,
+                )
+                .await?;
+                return Ok(());
+            }
+
+            match session.store {
+                SessionStore::Local => {
+                    serve_http(
+                        LocalSessionManager::default(),
+                        Default::default(),
+                        &mount,
+                        layers,
+                        &address,
+                    )
+                    .await?;
+                }
+                #[cfg(feature = "redis-sessions")]
+                SessionStore::Redis => {
+                    tracing::info!("Using Redis session store");
+                    let session_manager = session::RedisSessionManager::connect(
+                        &session.redis_url,
+                        session.session_ttl_secs,
+                    )
+                    .await?;
+                    serve_http(
+                        session_manager,
+                        Default::default(),
+                        &mount,
+                        layers,
+                        &address,
+                    )
+                    .await?;
+                }
+            }
         }
     }
 
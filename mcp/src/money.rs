@@ -0,0 +1,156 @@
+use serde_json::{Map, Value};
+
+/// Currencies whose minor unit is not a hundredth, as (code, decimal places).
+const MINOR_UNIT_EXPONENTS: &[(&str, u32)] = &[
+    ("BHD", 3),
+    ("BIF", 0),
+    ("CLP", 0),
+    ("DJF", 0),
+    ("GNF", 0),
+    ("IQD", 3),
+    ("ISK", 0),
+    ("JOD", 3),
+    ("JPY", 0),
+    ("KMF", 0),
+    ("KRW", 0),
+    ("KWD", 3),
+    ("LYD", 3),
+    ("OMR", 3),
+    ("PYG", 0),
+    ("RWF", 0),
+    ("TND", 3),
+    ("UGX", 0),
+    ("VND", 0),
+    ("VUV", 0),
+    ("XAF", 0),
+    ("XOF", 0),
+    ("XPF", 0),
+];
+
+/// Symbols written before the amount. Other currencies are suffixed with their code.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("USD", "$"),
+    ("EUR", "€"),
+    ("GBP", "£"),
+    ("JPY", "¥"),
+    ("CNY", "CN¥"),
+    ("INR", "₹"),
+    ("KRW", "₩"),
+    ("BRL", "R$"),
+    ("CAD", "CA$"),
+    ("AUD", "A$"),
+    ("NZD", "NZ$"),
+    ("MXN", "MX$"),
+    ("CHF", "CHF "),
+];
+
+/// Decimal places of the minor unit of `currency`, e.g. 2 for USD cents.
+pub fn minor_unit_exponent(currency: &str) -> u32 {
+    MINOR_UNIT_EXPONENTS
+        .iter()
+        .find(|(code, _)| *code == currency)
+        .map_or(2, |(_, exponent)| *exponent)
+}
+
+/// Format an amount in cents for display, e.g. `123456` USD as `$1,234.56`.
+pub fn format_cents(amount_cents: f64, currency: &str) -> String {
+    let currency = currency.to_uppercase();
+    let exponent = minor_unit_exponent(&currency);
+    let amount = amount_cents.abs() / 10f64.powi(exponent as i32);
+
+    let formatted = format!("{amount:.prec$}", prec = exponent as usize);
+    let (units, decimals) = formatted
+        .split_once('.')
+        .map_or((formatted.as_str(), None), |(units, decimals)| {
+            (units, Some(decimals))
+        });
+
+    let mut grouped = String::new();
+    for (i, digit) in units.chars().enumerate() {
+        if i > 0 && (units.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if let Some(decimals) = decimals {
+        grouped.push('.');
+        grouped.push_str(decimals);
+    }
+
+    let sign = if amount_cents < 0.0 && amount > 0.0 {
+        "-"
+    } else {
+        ""
+    };
+    match SYMBOLS.iter().find(|(code, _)| *code == currency) {
+        Some((_, symbol)) => format!("{sign}{symbol}{grouped}"),
+        None => format!("{sign}{grouped} {currency}"),
+    }
+}
+
+fn cents_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Add a display value next to every `*_cents` field of a JSON value, in place.
+///
+/// `total_amount_cents` gets a `total_amount_display` sibling. The currency is taken from
+/// the matching `*_currency` field (`amount_currency` for `amount_cents`), then from the
+/// object's `currency`, then from the closest enclosing object that has one. Amounts
+/// without a known currency are left alone.
+pub fn annotate_amounts(value: &mut Value) {
+    annotate(value, None);
+}
+
+fn annotate(value: &mut Value, inherited_currency: Option<&str>) {
+    match value {
+        Value::Object(map) => {
+            let currency = map
+                .get("currency")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .or_else(|| inherited_currency.map(str::to_string));
+
+            annotate_object(map, currency.as_deref());
+
+            for value in map.values_mut() {
+                annotate(value, currency.as_deref());
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                annotate(item, inherited_currency);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn annotate_object(map: &mut Map<String, Value>, currency: Option<&str>) {
+    let displays: Vec<(String, String)> = map
+        .iter()
+        .filter_map(|(key, value)| {
+            let prefix = key.strip_suffix("_cents")?;
+            let display_key = format!("{prefix}_display");
+            if map.contains_key(&display_key) {
+                return None;
+            }
+
+            let amount_cents = cents_value(value)?;
+            let currency = map
+                .get(&format!("{prefix}_currency"))
+                .and_then(Value::as_str)
+                .or(currency)?;
+
+            Some((display_key, format_cents(amount_cents, currency)))
+        })
+        .collect();
+
+    for (display_key, display) in displays {
+        map.insert(display_key, Value::String(display));
+    }
+}
@@ -0,0 +1,130 @@
+use std::{collections::HashMap, future::Future, sync::LazyLock};
+
+use lago_types::error::LagoError;
+use reqwest::{
+    RequestBuilder, Response, StatusCode,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{api_version, audit, backoff, errors, redaction, relay, settings};
+
+/// Concurrent Lago API requests allowed when `LAGO_MCP_MAX_CONCURRENT_REQUESTS` is unset.
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 16;
+
+/// Requests in flight to the Lago API, shared by every tool and session, so a burst of
+/// parallel calls queues here instead of tripping Lago's rate limits or exhausting sockets.
+static PERMITS: LazyLock<Semaphore> = LazyLock::new(|| {
+    let permits = match settings::var("LAGO_MCP_MAX_CONCURRENT_REQUESTS") {
+        Some(value) => match value.trim().parse::<usize>() {
+            Ok(permits) if permits > 0 => permits,
+            _ => {
+                tracing::warn!(value, "Ignoring invalid LAGO_MCP_MAX_CONCURRENT_REQUESTS");
+                DEFAULT_MAX_CONCURRENT_REQUESTS
+            }
+        },
+        None => DEFAULT_MAX_CONCURRENT_REQUESTS,
+    };
+    Semaphore::new(permits)
+});
+
+/// Wait for a slot to send a request to Lago, held until the permit is dropped.
+pub async fn permit() -> SemaphorePermit<'static> {
+    if PERMITS.available_permits() == 0 {
+        tracing::debug!("Waiting for a free Lago API request slot");
+    }
+    PERMITS
+        .acquire()
+        .await
+        .expect("the Lago request semaphore is never closed")
+}
+
+/// Send a request built by `build_request` to Lago once a slot is free, noting the id Lago
+/// gives it for the audit trail.
+///
+/// Requests rate limited by Lago are built again and retried after the delay it asks for, as
+/// long as the tool call has time left. Returns Lago's last response, with the slot it holds
+/// unless it is a rate limit.
+pub async fn send(
+    build_request: impl Fn() -> RequestBuilder,
+) -> Result<(Response, Option<SemaphorePermit<'static>>), reqwest::Error> {
+    let mut retries = 0;
+    loop {
+        let permit = permit().await;
+        let response = build_request().send().await?;
+        if let Some(request_id) = response
+            .headers()
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+        {
+            audit::record_lago_request_id(request_id);
+        }
+
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok((response, Some(permit)));
+        }
+        // Free the slot while waiting, for requests that aren't rate limited.
+        drop(permit);
+        if !backoff::wait_to_retry(backoff::retry_after(response.headers()), retries).await {
+            return Ok((response, None));
+        }
+        retries += 1;
+    }
+}
+
+/// Run a `lago-client` request, whose HTTP requests are sent with [`send`], noting its error
+/// as the failure of the tool call.
+pub async fn limited<T>(
+    request: impl Future<Output = Result<T, LagoError>>,
+) -> Result<T, LagoError> {
+    errors::forget();
+    let result = relay::serve(request).await;
+    if let Err(e) = &result {
+        errors::record_lago_client_error(e);
+    }
+    result
+}
+
+/// Headers added to every request sent to Lago: the pinned API version, and the extra headers
+/// from `LAGO_MCP_EXTRA_HEADERS`, e.g. `{"User-Agent": "billing-agent/1.0"}`.
+///
+/// Header values may be gateway credentials, so they are redacted like API keys.
+static HEADERS: LazyLock<HeaderMap> = LazyLock::new(|| {
+    let mut headers = HeaderMap::new();
+
+    if let Some(extra_headers) = settings::var("LAGO_MCP_EXTRA_HEADERS") {
+        let extra_headers: HashMap<String, String> = serde_json::from_str(&extra_headers)
+            .unwrap_or_else(|e| {
+                tracing::error!(error = %e, "Ignoring invalid LAGO_MCP_EXTRA_HEADERS");
+                HashMap::new()
+            });
+        for (name, value) in extra_headers {
+            match (
+                HeaderName::try_from(name.as_str()),
+                HeaderValue::try_from(value.as_str()),
+            ) {
+                (Ok(header_name), Ok(header_value)) => {
+                    redaction::register_secret(&value);
+                    headers.insert(header_name, header_value);
+                }
+                _ => tracing::warn!(header = %name, "Ignoring invalid extra header"),
+            }
+        }
+    }
+
+    if let Some(version) = api_version::pinned_version() {
+        match HeaderValue::try_from(version) {
+            Ok(version) => {
+                headers.insert(api_version::API_VERSION_HEADER, version);
+            }
+            Err(_) => tracing::warn!(version, "Ignoring invalid LAGO_API_VERSION header value"),
+        }
+    }
+
+    headers
+});
+
+/// Add the configured headers to a request to Lago.
+pub fn with_headers(request: RequestBuilder) -> RequestBuilder {
+    request.headers(HEADERS.clone())
+}
@@ -0,0 +1,26 @@
+use rmcp::{RoleServer, service::RequestContext};
+
+/// Authenticated caller of the HTTP transport.
+///
+/// Authentication layers insert it into the HTTP request extensions; tools read it back
+/// from the request parts carried by the [`RequestContext`].
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub subject: String,
+    pub tenant: Option<String>,
+    pub roles: Vec<String>,
+    /// Lago API key of the caller's tenant, when credentials are mapped from the identity.
+    pub api_key: Option<String>,
+    /// Whether the caller may use tools that modify Lago data.
+    pub can_write: bool,
+}
+
+impl Principal {
+    pub fn from_context(context: &RequestContext<RoleServer>) -> Option<&Self> {
+        context
+            .extensions
+            .get::<axum::http::request::Parts>()?
+            .extensions
+            .get::<Self>()
+    }
+}
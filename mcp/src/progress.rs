@@ -0,0 +1,58 @@
+use std::future::Future;
+
+use rmcp::{
+    Peer, RoleServer,
+    model::{ProgressNotificationParam, ProgressToken},
+    service::RequestContext,
+};
+
+/// Where to send the progress of a tool call, when its client asked for it.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    peer: Peer<RoleServer>,
+    token: ProgressToken,
+}
+
+impl ProgressReporter {
+    /// The reporter of a request carrying a progress token.
+    pub fn from_context(context: &RequestContext<RoleServer>) -> Option<Self> {
+        let token = context.meta.get_progress_token()?;
+
+        Some(Self {
+            peer: context.peer.clone(),
+            token,
+        })
+    }
+}
+
+tokio::task_local! {
+    static CALL_PROGRESS: ProgressReporter;
+}
+
+/// Run a tool call whose progress is reported to `reporter`, when given.
+pub async fn scope<F: Future>(reporter: Option<ProgressReporter>, call: F) -> F::Output {
+    match reporter {
+        Some(reporter) => CALL_PROGRESS.scope(reporter, call).await,
+        None => call.await,
+    }
+}
+
+/// Notify the client of the progress of the current tool call, if it asked for it.
+///
+/// Long operations (walking pages, sending batches) call this after each step so that
+/// clients can show progress instead of appearing hung.
+pub async fn report(progress: usize, total: Option<usize>, message: impl Into<String>) {
+    let Ok(reporter) = CALL_PROGRESS.try_with(Clone::clone) else {
+        return;
+    };
+
+    let notification = ProgressNotificationParam {
+        progress_token: reporter.token,
+        progress: progress as f64,
+        total: total.map(|total| total as f64),
+        message: Some(message.into()),
+    };
+    if let Err(e) = reporter.peer.notify_progress(notification).await {
+        tracing::debug!(error = %e, "Failed to send progress notification");
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{LazyLock, Mutex},
+};
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use rmcp::{RoleServer, service::RequestContext};
+use serde_json::Value;
+
+use crate::credentials::SessionCredentials;
+use crate::principal::Principal;
+use crate::settings;
+use crate::tools::is_write_tool;
+
+/// Scope of the limits counting every tool call.
+const ALL_TOOLS: &str = "*";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Window {
+    Hour,
+    Day,
+}
+
+impl Window {
+    fn duration(self) -> Duration {
+        match self {
+            Self::Hour => Duration::hours(1),
+            Self::Day => Duration::days(1),
+        }
+    }
+
+    fn start(self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now.duration_trunc(self.duration()).unwrap_or(now)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hour => "hour",
+            Self::Day => "day",
+        }
+    }
+}
+
+/// Call budgets per credential, read from the environment. Unset limits are unlimited.
+struct QuotaLimits {
+    calls_per_hour: Option<u64>,
+    calls_per_day: Option<u64>,
+    /// Applied to each write tool separately.
+    write_calls_per_hour: Option<u64>,
+    write_calls_per_day: Option<u64>,
+}
+
+static LIMITS: LazyLock<QuotaLimits> = LazyLock::new(|| {
+    let limit = |name: &str| {
+        let value = settings::var(name)?;
+        let parsed = value.trim().parse().ok();
+        if parsed.is_none() {
+            tracing::warn!(name, value, "Ignoring invalid quota limit");
+        }
+        parsed
+    };
+
+    QuotaLimits {
+        calls_per_hour: limit("LAGO_MCP_QUOTA_CALLS_PER_HOUR"),
+        calls_per_day: limit("LAGO_MCP_QUOTA_CALLS_PER_DAY"),
+        write_calls_per_hour: limit("LAGO_MCP_QUOTA_WRITE_CALLS_PER_HOUR"),
+        write_calls_per_day: limit("LAGO_MCP_QUOTA_WRITE_CALLS_PER_DAY"),
+    }
+});
+
+/// (credential, window, scope) -> (window start, calls in the window)
+type UsageKey = (String, Window, String);
+type Usage = (DateTime<Utc>, u64);
+
+static USAGE: LazyLock<Mutex<HashMap<UsageKey, Usage>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Limits applying to a call of `tool_name`, as (window, scope, limit).
+fn limits_for(tool_name: &str) -> Vec<(Window, &str, u64)> {
+    let mut limits = Vec::new();
+
+    if let Some(limit) = LIMITS.calls_per_hour {
+        limits.push((Window::Hour, ALL_TOOLS, limit));
+    }
+    if let Some(limit) = LIMITS.calls_per_day {
+        limits.push((Window::Day, ALL_TOOLS, limit));
+    }
+    if is_write_tool(tool_name) {
+        if let Some(limit) = LIMITS.write_calls_per_hour {
+            limits.push((Window::Hour, tool_name, limit));
+        }
+        if let Some(limit) = LIMITS.write_calls_per_day {
+            limits.push((Window::Day, tool_name, limit));
+        }
+    }
+
+    limits
+}
+
+/// Identify the credential a call is made with, without keeping raw API keys around.
+pub fn credential_id(context: &RequestContext<RoleServer>) -> String {
+    if let Some(principal) = Principal::from_context(context) {
+        return match &principal.tenant {
+            Some(tenant) => format!("tenant:{tenant}"),
+            None => format!("subject:{}", principal.subject),
+        };
+    }
+
+    let header_key = context
+        .extensions
+        .get::<axum::http::request::Parts>()
+        .and_then(|parts| parts.headers.get("X-LAGO-API-KEY"))
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| {
+            context
+                .extensions
+                .get::<SessionCredentials>()
+                .map(|credentials| credentials.api_key.as_str())
+        });
+
+    match header_key {
+        Some(api_key) => {
+            let mut hasher = DefaultHasher::new();
+            api_key.hash(&mut hasher);
+            format!("api_key:{:016x}", hasher.finish())
+        }
+        None => "environment".to_string(),
+    }
+}
+
+/// Count a call of `tool_name` against the credential's budgets.
+///
+/// Nothing is counted when a budget is exhausted; the error explains which one and when
+/// it resets.
+pub fn consume(credential: &str, tool_name: &str) -> Result<(), String> {
+    let limits = limits_for(tool_name);
+    if limits.is_empty() {
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let mut usage = USAGE.lock().unwrap_or_else(|e| e.into_inner());
+
+    for (window, scope, limit) in &limits {
+        let window_start = window.start(now);
+        let used = usage
+            .get(&(credential.to_string(), *window, scope.to_string()))
+            .filter(|(start, _)| *start == window_start)
+            .map_or(0, |(_, count)| *count);
+
+        if used >= *limit {
+            let subject = if *scope == ALL_TOOLS {
+                "tool calls".to_string()
+            } else {
+                format!("{scope} calls")
+            };
+            return Err(format!(
+                "Quota exceeded: {used}/{limit} {subject} used this {}. The quota resets at {}",
+                window.name(),
+                (window_start + window.duration()).to_rfc3339()
+            ));
+        }
+    }
+
+    for (window, scope, _) in limits {
+        let window_start = window.start(now);
+        let entry = usage
+            .entry((credential.to_string(), window, scope.to_string()))
+            .or_insert((window_start, 0));
+        if entry.0 != window_start {
+            *entry = (window_start, 0);
+        }
+        entry.1 += 1;
+    }
+
+    Ok(())
+}
+
+/// Remaining budgets of a credential, in the shape returned by `get_remaining_quota`.
+pub fn remaining(credential: &str) -> Value {
+    let now = Utc::now();
+    let usage = USAGE.lock().unwrap_or_else(|e| e.into_inner());
+
+    let describe = |window: Window, scope: &str, limit: u64| {
+        let window_start = window.start(now);
+        let used = usage
+            .get(&(credential.to_string(), window, scope.to_string()))
+            .filter(|(start, _)| *start == window_start)
+            .map_or(0, |(_, count)| *count);
+
+        serde_json::json!({
+            "window": window.name(),
+            "limit": limit,
+            "used": used,
+            "remaining": limit.saturating_sub(used),
+            "resets_at": (window_start + window.duration()).to_rfc3339(),
+        })
+    };
+
+    let mut all_tools = Vec::new();
+    if let Some(limit) = LIMITS.calls_per_hour {
+        all_tools.push(describe(Window::Hour, ALL_TOOLS, limit));
+    }
+    if let Some(limit) = LIMITS.calls_per_day {
+        all_tools.push(describe(Window::Day, ALL_TOOLS, limit));
+    }
+
+    let mut write_tools = serde_json::Map::new();
+    for tool_name in crate::tools::WRITE_TOOLS {
+        let mut windows = Vec::new();
+        if let Some(limit) = LIMITS.write_calls_per_hour {
+            windows.push(describe(Window::Hour, tool_name, limit));
+        }
+        if let Some(limit) = LIMITS.write_calls_per_day {
+            windows.push(describe(Window::Day, tool_name, limit));
+        }
+        if !windows.is_empty() {
+            write_tools.insert(tool_name.to_string(), Value::Array(windows));
+        }
+    }
+
+    let unlimited = all_tools.is_empty() && write_tools.is_empty();
+
+    serde_json::json!({
+        "credential": credential,
+        "unlimited": unlimited,
+        "all_tools": all_tools,
+        "write_tools": write_tools,
+    })
+}
@@ -0,0 +1,178 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{StatusCode, header::RETRY_AFTER},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::ip_allowlist;
+
+/// Number of buckets above which the ones that are full again are dropped.
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+/// Request rates allowed on the `/mcp` route. Unset rates are unlimited.
+#[derive(Clone, Debug, clap::Args)]
+pub struct RateLimitArgs {
+    /// Requests per minute allowed from each client IP
+    #[arg(long, env = "LAGO_MCP_RATE_LIMIT_PER_IP")]
+    pub rate_limit_per_ip: Option<u32>,
+    /// Requests per minute allowed with each Lago API key sent in the X-LAGO-API-KEY header
+    #[arg(long, env = "LAGO_MCP_RATE_LIMIT_PER_API_KEY")]
+    pub rate_limit_per_api_key: Option<u32>,
+}
+
+impl RateLimitArgs {
+    /// Build the limiter, reading client IPs the same way as the IP allowlist.
+    pub fn limiter(&self, trust_forwarded_for: bool) -> Option<Arc<RateLimiter>> {
+        let per_ip = self.rate_limit_per_ip.filter(|rate| *rate > 0);
+        let per_api_key = self.rate_limit_per_api_key.filter(|rate| *rate > 0);
+        if per_ip.is_none() && per_api_key.is_none() {
+            return None;
+        }
+
+        tracing::info!(
+            per_ip = ?per_ip,
+            per_api_key = ?per_api_key,
+            "Rate limiting enabled (requests per minute)"
+        );
+        Some(Arc::new(RateLimiter {
+            per_ip,
+            per_api_key,
+            trust_forwarded_for,
+            buckets: Mutex::new(HashMap::new()),
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Ip(IpAddr),
+    /// Hash of the API key, so that raw keys are not kept around.
+    ApiKey(u64),
+}
+
+/// Token bucket holding up to a minute of requests, refilled continuously.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, per_minute: u32, now: Instant) {
+        let capacity = f64::from(per_minute);
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * capacity / 60.0).min(capacity);
+        self.updated_at = now;
+    }
+
+    /// Time until the bucket holds a whole token again.
+    fn retry_after(&self, per_minute: u32) -> Duration {
+        let missing = (1.0 - self.tokens).max(0.0);
+        Duration::from_secs_f64(missing * 60.0 / f64::from(per_minute))
+    }
+}
+
+pub struct RateLimiter {
+    per_ip: Option<u32>,
+    per_api_key: Option<u32>,
+    trust_forwarded_for: bool,
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Take one token from every bucket the request counts against.
+    ///
+    /// Nothing is taken when a bucket is empty; the error is the time to wait and which
+    /// limit was hit.
+    fn take(&self, keys: &[(BucketKey, u32)]) -> Result<(), (Duration, BucketKey)> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() > MAX_TRACKED_BUCKETS {
+            buckets.retain(|_, bucket| {
+                now.duration_since(bucket.updated_at) < Duration::from_secs(60)
+            });
+        }
+
+        for (key, per_minute) in keys {
+            let bucket = buckets.entry(*key).or_insert(Bucket {
+                tokens: f64::from(*per_minute),
+                updated_at: now,
+            });
+            bucket.refill(*per_minute, now);
+            if bucket.tokens < 1.0 {
+                return Err((bucket.retry_after(*per_minute), *key));
+            }
+        }
+
+        for (key, _) in keys {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn api_key_hash(request: &Request) -> Option<u64> {
+    let api_key = request
+        .headers()
+        .get("X-LAGO-API-KEY")
+        .and_then(|value| value.to_str().ok())?;
+
+    let mut hasher = DefaultHasher::new();
+    api_key.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Reject requests over the per-IP or per-API-key rate with `429` and a `Retry-After` header.
+pub async fn enforce_rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client_ip = ip_allowlist::client_ip(peer, &request, limiter.trust_forwarded_for);
+
+    let mut keys = Vec::with_capacity(2);
+    if let Some(per_ip) = limiter.per_ip {
+        keys.push((BucketKey::Ip(client_ip), per_ip));
+    }
+    if let (Some(per_api_key), Some(api_key_hash)) = (limiter.per_api_key, api_key_hash(&request)) {
+        keys.push((BucketKey::ApiKey(api_key_hash), per_api_key));
+    }
+
+    if let Err((retry_after, key)) = limiter.take(&keys) {
+        let limit = match key {
+            BucketKey::Ip(_) => "client IP",
+            BucketKey::ApiKey(_) => "API key",
+        };
+        let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        tracing::warn!(
+            %client_ip,
+            limit,
+            retry_after_secs,
+            method = %request.method(),
+            path = %request.uri().path(),
+            "Rejected request over the rate limit"
+        );
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, retry_after_secs.to_string())],
+            format!("Too Many Requests: {limit} rate limit exceeded, retry in {retry_after_secs}s"),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
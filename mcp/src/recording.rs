@@ -0,0 +1,405 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use anyhow::{Context, Result};
+use axum::{
+    Router,
+    body::Bytes,
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{settings, tools::default_api_url};
+
+/// Headers of Lago's responses kept in the recordings, besides their content type.
+const RECORDED_HEADERS: &[&str] = &["retry-after", "x-request-id"];
+
+/// Request headers not forwarded to Lago: they describe the connection to the proxy.
+const HOP_HEADERS: &[&str] = &["host", "content-length", "connection"];
+
+/// Lago API interactions, recorded or replayed.
+#[derive(Clone, Debug)]
+pub enum Mode {
+    /// Forward requests to Lago and save every exchange to the directory.
+    Record(PathBuf),
+    /// Answer requests with the exchanges saved in the directory, without reaching Lago.
+    Replay(PathBuf),
+}
+
+/// One request to Lago and its response, stored as a JSON file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Exchange {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    path: String,
+    query: Option<String>,
+    content_type: Option<String>,
+    #[serde(flatten)]
+    body: Body,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    headers: HashMap<String, String>,
+    content_type: Option<String>,
+    #[serde(flatten)]
+    body: Body,
+}
+
+/// A request or response body, stored under `body` with its `body_encoding`: JSON bodies as
+/// JSON, other text as a string, so that recordings can be read and edited, and binary
+/// bodies such as invoice PDFs base64-encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "body_encoding", content = "body", rename_all = "lowercase")]
+enum Body {
+    Json(Value),
+    Utf8(String),
+    Base64(#[serde(with = "base64_bytes")] Vec<u8>),
+}
+
+impl Body {
+    fn new(bytes: &[u8]) -> Self {
+        if let Ok(json) = serde_json::from_slice(bytes) {
+            return Self::Json(json);
+        }
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self::Utf8(text.to_string()),
+            Err(_) => Self::Base64(bytes.to_vec()),
+        }
+    }
+
+    fn to_bytes(&self) -> Bytes {
+        match self {
+            Self::Json(json) => Bytes::from(json.to_string()),
+            Self::Utf8(text) => Bytes::from(text.clone()),
+            Self::Base64(bytes) => Bytes::from(bytes.clone()),
+        }
+    }
+}
+
+mod base64_bytes {
+    use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64.encode(body))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        BASE64.decode(encoded).map_err(D::Error::custom)
+    }
+}
+
+impl RecordedRequest {
+    /// Requests are replayed by method, path and query, in the order they were recorded.
+    /// Bodies are not compared: they often carry timestamps of the time they were sent.
+    fn key(&self) -> String {
+        format!(
+            "{} {}?{}",
+            self.method,
+            self.path,
+            self.query.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+struct Recorder {
+    directory: PathBuf,
+    upstream: String,
+    http_client: reqwest::Client,
+    recorded: AtomicUsize,
+}
+
+/// Recorded exchanges not replayed yet, by request key.
+type Replayer = Mutex<HashMap<String, VecDeque<Exchange>>>;
+
+/// Start the recording or replaying proxy on a loopback port and point the server at it.
+pub async fn start(mode: Mode) -> Result<()> {
+    let router = match &mode {
+        Mode::Record(directory) => {
+            std::fs::create_dir_all(directory).with_context(|| {
+                format!(
+                    "Failed to create recording directory {}",
+                    directory.display()
+                )
+            })?;
+            let upstream = default_api_url();
+            let recorder = Recorder {
+                directory: directory.clone(),
+                // Requests are sent to the root of the Lago instance, so `/health` is
+                // recorded as well as the API.
+                upstream: upstream
+                    .trim_end_matches('/')
+                    .trim_end_matches("/api/v1")
+                    .to_string(),
+                http_client: reqwest::Client::new(),
+                recorded: AtomicUsize::new(count_recordings(directory)?),
+            };
+            tracing::warn!(
+                directory = %directory.display(),
+                %upstream,
+                "Recording Lago API interactions: recordings hold customer data"
+            );
+            Router::new()
+                .fallback(record)
+                .with_state(Arc::new(recorder))
+        }
+        Mode::Replay(directory) => {
+            let exchanges = load_recordings(directory)?;
+            tracing::warn!(
+                directory = %directory.display(),
+                exchanges = exchanges.values().map(VecDeque::len).sum::<usize>(),
+                "Replaying recorded Lago API interactions, Lago is not reached"
+            );
+            Router::new()
+                .fallback(replay)
+                .with_state(Arc::new(Mutex::new(exchanges)))
+        }
+    };
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("Failed to start the recording proxy")?;
+    let address = listener.local_addr()?;
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            tracing::error!(error = %e, "Recording proxy stopped");
+        }
+    });
+
+    let proxy_url = format!("http://{address}/api/v1");
+    settings::set("LAGO_REGION", proxy_url.clone());
+    settings::set("LAGO_API_URL", proxy_url);
+    if matches!(mode, Mode::Replay(_)) && settings::var("LAGO_API_KEY").is_none() {
+        settings::set("LAGO_API_KEY", "replay".to_string());
+    }
+
+    Ok(())
+}
+
+fn recording_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(directory)
+        .with_context(|| format!("Failed to read recordings {}", directory.display()))?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            files.push(path);
+        }
+    }
+    // Files are numbered in recording order.
+    files.sort();
+    Ok(files)
+}
+
+fn count_recordings(directory: &Path) -> Result<usize> {
+    Ok(recording_files(directory)?.len())
+}
+
+fn load_recordings(directory: &Path) -> Result<HashMap<String, VecDeque<Exchange>>> {
+    let mut exchanges: HashMap<String, VecDeque<Exchange>> = HashMap::new();
+    for path in recording_files(directory)? {
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read recording {}", path.display()))?;
+        let exchange: Exchange = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid recording {}", path.display()))?;
+        exchanges
+            .entry(exchange.request.key())
+            .or_default()
+            .push_back(exchange);
+    }
+    Ok(exchanges)
+}
+
+fn recorded_request(
+    method: &Method,
+    uri: &axum::http::Uri,
+    headers: &HeaderMap,
+    body: &Bytes,
+) -> RecordedRequest {
+    RecordedRequest {
+        method: method.to_string(),
+        path: uri.path().to_string(),
+        query: uri.query().map(str::to_string),
+        content_type: content_type(headers),
+        body: Body::new(body),
+    }
+}
+
+fn content_type(headers: &HeaderMap) -> Option<String> {
+    let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+    Some(content_type.to_string())
+}
+
+async fn record(State(recorder): State<Arc<Recorder>>, request: Request) -> Response {
+    let (parts, body) = request.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, usize::MAX).await else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    let url = format!(
+        "{}{}",
+        recorder.upstream,
+        parts
+            .uri
+            .path_and_query()
+            .map(|path| path.as_str())
+            .unwrap_or("/")
+    );
+    let mut headers = parts.headers.clone();
+    for name in HOP_HEADERS {
+        headers.remove(*name);
+    }
+    let response = match recorder
+        .http_client
+        .request(parts.method.clone(), &url)
+        .headers(headers)
+        .body(body.clone())
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::error!(%url, error = %e, "Failed to reach Lago while recording");
+            return StatusCode::BAD_GATEWAY.into_response();
+        }
+    };
+
+    let status = response.status();
+    let response_headers: HashMap<String, String> = RECORDED_HEADERS
+        .iter()
+        .filter_map(|name| {
+            let value = response.headers().get(*name)?.to_str().ok()?;
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect();
+    let response_content_type = content_type(response.headers());
+    let response_body = response.bytes().await.unwrap_or_default();
+
+    let exchange = Exchange {
+        request: recorded_request(&parts.method, &parts.uri, &parts.headers, &body),
+        response: RecordedResponse {
+            status: status.as_u16(),
+            headers: response_headers,
+            content_type: response_content_type,
+            body: Body::new(&response_body),
+        },
+    };
+    let index = recorder.recorded.fetch_add(1, Ordering::Relaxed);
+    let path = recorder.directory.join(format!("{index:06}.json"));
+    let written = serde_json::to_vec_pretty(&exchange)
+        .map_err(std::io::Error::other)
+        .and_then(|contents| std::fs::write(&path, contents));
+    if let Err(e) = written {
+        tracing::error!(path = %path.display(), error = %e, "Failed to save a recording");
+    }
+
+    respond(&exchange.response)
+}
+
+async fn replay(
+    State(replayer): State<Arc<Replayer>>,
+    method: Method,
+    uri: axum::http::Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let request = recorded_request(&method, &uri, &headers, &body);
+    let exchange = replayer
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_mut(&request.key())
+        .and_then(VecDeque::pop_front);
+
+    match exchange {
+        Some(exchange) => respond(&exchange.response),
+        None => {
+            tracing::warn!(request = %request.key(), "No recorded response left for the request");
+            let body = serde_json::json!({
+                "status": 404,
+                "error": "Not Found",
+                "code": "not_recorded",
+            });
+            (StatusCode::NOT_FOUND, axum::Json(body)).into_response()
+        }
+    }
+}
+
+fn respond(response: &RecordedResponse) -> Response {
+    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut headers = HeaderMap::new();
+    for (name, value) in &response.headers {
+        if let (Ok(name), Ok(value)) = (
+            header::HeaderName::try_from(name.as_str()),
+            HeaderValue::try_from(value.as_str()),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+    if let Some(content_type) = response
+        .content_type
+        .as_deref()
+        .and_then(|content_type| HeaderValue::try_from(content_type).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    (status, headers, response.body.to_bytes()).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn bodies_are_stored_inline_unless_binary() {
+        for (bytes, stored) in [
+            (
+                br#"{"customer":{"external_id":"acme"}}"#.to_vec(),
+                json!({ "body_encoding": "json", "body": { "customer": { "external_id": "acme" } } }),
+            ),
+            (
+                b"Retry later".to_vec(),
+                json!({ "body_encoding": "utf8", "body": "Retry later" }),
+            ),
+            (Vec::new(), json!({ "body_encoding": "utf8", "body": "" })),
+            (
+                b"%PDF-1.7\n\xff\xfe\x00binary".to_vec(),
+                json!({ "body_encoding": "base64", "body": "JVBERi0xLjcK//4AYmluYXJ5" }),
+            ),
+        ] {
+            let response = RecordedResponse {
+                status: 200,
+                headers: HashMap::new(),
+                content_type: None,
+                body: Body::new(&bytes),
+            };
+            let serialized = serde_json::to_value(&response).unwrap();
+            assert_eq!(serialized["body_encoding"], stored["body_encoding"]);
+            assert_eq!(serialized["body"], stored["body"]);
+
+            let replayed: RecordedResponse = serde_json::from_value(serialized).unwrap();
+            assert_eq!(replayed.body.to_bytes(), Bytes::from(bytes));
+        }
+    }
+}
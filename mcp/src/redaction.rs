@@ -0,0 +1,353 @@
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    sync::{LazyLock, RwLock},
+};
+
+use serde_json::Value;
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::settings;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Secrets shorter than this are not tracked, so that redacting them can't mangle ordinary
+/// words.
+const MIN_SECRET_LEN: usize = 8;
+
+/// API keys and tokens known to the server, scrubbed from any text leaving it.
+///
+/// Seeded with the configured credentials, and completed with the keys received from clients
+/// as they are used.
+static SECRETS: LazyLock<RwLock<HashSet<String>>> = LazyLock::new(|| {
+    let mut secrets = HashSet::new();
+    secrets.extend(settings::var("LAGO_API_KEY"));
+    if let Some(tokens) = settings::var("LAGO_MCP_BEARER_TOKENS") {
+        secrets.extend(tokens.split(',').map(|token| token.trim().to_string()));
+    }
+    if let Some(tenant_api_keys) = settings::var("LAGO_MCP_TENANT_API_KEYS")
+        && let Ok(keys) = serde_json::from_str::<serde_json::Map<String, Value>>(&tenant_api_keys)
+    {
+        secrets.extend(keys.values().filter_map(Value::as_str).map(str::to_string));
+    }
+    secrets.retain(|secret| secret.len() >= MIN_SECRET_LEN);
+    RwLock::new(secrets)
+});
+
+/// Remember a secret, such as an API key sent by a client, so it is redacted from logs and
+/// error messages.
+pub fn register_secret(secret: &str) {
+    if secret.len() < MIN_SECRET_LEN {
+        return;
+    }
+    if SECRETS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(secret)
+    {
+        return;
+    }
+    SECRETS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(secret.to_string());
+}
+
+/// Object keys whose values are secrets or customer PII and must never be echoed back.
+const SENSITIVE_KEYS: [&str; 17] = [
+    "api_key",
+    "apikey",
+    "authorization",
+    "x-lago-api-key",
+    "password",
+    "secret",
+    "client_secret",
+    "token",
+    "access_token",
+    "refresh_token",
+    "email",
+    "phone",
+    "tax_identification_number",
+    "legal_number",
+    "address_line1",
+    "address_line2",
+    "zipcode",
+];
+
+fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SENSITIVE_KEYS.contains(&key.as_str())
+        || key.ends_with("_api_key")
+        || key.ends_with("_secret")
+        || key.ends_with("_token")
+        || key.ends_with("_email")
+}
+
+/// Redact secrets and PII from a JSON value in place.
+///
+/// Values stored under sensitive keys are replaced entirely, and free-form strings are
+/// scrubbed as with [`redact_text`]. Strings holding serialized JSON (as API
+/// log bodies often do) are parsed, redacted and re-serialized.
+pub fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive_key(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::String(text) => {
+            if let Ok(mut nested @ (Value::Object(_) | Value::Array(_))) =
+                serde_json::from_str::<Value>(text)
+            {
+                redact_value(&mut nested);
+                *text = nested.to_string();
+            } else {
+                *text = redact_text(text);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Scrub known secrets, bearer tokens, email addresses, phone numbers and VAT numbers from
+/// free-form text.
+pub fn redact_text(text: &str) -> String {
+    let text = redact_phone_numbers(&redact_secrets(text));
+    let mut redacted = Vec::new();
+    let mut after_bearer = false;
+
+    for word in text.split(' ') {
+        if after_bearer && !word.is_empty() {
+            redacted.push(REDACTED.to_string());
+            after_bearer = false;
+            continue;
+        }
+
+        after_bearer = word.eq_ignore_ascii_case("bearer");
+
+        if looks_like_email(word) || looks_like_tax_id(word) {
+            redacted.push(REDACTED.to_string());
+        } else {
+            redacted.push(word.to_string());
+        }
+    }
+
+    redacted.join(" ")
+}
+
+fn redact_secrets(text: &str) -> String {
+    let secrets = SECRETS.read().unwrap_or_else(|e| e.into_inner());
+    let mut text = text.to_string();
+    for secret in secrets.iter() {
+        if text.contains(secret.as_str()) {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+    }
+    text
+}
+
+fn looks_like_email(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+    match word.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty() && domain.contains('.') && !domain.starts_with('.')
+        }
+        None => false,
+    }
+}
+
+/// Country prefixes of VAT numbers, e.g. `FR` in `FR40303265045`.
+const TAX_ID_PREFIXES: [&str; 31] = [
+    "AT", "BE", "BG", "CH", "CY", "CZ", "DE", "DK", "EE", "EL", "ES", "FI", "FR", "GB", "HR", "HU",
+    "IE", "IT", "LT", "LU", "LV", "MT", "NL", "NO", "PL", "PT", "RO", "SE", "SI", "SK", "XI",
+];
+
+/// A VAT number: a country prefix followed by 8 to 12 letters and digits, mostly digits.
+fn looks_like_tax_id(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+    let Some(number) = TAX_ID_PREFIXES
+        .iter()
+        .find_map(|prefix| word.strip_prefix(prefix))
+    else {
+        return false;
+    };
+    (8..=12).contains(&number.len())
+        && number
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_ascii_uppercase())
+        && number.chars().filter(char::is_ascii_digit).count() >= 7
+}
+
+/// Characters a phone number is written with, besides digits.
+fn is_phone_separator(c: char) -> bool {
+    matches!(c, ' ' | '-' | '.' | '(' | ')')
+}
+
+/// Replace the phone numbers in `text`: runs of 9 to 15 digits, either international
+/// (`+33 6 12 34 56 78`) or split into groups (`(415) 555-0132`, `06.12.34.56.78`).
+///
+/// Digits glued to letters, bare numbers (amounts, Unix timestamps), dates and IP addresses
+/// are left alone.
+fn redact_phone_numbers(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut redacted = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let at_boundary = i == 0
+            || !(chars[i - 1].is_alphanumeric() || matches!(chars[i - 1], '_' | '/' | '-' | '+'));
+        if !(at_boundary && (c == '+' || c == '(' || c.is_ascii_digit())) {
+            redacted.push(c);
+            i += 1;
+            continue;
+        }
+
+        let mut end = i + 1;
+        while end < chars.len() && (chars[end].is_ascii_digit() || is_phone_separator(chars[end])) {
+            end += 1;
+        }
+        while end > i + 1 && matches!(chars[end - 1], ' ' | '-' | '.' | '(') {
+            end -= 1;
+        }
+        let glued = end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == ':');
+        let candidate: String = chars[i..end].iter().collect();
+
+        if !glued && looks_like_phone_number(&candidate) {
+            redacted.push_str(REDACTED);
+        } else {
+            redacted.push_str(&candidate);
+        }
+        i = end;
+    }
+
+    redacted
+}
+
+fn looks_like_phone_number(candidate: &str) -> bool {
+    let digits = candidate.chars().filter(char::is_ascii_digit).count();
+    if !(9..=15).contains(&digits) {
+        return false;
+    }
+    if candidate.starts_with('+') {
+        return true;
+    }
+
+    let groups: Vec<&str> = candidate
+        .split(is_phone_separator)
+        .filter(|group| !group.is_empty())
+        .collect();
+    let is_date = candidate.len() >= 10
+        && candidate.as_bytes()[4] == b'-'
+        && groups.first().is_some_and(|year| year.len() == 4);
+    let is_ip_address = groups.len() == 4
+        && candidate.chars().filter(|c| *c == '.').count() == 3
+        && groups.iter().all(|group| group.len() <= 3);
+    groups.len() > 1 && !is_date && !is_ip_address
+}
+
+/// Log writer redacting each event before it reaches stderr.
+///
+/// Events are JSON lines: fields under sensitive keys are replaced and the other strings
+/// scrubbed, as with [`redact_value`]. Anything that isn't JSON is scrubbed as text.
+pub struct RedactingStderr;
+
+impl<'a> MakeWriter<'a> for RedactingStderr {
+    type Writer = RedactingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(Vec::new())
+    }
+}
+
+/// Buffers one event, written out redacted when dropped.
+pub struct RedactingWriter(Vec<u8>);
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RedactingWriter {
+    fn drop(&mut self) {
+        if self.0.is_empty() {
+            return;
+        }
+
+        let line = String::from_utf8_lossy(&self.0);
+        let redacted = match serde_json::from_str::<Value>(line.trim_end()) {
+            Ok(mut event) => {
+                redact_value(&mut event);
+                format!("{event}\n")
+            }
+            Err(_) => redact_text(&line),
+        };
+        let _ = io::stderr().write_all(redacted.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phone_numbers_and_tax_ids_are_redacted_from_lago_errors() {
+        let error = r#"Failed to update customer: HTTP 422 Unprocessable Entity: {"status":422,"error":"Unprocessable Entity","code":"validation_errors","error_details":{"tax_identification_number":["invalid_format"]}} (tax_identification_number FR40303265045, phone +33 6 12 34 56 78)"#;
+        let redacted = redact_text(error);
+        assert!(!redacted.contains("FR40303265045"), "{redacted}");
+        assert!(!redacted.contains("12 34 56 78"), "{redacted}");
+        assert!(
+            redacted.contains(r#""code":"validation_errors""#),
+            "{redacted}"
+        );
+        assert!(
+            redacted.contains("HTTP 422 Unprocessable Entity"),
+            "{redacted}"
+        );
+
+        for (text, number) in [
+            (
+                "Customer acme phone (415) 555-0132 is not a mobile",
+                "555-0132",
+            ),
+            (
+                "Contact billing at 06.12.34.56.78 for the dunning",
+                "06.12.34.56.78",
+            ),
+            ("VAT number DE123456789 is not registered", "DE123456789"),
+            (
+                "VAT number NL123456789B01 is not registered",
+                "NL123456789B01",
+            ),
+        ] {
+            let redacted = redact_text(text);
+            assert!(!redacted.contains(number), "{redacted}");
+            assert!(redacted.contains(REDACTED), "{redacted}");
+        }
+    }
+
+    #[test]
+    fn identifiers_amounts_and_dates_are_kept() {
+        for text in [
+            r#"Failed to get invoice: HTTP 404 Not Found: {"status":404,"error":"Not Found","code":"invoice_not_found"}"#,
+            "Invoice 1a901a90-1a90-1a90-1a90-1a901a901a90 for ACME-2024-0001 is finalized",
+            "Refusing to create a payment of 150000 cents: the invoice's remaining amount due is 120000 cents EUR",
+            "Event timestamp 1704067200 is before 2024-01-01 00:00:00 UTC",
+            "Subscription sub_123456789 started on 2024-01-01T00:00:00Z",
+            "Lago at 192.168.100.200 answered HTTP 503 Service Unavailable",
+        ] {
+            assert_eq!(redact_text(text), text);
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use axum::{
+    Router,
+    body::Bytes,
+    extract::{Path, RawQuery},
+    http::{
+        HeaderMap, HeaderName, Method, StatusCode,
+        header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, USER_AGENT},
+    },
+    response::{IntoResponse, Response},
+    routing::any,
+};
+use tokio::sync::{OnceCell, mpsc, oneshot};
+
+use crate::{
+    errors::{self, ErrorCode},
+    outbound,
+};
+
+/// Headers of `lago-client` requests forwarded to Lago.
+const FORWARDED_HEADERS: &[HeaderName] = &[ACCEPT, AUTHORIZATION, CONTENT_TYPE, USER_AGENT];
+
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+/// Address of the relay, started with the first tool call that builds a `lago-client`.
+static ADDRESS: OnceCell<Option<SocketAddr>> = OnceCell::const_new();
+
+/// Lago API URL of a route, and the tool call its requests are sent from.
+type Route = (String, mpsc::UnboundedSender<Job>);
+
+/// Routes by token. Tokens are random 128-bit values, so that another process of the host
+/// can't guess the route of a tool call and have its requests sent with the call's API key.
+static ROUTES: LazyLock<Mutex<HashMap<String, Route>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// A `lago-client` request waiting to be sent to Lago by its tool call.
+struct Job {
+    method: Method,
+    url: String,
+    headers: HeaderMap,
+    body: Bytes,
+    reply: oneshot::Sender<Response>,
+}
+
+/// Relay of one tool call: the requests of its `lago-client`s, and the routes they use,
+/// removed when the tool call finishes or is cancelled.
+struct CallRelay {
+    jobs: mpsc::UnboundedSender<Job>,
+    pending: tokio::sync::Mutex<mpsc::UnboundedReceiver<Job>>,
+    routes: Mutex<Vec<String>>,
+}
+
+impl Drop for CallRelay {
+    fn drop(&mut self) {
+        let routes = self.routes.get_mut().unwrap_or_else(|e| e.into_inner());
+        let mut all_routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+        for route in routes.iter() {
+            all_routes.remove(route);
+        }
+    }
+}
+
+tokio::task_local! {
+    static CALL_RELAY: Arc<CallRelay>;
+}
+
+/// Run a tool call whose `lago-client` requests are relayed through it.
+///
+/// `lago-client` sends its requests with its own HTTP client, from outside the tool call.
+/// Pointed at the relay, they are handed back to the tool call and sent from there, so they
+/// are tracked like the requests of [`crate::tools::lago_request`].
+pub async fn scope<F: Future>(call: F) -> F::Output {
+    let (jobs, pending) = mpsc::unbounded_channel();
+    let relay = CallRelay {
+        jobs,
+        pending: tokio::sync::Mutex::new(pending),
+        routes: Mutex::new(Vec::new()),
+    };
+    CALL_RELAY.scope(Arc::new(relay), Box::pin(call)).await
+}
+
+/// URL a `lago-client` of the current tool call should use to reach the Lago API at
+/// `api_url`: a route of the relay, or `api_url` itself outside of tool calls.
+pub async fn endpoint(api_url: String) -> String {
+    let Ok(relay) = CALL_RELAY.try_with(Arc::clone) else {
+        return api_url;
+    };
+    let Some(address) = *ADDRESS.get_or_init(start).await else {
+        return api_url;
+    };
+
+    let route = format!("{:032x}", rand::random::<u128>());
+    let endpoint = format!("http://{address}/{route}");
+    ROUTES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(route.clone(), (api_url, relay.jobs.clone()));
+    relay
+        .routes
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(route);
+
+    endpoint
+}
+
+/// Run a `lago-client` request, sending the HTTP requests it relays until it completes.
+pub async fn serve<F: Future>(request: F) -> F::Output {
+    let Ok(relay) = CALL_RELAY.try_with(Arc::clone) else {
+        return request.await;
+    };
+    let mut pending = relay.pending.lock().await;
+
+    let mut request = Box::pin(request);
+    loop {
+        tokio::select! {
+            output = &mut request => return output,
+            Some(job) = pending.recv() => {
+                let response = send(job.method, &job.url, job.headers, job.body).await;
+                let _ = job.reply.send(response);
+            }
+        }
+    }
+}
+
+async fn start() -> Option<SocketAddr> {
+    let router = Router::new().route("/{route}/{*path}", any(relay));
+    let listener = match tokio::net::TcpListener::bind("127.0.0.1:0").await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to start the Lago request relay, lago-client requests go to Lago directly");
+            return None;
+        }
+    };
+    let address = listener.local_addr().ok()?;
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            tracing::error!(error = %e, "Lago request relay stopped");
+        }
+    });
+
+    Some(address)
+}
+
+async fn relay(
+    Path((route, path)): Path<(String, String)>,
+    RawQuery(query): RawQuery,
+    method: Method,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let route = ROUTES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&route)
+        .cloned();
+    let Some((api_url, jobs)) = route else {
+        return (StatusCode::BAD_GATEWAY, "Unknown relay route").into_response();
+    };
+
+    let mut url = format!("{}/{path}", api_url.trim_end_matches('/'));
+    if let Some(query) = query {
+        url = format!("{url}?{query}");
+    }
+    let headers = FORWARDED_HEADERS
+        .iter()
+        .filter_map(|name| Some((name.clone(), headers.get(name)?.clone())))
+        .collect();
+
+    let (reply, response) = oneshot::channel();
+    let job = Job {
+        method,
+        url,
+        headers,
+        body,
+        reply,
+    };
+    if jobs.send(job).is_err() {
+        return (StatusCode::BAD_GATEWAY, "The tool call is over").into_response();
+    }
+    response
+        .await
+        .unwrap_or_else(|_| (StatusCode::BAD_GATEWAY, "The tool call is over").into_response())
+}
+
+/// Send a relayed request to Lago from the tool call, and return Lago's response.
+async fn send(method: Method, url: &str, headers: HeaderMap, body: Bytes) -> Response {
+    let build_request = || {
+        let request = HTTP_CLIENT
+            .request(method.clone(), url)
+            .headers(headers.clone())
+            .body(body.clone());
+        outbound::with_headers(request)
+    };
+    let (response, _permit) = match outbound::send(build_request).await {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to send a relayed request to Lago");
+            errors::record(ErrorCode::Network);
+            return (StatusCode::BAD_GATEWAY, e.to_string()).into_response();
+        }
+    };
+
+    let status = response.status();
+    let content_type = response.headers().get(CONTENT_TYPE).cloned();
+    let body = response.bytes().await.unwrap_or_default();
+    let mut relayed = (status, body).into_response();
+    if let Some(content_type) = content_type {
+        relayed.headers_mut().insert(CONTENT_TYPE, content_type);
+    }
+    relayed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn routes_are_random_and_removed_when_the_call_finishes() {
+        let (first, second) = scope(async {
+            let first = endpoint("http://lago.test/api/v1".to_string()).await;
+            let second = endpoint("http://lago.test/api/v1".to_string()).await;
+            (first, second)
+        })
+        .await;
+
+        let route = |endpoint: &str| endpoint.rsplit('/').next().unwrap().to_string();
+        let (first_route, second_route) = (route(&first), route(&second));
+        assert_eq!(first_route.len(), 32);
+        assert!(first_route.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(first_route, second_route);
+
+        {
+            let routes = ROUTES.lock().unwrap();
+            assert!(!routes.contains_key(&first_route));
+            assert!(!routes.contains_key(&second_route));
+        }
+
+        let response = reqwest::get(format!("{first}/customers")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+}
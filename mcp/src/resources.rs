@@ -0,0 +1,118 @@
+use rmcp::{ErrorData as McpError, RoleServer, model::*, service::RequestContext};
+use serde_json::Value;
+
+use crate::money;
+use crate::tools::{FETCH_ALL_MAX_RECORDS, get_lago_api_config, lago_get_all};
+
+/// MIME type of the catalogs, listed and read as JSON documents.
+const MIME_TYPE: &str = "application/json";
+
+/// A catalog published as an MCP resource.
+struct Catalog {
+    uri: &'static str,
+    name: &'static str,
+    description: &'static str,
+    /// Lago list endpoint serving the catalog.
+    path: &'static str,
+    /// Key of the items in the endpoint's response.
+    key: &'static str,
+}
+
+const CATALOGS: [Catalog; 2] = [
+    Catalog {
+        uri: "lago://plans",
+        name: "plans",
+        description: "Every plan of the Lago organization, with its interval, base amount and charges",
+        path: "/plans",
+        key: "plans",
+    },
+    Catalog {
+        uri: "lago://billable_metrics",
+        name: "billable_metrics",
+        description: "Every billable metric of the Lago organization, with its aggregation and filters",
+        path: "/billable_metrics",
+        key: "billable_metrics",
+    },
+];
+
+/// Pricing catalog resources, read live from Lago so that clients can load them as context
+/// without calling tools.
+#[derive(Clone)]
+pub struct CatalogResources {
+    http_client: reqwest::Client,
+}
+
+impl CatalogResources {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn list(&self) -> Vec<Resource> {
+        CATALOGS
+            .iter()
+            .map(|catalog| {
+                let mut resource = RawResource::new(catalog.uri, catalog.name);
+                resource.description = Some(catalog.description.to_string());
+                resource.mime_type = Some(MIME_TYPE.to_string());
+                resource.no_annotation()
+            })
+            .collect()
+    }
+
+    pub async fn read(
+        &self,
+        uri: &str,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let Some(catalog) = CATALOGS.iter().find(|catalog| catalog.uri == uri) else {
+            return Err(McpError::resource_not_found(
+                format!("Unknown resource {uri}"),
+                None,
+            ));
+        };
+
+        let config = get_lago_api_config(context).await.map_err(|error_result| {
+            let message = error_result
+                .content
+                .first()
+                .and_then(|content| content.as_text())
+                .map(|text| text.text.clone())
+                .unwrap_or_else(|| "Lago API configuration is missing".to_string());
+            McpError::internal_error(message, None)
+        })?;
+
+        let (items, truncated) = lago_get_all(
+            &self.http_client,
+            &config,
+            catalog.path,
+            &[],
+            catalog.key,
+            FETCH_ALL_MAX_RECORDS,
+        )
+        .await
+        .map_err(|e| {
+            let error_message = format!("Failed to read {uri}: {e}");
+            tracing::error!("{error_message}");
+            McpError::internal_error(error_message, None)
+        })?;
+
+        let mut contents = serde_json::Map::new();
+        contents.insert("total_count".to_string(), items.len().into());
+        contents.insert("truncated".to_string(), truncated.into());
+        contents.insert(catalog.key.to_string(), Value::Array(items));
+
+        let mut contents = Value::Object(contents);
+        money::annotate_amounts(&mut contents);
+        let text = serde_json::to_string_pretty(&contents).unwrap_or_default();
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some(MIME_TYPE.to_string()),
+                text,
+            }],
+        })
+    }
+}
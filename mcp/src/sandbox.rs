@@ -0,0 +1,864 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use anyhow::{Context, Result};
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Path as UrlPath, Query, State},
+    http::{Method, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{any, get},
+};
+use chrono::{Datelike, Months, NaiveTime, Utc};
+use lago_types::models::{
+    AppliedCoupon, BillableMetric, Coupon, CreditNote, Customer, Event, Invoice, Payment, Plan,
+    Subscription,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+
+use crate::settings;
+
+/// Version reported by the sandbox, recent enough for every tool.
+const SANDBOX_VERSION: &str = "v1.30.0";
+
+/// API key the server uses to reach the sandbox.
+const SANDBOX_API_KEY: &str = "sandbox";
+
+/// Fields identifying a record in the URL of `GET`, `PUT` and `DELETE` requests.
+const ID_FIELDS: &[&str] = &["lago_id", "code", "external_id", "transaction_id", "number"];
+
+/// Fields making a `POST` update an existing record rather than add one, as Lago does for
+/// customers and other resources created by code or external ID.
+const UPSERT_FIELDS: &[&str] = &["code", "external_id", "transaction_id"];
+
+/// Collections whose records refer to records of the collections before them, in the order
+/// fixtures are loaded.
+const LOAD_ORDER: &[&str] = &[
+    "customers",
+    "billable_metrics",
+    "plans",
+    "coupons",
+    "subscriptions",
+    "applied_coupons",
+    "events",
+];
+
+static BASE_URL: OnceLock<String> = OnceLock::new();
+
+/// Records of each collection (`customers`, `plans`...), as Lago returns them.
+type Collections = HashMap<String, Vec<Value>>;
+
+type Store = Arc<Mutex<Collections>>;
+
+/// Whether tool calls are served by the sandbox.
+pub fn enabled() -> bool {
+    BASE_URL.get().is_some()
+}
+
+/// Start the fake Lago backend on a loopback port and point the server at it.
+///
+/// Fixtures are read from `<collection>.json` files holding an array of records (or a Lago
+/// list response); without any, a small demo catalog is loaded.
+pub async fn start(fixtures: Option<&Path>) -> Result<()> {
+    let collections = match fixtures {
+        Some(directory) => load_fixtures(directory)?,
+        None => demo_fixtures(),
+    };
+    let base_url = serve(collections).await?;
+
+    // The sandbox wins over a configured region too, so no call reaches a real Lago.
+    settings::set("LAGO_REGION", base_url.clone());
+    settings::set("LAGO_API_URL", base_url.clone());
+    settings::set("LAGO_API_KEY", SANDBOX_API_KEY.to_string());
+    tracing::warn!(%base_url, "Sandbox mode: tool calls are served by an in-memory fake Lago, no real data is read or changed");
+    let _ = BASE_URL.set(base_url);
+
+    Ok(())
+}
+
+/// Serve the collections on a loopback port, and return the sandbox's API URL.
+async fn serve(collections: Collections) -> Result<String> {
+    let store: Store = Arc::new(Mutex::new(collections));
+
+    let router = Router::new()
+        .route(
+            "/health",
+            get(|| async { Json(json!({ "version": SANDBOX_VERSION })) }),
+        )
+        .route("/api/v1/{*path}", any(handle))
+        .with_state(store);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("Failed to start the sandbox")?;
+    let address: SocketAddr = listener.local_addr()?;
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            tracing::error!(error = %e, "Sandbox stopped");
+        }
+    });
+
+    Ok(format!("http://{address}/api/v1"))
+}
+
+fn load_fixtures(directory: &Path) -> Result<Collections> {
+    let mut fixtures = HashMap::new();
+    let entries = std::fs::read_dir(directory)
+        .with_context(|| format!("Failed to read sandbox fixtures {}", directory.display()))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        let Some(collection) = path.file_stem().and_then(|stem| stem.to_str()).filter(|_| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        }) else {
+            continue;
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read sandbox fixture {}", path.display()))?;
+        let records = match serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid sandbox fixture {}", path.display()))?
+        {
+            Value::Array(records) => records,
+            Value::Object(mut response) => match response.remove(collection) {
+                Some(Value::Array(records)) => records,
+                _ => anyhow::bail!("{} has no {collection} array", path.display()),
+            },
+            _ => anyhow::bail!("{} must hold an array of records", path.display()),
+        };
+        tracing::info!(
+            collection,
+            records = records.len(),
+            "Loaded sandbox fixture"
+        );
+        fixtures.insert(collection.to_string(), records);
+    }
+
+    load(fixtures).map_err(anyhow::Error::msg)
+}
+
+/// Store fixture records as if they were created through the API, so they are completed and
+/// checked like created records.
+fn load(mut fixtures: Collections) -> Result<Collections, String> {
+    let mut names: Vec<String> = LOAD_ORDER
+        .iter()
+        .map(|name| name.to_string())
+        .filter(|name| fixtures.contains_key(name))
+        .collect();
+    let mut others: Vec<String> = fixtures
+        .keys()
+        .filter(|name| !LOAD_ORDER.contains(&name.as_str()))
+        .cloned()
+        .collect();
+    others.sort();
+    names.extend(others);
+
+    let mut store = Collections::new();
+    for name in names {
+        store.entry(name.clone()).or_default();
+        for record in fixtures.remove(&name).unwrap_or_default() {
+            upsert(&mut store, &name, record)
+                .map_err(|e| format!("Invalid sandbox fixture in {name}: {e}"))?;
+        }
+    }
+    Ok(store)
+}
+
+/// A billable metric, a plan charging it, and a customer subscribed to the plan.
+fn demo_fixtures() -> Collections {
+    let created_at = "2025-01-01T00:00:00Z";
+    let fixtures = HashMap::from([
+        (
+            "billable_metrics".to_string(),
+            vec![json!({
+                "name": "API calls",
+                "code": "api_calls",
+                "description": "Requests made to the API",
+                "aggregation_type": "count_agg",
+                "created_at": created_at,
+            })],
+        ),
+        (
+            "plans".to_string(),
+            vec![json!({
+                "name": "Startup",
+                "code": "startup",
+                "interval": "monthly",
+                "amount_cents": 4900,
+                "amount_currency": "USD",
+                "pay_in_advance": true,
+                "charges": [{
+                    "billable_metric_code": "api_calls",
+                    "charge_model": "standard",
+                    "properties": { "amount": "0.01" },
+                }],
+                "created_at": created_at,
+            })],
+        ),
+        (
+            "customers".to_string(),
+            vec![json!({
+                "external_id": "acme",
+                "name": "Acme Inc",
+                "email": "billing@acme.example",
+                "currency": "USD",
+                "country": "US",
+                "customer_type": "company",
+                "timezone": "America/New_York",
+                "created_at": created_at,
+            })],
+        ),
+        (
+            "subscriptions".to_string(),
+            vec![json!({
+                "external_id": "acme_startup",
+                "external_customer_id": "acme",
+                "plan_code": "startup",
+                "started_at": created_at,
+                "subscription_at": created_at,
+                "created_at": created_at,
+            })],
+        ),
+    ]);
+    load(fixtures).expect("the demo fixtures are valid")
+}
+
+/// Key a record is wrapped in, e.g. `customer` for the `customers` collection.
+fn singular(collection: &str) -> String {
+    match collection {
+        "taxes" => "tax".to_string(),
+        collection => collection
+            .strip_suffix('s')
+            .unwrap_or(collection)
+            .to_string(),
+    }
+}
+
+fn matches_id(record: &Value, id: &str) -> bool {
+    ID_FIELDS
+        .iter()
+        .any(|field| record[*field].as_str() == Some(id))
+}
+
+/// First record of a collection whose `field` is `value`.
+fn find<'a>(store: &'a Collections, collection: &str, field: &str, value: &Value) -> &'a Value {
+    store
+        .get(collection)
+        .and_then(|records| {
+            records
+                .iter()
+                .find(|record| !value.is_null() && record[field] == *value)
+        })
+        .unwrap_or(&Value::Null)
+}
+
+/// Field of a record being built, `null` when it isn't set.
+fn field_of<'a>(record: &'a Map<String, Value>, name: &str) -> &'a Value {
+    record.get(name).unwrap_or(&Value::Null)
+}
+
+/// Value of a record's field, for filters: Lago filters invoices on the external ID of
+/// their customer, which they nest.
+fn field<'a>(record: &'a Value, name: &str) -> &'a Value {
+    match (&record[name], name) {
+        (Value::Null, "external_customer_id") => &record["customer"]["external_id"],
+        (value, _) => value,
+    }
+}
+
+fn error(status: StatusCode, code: String) -> Response {
+    let body = json!({
+        "status": status.as_u16(),
+        "error": status.canonical_reason().unwrap_or_default(),
+        "code": code,
+    });
+    (status, Json(body)).into_response()
+}
+
+/// A `422` for a record the sandbox refused to store.
+fn invalid(message: String) -> Response {
+    let body = json!({
+        "status": 422,
+        "error": "Unprocessable Entity",
+        "code": "validation_errors",
+        "error_details": { "record": [message] },
+    });
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+}
+
+async fn handle(
+    State(store): State<Store>,
+    method: Method,
+    UrlPath(path): UrlPath<String>,
+    Query(query): Query<Vec<(String, String)>>,
+    body: Bytes,
+) -> Response {
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let mut store = store.lock().unwrap_or_else(|e| e.into_inner());
+
+    match (method.as_str(), segments.as_slice()) {
+        ("GET", ["customers", customer_id, "current_usage"]) => {
+            current_usage(&store, customer_id, &query)
+        }
+        (
+            "GET",
+            [
+                "customers",
+                customer_id,
+                collection @ ("invoices" | "payments" | "subscriptions"),
+            ],
+        ) => {
+            let mut query = query.clone();
+            query.push(("external_customer_id".to_string(), customer_id.to_string()));
+            list(
+                store
+                    .get(*collection)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+                collection,
+                &query,
+            )
+        }
+        // No invoices are issued by the sandbox, so there is nothing to report on.
+        ("GET", ["analytics", report]) => Json(json!({ format!("{report}s"): [] })).into_response(),
+        ("GET", [collection]) => list(
+            store
+                .get(*collection)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+            collection,
+            &query,
+        ),
+        ("POST", ["events", "batch"]) => {
+            let events = body["events"].as_array().cloned().unwrap_or_default();
+            let mut created = Vec::new();
+            for event in events {
+                match upsert(&mut store, "events", event) {
+                    Ok(event) => created.push(event),
+                    Err(message) => return invalid(message),
+                }
+            }
+            Json(json!({ "events": created })).into_response()
+        }
+        ("POST", [collection]) => {
+            let key = singular(collection);
+            let record = body.get(&key).cloned().unwrap_or(body);
+            match upsert(&mut store, collection, record) {
+                Ok(record) => Json(json!({ key: record })).into_response(),
+                Err(message) => invalid(message),
+            }
+        }
+        (method @ ("GET" | "PUT" | "DELETE"), [collection, id]) => {
+            let key = singular(collection);
+            let records = store.entry(collection.to_string()).or_default();
+            let Some(index) = records.iter().position(|record| matches_id(record, id)) else {
+                return error(StatusCode::NOT_FOUND, format!("{key}_not_found"));
+            };
+
+            let record = match method {
+                "DELETE" => records.remove(index),
+                "PUT" => {
+                    let mut record = records[index].clone();
+                    if let (Some(fields), Some(Value::Object(changes))) =
+                        (record.as_object_mut(), body.get(&key))
+                    {
+                        fields.extend(changes.clone());
+                        fields.insert("updated_at".to_string(), now());
+                    }
+                    if let Err(message) = check(collection, &record) {
+                        return invalid(message);
+                    }
+                    records[index] = record.clone();
+                    record
+                }
+                _ => records[index].clone(),
+            };
+            Json(json!({ key: record })).into_response()
+        }
+        _ => error(
+            StatusCode::NOT_FOUND,
+            "not_supported_by_sandbox".to_string(),
+        ),
+    }
+}
+
+/// One page of a collection, filtered on the query parameters naming a field of its records.
+///
+/// Records without the field don't match a filter on it.
+fn list(records: &[Value], collection: &str, query: &[(String, String)]) -> Response {
+    let mut page = 1;
+    let mut per_page = 20;
+    let mut filters: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (name, value) in query {
+        match name.as_str() {
+            "page" => page = value.parse().unwrap_or(1).max(1),
+            "per_page" => per_page = value.parse().unwrap_or(20).clamp(1, 100),
+            name => filters
+                .entry(name.trim_end_matches("[]"))
+                .or_default()
+                .push(value),
+        }
+    }
+
+    let matching: Vec<&Value> = records
+        .iter()
+        .filter(|record| {
+            filters
+                .iter()
+                .all(|(name, values)| match field(record, name) {
+                    Value::Null => false,
+                    Value::String(text) => values.contains(&text.as_str()),
+                    value => values.contains(&value.to_string().as_str()),
+                })
+        })
+        .collect();
+
+    let total_count = matching.len();
+    let total_pages = total_count.div_ceil(per_page).max(1);
+    let items: Vec<&Value> = matching
+        .into_iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .collect();
+
+    Json(json!({
+        collection: items,
+        "meta": {
+            "current_page": page,
+            "next_page": (page < total_pages).then_some(page + 1),
+            "prev_page": (page > 1).then(|| page - 1),
+            "total_pages": total_pages,
+            "total_count": total_count,
+        },
+    }))
+    .into_response()
+}
+
+/// Add a record, or update the one sharing its code or external ID.
+///
+/// New records get the fields Lago fills in itself. Records `lago-client` couldn't read are
+/// refused rather than stored, so that one bad call can't break later ones.
+fn upsert(store: &mut Collections, collection: &str, record: Value) -> Result<Value, String> {
+    let Value::Object(record) = record else {
+        return Err(format!("a {} must be an object", singular(collection)));
+    };
+
+    let records = store.get(collection).map(Vec::as_slice).unwrap_or_default();
+    // Events share the code of their metric: only their transaction ID identifies them.
+    let upsert_fields = match collection {
+        "events" => &["transaction_id"],
+        _ => UPSERT_FIELDS,
+    };
+    let existing = upsert_fields.iter().find_map(|field| {
+        let value = record.get(*field)?.as_str()?;
+        records
+            .iter()
+            .position(|existing| existing[*field].as_str() == Some(value))
+    });
+
+    let record = match existing {
+        Some(index) => {
+            let mut updated = records[index].as_object().cloned().unwrap_or_default();
+            updated.extend(record);
+            updated.insert("updated_at".to_string(), now());
+            Value::Object(updated)
+        }
+        None => complete(store, collection, record),
+    };
+    check(collection, &record)?;
+
+    let records = store.entry(collection.to_string()).or_default();
+    match existing {
+        Some(index) => records[index] = record.clone(),
+        None => records.push(record.clone()),
+    }
+    Ok(record)
+}
+
+/// A new record with the fields Lago fills in when they aren't sent.
+fn complete(store: &Collections, collection: &str, mut record: Map<String, Value>) -> Value {
+    let now = now();
+    let defaults = match collection {
+        "customers" => json!({
+            "billing_entity_code": "default",
+            "account_type": "customer",
+            "applicable_timezone": record.get("timezone").filter(|timezone| !timezone.is_null()).cloned().unwrap_or(json!("UTC")),
+            "finalize_zero_amount_invoice": "inherit",
+            "skip_invoice_custom_sections": false,
+            "billing_configuration": {},
+            "shipping_address": {},
+            "metadata": [],
+            "integration_customers": [],
+        }),
+        "subscriptions" => {
+            let customer = find(
+                store,
+                "customers",
+                "external_id",
+                field_of(&record, "external_customer_id"),
+            );
+            json!({
+                "external_id": uuid::Uuid::new_v4().to_string(),
+                "lago_customer_id": customer["lago_id"],
+                "status": "active",
+                "billing_time": "calendar",
+                "started_at": now,
+                "subscription_at": now,
+            })
+        }
+        "plans" => json!({ "pay_in_advance": false, "charges": [] }),
+        "billable_metrics" => json!({ "recurring": false, "filters": [] }),
+        "coupons" => json!({
+            "frequency": "once",
+            "reusable": true,
+            "limited_plans": false,
+            "limited_billable_metrics": false,
+            "expiration": "no_expiration",
+        }),
+        "applied_coupons" => {
+            let coupon = find(store, "coupons", "code", field_of(&record, "coupon_code"));
+            let customer = find(
+                store,
+                "customers",
+                "external_id",
+                field_of(&record, "external_customer_id"),
+            );
+            json!({
+                "lago_coupon_id": coupon["lago_id"],
+                "coupon_name": coupon["name"],
+                "lago_customer_id": customer["lago_id"],
+                "status": "active",
+                "frequency": coupon["frequency"],
+            })
+        }
+        "events" => {
+            let customer = find(
+                store,
+                "customers",
+                "external_id",
+                field_of(&record, "external_customer_id"),
+            );
+            let subscription = find(
+                store,
+                "subscriptions",
+                "external_id",
+                field_of(&record, "external_subscription_id"),
+            );
+            json!({
+                "timestamp": now,
+                "lago_customer_id": customer["lago_id"],
+                "lago_subscription_id": subscription["lago_id"],
+            })
+        }
+        _ => json!({}),
+    };
+    if let Value::Object(defaults) = defaults {
+        for (field, value) in defaults {
+            if record.get(&field).is_none_or(Value::is_null) {
+                record.insert(field, value);
+            }
+        }
+    }
+
+    // Lago takes event timestamps and amounts as numbers, and returns them as strings.
+    if collection == "events" {
+        if let Some(timestamp) = field_of(&record, "timestamp").as_i64() {
+            let timestamp = chrono::DateTime::from_timestamp(timestamp, 0).unwrap_or_default();
+            record.insert("timestamp".to_string(), json!(timestamp.to_rfc3339()));
+        }
+        if let Some(amount) = record
+            .get("precise_total_amount_cents")
+            .filter(|amount| amount.is_number())
+        {
+            record.insert(
+                "precise_total_amount_cents".to_string(),
+                json!(amount.to_string()),
+            );
+        }
+    }
+    if let Some(Value::Array(charges)) = record.get_mut("charges") {
+        for charge in charges.iter_mut().filter_map(Value::as_object_mut) {
+            charge
+                .entry("lago_id")
+                .or_insert_with(|| json!(uuid::Uuid::new_v4()));
+        }
+    }
+
+    record
+        .entry("lago_id")
+        .or_insert_with(|| json!(uuid::Uuid::new_v4()));
+    record.entry("created_at").or_insert_with(|| now.clone());
+    record.entry("updated_at").or_insert(now);
+    Value::Object(record)
+}
+
+/// Check that a record reads as the Lago model of its collection, as `lago-client` reads it.
+fn check(collection: &str, record: &Value) -> Result<(), String> {
+    fn read<T: DeserializeOwned>(record: &Value) -> Result<(), String> {
+        T::deserialize(record).map(drop).map_err(|e| e.to_string())
+    }
+
+    match collection {
+        "customers" => read::<Customer>(record),
+        "subscriptions" => read::<Subscription>(record),
+        "plans" => read::<Plan>(record),
+        "billable_metrics" => read::<BillableMetric>(record),
+        "coupons" => read::<Coupon>(record),
+        "applied_coupons" => read::<AppliedCoupon>(record),
+        "events" => read::<Event>(record),
+        "invoices" => read::<Invoice>(record),
+        "payments" => read::<Payment>(record),
+        "credit_notes" => read::<CreditNote>(record),
+        _ => Ok(()),
+    }
+}
+
+/// Usage of a subscription in the current month, priced from the events it received with
+/// the `standard` charges of its plan.
+fn current_usage(store: &Collections, customer_id: &str, query: &[(String, String)]) -> Response {
+    let subscription_id = query
+        .iter()
+        .find(|(name, _)| name == "external_subscription_id")
+        .map(|(_, value)| value.as_str());
+    let subscription = store
+        .get("subscriptions")
+        .and_then(|subscriptions| {
+            subscriptions.iter().find(|subscription| {
+                subscription["external_customer_id"] == customer_id
+                    && subscription_id.is_none_or(|id| subscription["external_id"] == id)
+            })
+        })
+        .unwrap_or(&Value::Null);
+    if subscription.is_null() {
+        return error(StatusCode::NOT_FOUND, "subscription_not_found".to_string());
+    }
+    let plan = find(store, "plans", "code", &subscription["plan_code"]);
+    if plan.is_null() {
+        return error(StatusCode::NOT_FOUND, "plan_not_found".to_string());
+    }
+
+    let currency = &plan["amount_currency"];
+    let events: Vec<&Value> = store
+        .get("events")
+        .map(|events| {
+            events
+                .iter()
+                .filter(|event| {
+                    event["external_subscription_id"] == subscription["external_id"]
+                        || (event["external_subscription_id"].is_null()
+                            && event["external_customer_id"] == customer_id)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let charges = plan["charges"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let charges_usage: Vec<Value> = charges
+        .iter()
+        .map(|charge| {
+            let metric = find(
+                store,
+                "billable_metrics",
+                "code",
+                &charge["billable_metric_code"],
+            );
+            let metric_events: Vec<&&Value> = events
+                .iter()
+                .filter(|event| event["code"] == metric["code"])
+                .collect();
+            let units: f64 = match (
+                metric["aggregation_type"].as_str(),
+                metric["field_name"].as_str(),
+            ) {
+                (Some("sum_agg"), Some(field_name)) => metric_events
+                    .iter()
+                    .filter_map(|event| number(&event["properties"][field_name]))
+                    .sum(),
+                _ => metric_events.len() as f64,
+            };
+            let unit_amount = match charge["charge_model"].as_str() {
+                Some("standard") => number(&charge["properties"]["amount"]).unwrap_or_default(),
+                _ => 0.0,
+            };
+            json!({
+                "units": units.to_string(),
+                "events_count": metric_events.len(),
+                "amount_cents": (units * unit_amount * 100.0).round() as i64,
+                "amount_currency": currency,
+                "charge": { "lago_id": charge["lago_id"], "charge_model": charge["charge_model"] },
+                "billable_metric": {
+                    "lago_id": metric["lago_id"],
+                    "name": metric["name"],
+                    "code": metric["code"],
+                    "aggregation_type": metric["aggregation_type"],
+                },
+                "filters": [],
+                "grouped_usage": [],
+            })
+        })
+        .collect();
+    let amount_cents: i64 = charges_usage
+        .iter()
+        .filter_map(|usage| usage["amount_cents"].as_i64())
+        .sum();
+
+    let today = Utc::now().date_naive();
+    let from = today.with_day(1).unwrap_or(today);
+    let to = from.checked_add_months(Months::new(1)).unwrap_or(today);
+    Json(json!({
+        "customer_usage": {
+            "from_datetime": from.and_time(NaiveTime::MIN).and_utc(),
+            "to_datetime": to.and_time(NaiveTime::MIN).and_utc(),
+            "issuing_date": to.to_string(),
+            "lago_invoice_id": null,
+            "currency": currency,
+            "amount_cents": amount_cents,
+            "taxes_amount_cents": 0,
+            "total_amount_cents": amount_cents,
+            "charges_usage": charges_usage,
+        },
+    }))
+    .into_response()
+}
+
+/// A number sent as a JSON number or as a string, as Lago does for amounts and properties.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(text) => text.parse().ok(),
+        value => value.as_f64(),
+    }
+}
+
+fn now() -> Value {
+    Value::String(chrono::Utc::now().to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use lago_client::{Config, Credentials, LagoClient, Region};
+    use lago_types::requests::{
+        customer::{CreateCustomerInput, CreateCustomerRequest},
+        customer_usage::GetCustomerCurrentUsageRequest,
+        event::{CreateEventInput, CreateEventRequest},
+        subscription::{
+            CreateSubscriptionInput, CreateSubscriptionRequest, ListCustomerSubscriptionsRequest,
+        },
+    };
+
+    use super::*;
+
+    async fn client() -> LagoClient {
+        let base_url = serve(demo_fixtures()).await.unwrap();
+        let config = Config::builder()
+            .credentials(Credentials::new(SANDBOX_API_KEY.to_string()))
+            .region(Region::Custom(base_url))
+            .build();
+        LagoClient::new(config)
+    }
+
+    #[tokio::test]
+    async fn created_customers_and_subscriptions_are_listed() {
+        let client = client().await;
+
+        let customer = CreateCustomerInput::new("globex".to_string())
+            .with_name("Globex".to_string())
+            .with_currency("EUR".to_string());
+        let created = client
+            .create_customer(CreateCustomerRequest::new(customer))
+            .await
+            .unwrap();
+        assert_eq!(created.customer.external_id.as_deref(), Some("globex"));
+
+        let customers = client.list_customers(None).await.unwrap();
+        assert_eq!(customers.customers.len(), 2);
+
+        let subscription =
+            CreateSubscriptionInput::new("globex".to_string(), "startup".to_string())
+                .with_external_id("globex_startup".to_string());
+        client
+            .create_subscription(CreateSubscriptionRequest::new(subscription))
+            .await
+            .unwrap();
+
+        let subscriptions = client
+            .list_customer_subscriptions(ListCustomerSubscriptionsRequest::new(
+                "globex".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(subscriptions.subscriptions.len(), 1);
+        assert_eq!(subscriptions.subscriptions[0].external_id, "globex_startup");
+    }
+
+    #[tokio::test]
+    async fn current_usage_prices_the_events_received() {
+        let client = client().await;
+
+        for transaction_id in ["call_1", "call_2", "call_3"] {
+            let event = CreateEventInput::for_subscription(
+                transaction_id.to_string(),
+                "acme_startup".to_string(),
+                "api_calls".to_string(),
+            );
+            client
+                .create_event(CreateEventRequest::new(event))
+                .await
+                .unwrap();
+        }
+
+        let usage = client
+            .get_customer_current_usage(GetCustomerCurrentUsageRequest::new(
+                "acme".to_string(),
+                "acme_startup".to_string(),
+            ))
+            .await
+            .unwrap()
+            .customer_usage;
+        assert_eq!(usage.currency, "USD");
+        assert_eq!(usage.charges_usage[0].events_count, 3);
+        assert_eq!(usage.amount_cents, 3);
+    }
+
+    #[tokio::test]
+    async fn unreadable_records_are_refused_without_being_stored() {
+        let client = client().await;
+
+        let subscription =
+            CreateSubscriptionInput::new("unknown".to_string(), "startup".to_string());
+        let refused = client
+            .create_subscription(CreateSubscriptionRequest::new(subscription))
+            .await;
+        assert!(refused.is_err());
+
+        let subscriptions = client.list_subscriptions(None).await.unwrap();
+        assert_eq!(subscriptions.subscriptions.len(), 1);
+    }
+
+    #[test]
+    fn records_without_a_filtered_field_do_not_match() {
+        let records = [
+            json!({ "code": "a", "plan_code": "startup" }),
+            json!({ "code": "b", "plan_code": null }),
+            json!({ "code": "c" }),
+        ];
+        let query = [("plan_code".to_string(), "startup".to_string())];
+
+        let response = list(&records, "subscriptions", &query);
+        let body =
+            futures::executor::block_on(axum::body::to_bytes(response.into_body(), usize::MAX))
+                .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["meta"]["total_count"], 1);
+        assert_eq!(body["subscriptions"][0]["code"], "a");
+    }
+}
@@ -1,14 +1,44 @@
 use anyhow::Result;
 use rmcp::{
-    ErrorData as McpError, RoleServer, ServerHandler,
-    handler::server::{router::tool::ToolRouter, tool::Parameters},
+    ErrorData as McpError, Peer, RoleServer, ServerHandler,
+    handler::server::{
+        router::tool::ToolRouter,
+        tool::{Parameters, ToolCallContext},
+    },
     model::*,
     service::RequestContext,
-    tool, tool_handler, tool_router,
+    tool, tool_router,
 };
 use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use tracing::Instrument;
+
+use crate::api_version;
+use crate::audit::{self, AuditEntry};
+use crate::backoff;
+use crate::client_log::ClientLogger;
+use crate::completion::Completions;
+use crate::confirmation;
+use crate::credentials::{self, CredentialStore, SessionCredentials};
+use crate::errors::{self, ErrorCode};
+use crate::features;
+use crate::principal::Principal;
+use crate::progress::{self, ProgressReporter};
+use crate::quota;
+use crate::relay;
+use crate::resources::CatalogResources;
+use crate::shaping::{self, Shaping};
+use crate::spending;
+use crate::stats;
+use crate::timeout;
+use crate::timezone::{self, DisplayTimezone};
+use crate::tools::{
+    error_result, get_lago_api_config, is_write_tool, success_result, tool_annotations,
+};
 
 use crate::tools::activity_log::ActivityLogService;
+use crate::tools::analytics::AnalyticsService;
 use crate::tools::api_log::ApiLogService;
 use crate::tools::applied_coupon::AppliedCouponService;
 use crate::tools::billable_metric::BillableMetricService;
@@ -18,9 +48,14 @@ use crate::tools::customer::CustomerService;
 use crate::tools::customer_usage::CustomerUsageService;
 use crate::tools::event::EventService;
 use crate::tools::invoice::InvoiceService;
+use crate::tools::invoice_custom_section::InvoiceCustomSectionService;
 use crate::tools::payment::PaymentService;
 use crate::tools::plan::PlanService;
+use crate::tools::report::ReportService;
 use crate::tools::subscription::SubscriptionService;
+use crate::tools::tax::TaxService;
+use crate::tools::wallet::WalletService;
+use crate::tools::webhook::WebhookService;
 
 #[derive(Clone)]
 #[allow(dead_code)]
@@ -38,9 +73,28 @@ pub struct LagoMcpServer {
     event_service: EventService,
     payment_service: PaymentService,
     plan_service: PlanService,
+    report_service: ReportService,
+    wallet_service: WalletService,
+    tax_service: TaxService,
+    analytics_service: AnalyticsService,
+    webhook_service: WebhookService,
+    invoice_custom_section_service: InvoiceCustomSectionService,
+    catalog_resources: CatalogResources,
+    client_logger: ClientLogger,
+    completions: Completions,
+    credential_store: CredentialStore,
+    /// Whether the server talks to a single local client over stdio, the only transport on
+    /// which the client may set the session's Lago credentials.
+    stdio: bool,
     tool_router: ToolRouter<Self>,
 }
 
+impl Default for LagoMcpServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[allow(dead_code)]
 impl LagoMcpServer {
     pub fn new() -> Self {
@@ -57,6 +111,19 @@ impl LagoMcpServer {
         let event_service = EventService::new();
         let payment_service = PaymentService::new();
         let plan_service = PlanService::new();
+        let report_service = ReportService::new();
+        let wallet_service = WalletService::new();
+        let tax_service = TaxService::new();
+        let analytics_service = AnalyticsService::new();
+        let webhook_service = WebhookService::new();
+        let invoice_custom_section_service = InvoiceCustomSectionService::new();
+        let catalog_resources = CatalogResources::new();
+        let client_logger = ClientLogger::default();
+        let completions = Completions::new();
+        let credential_store = CredentialStore::default();
+
+        let mut tool_router = Self::tool_router();
+        features::apply(&mut tool_router);
 
         Self {
             invoice_service,
@@ -72,15 +139,71 @@ impl LagoMcpServer {
             event_service,
             payment_service,
             plan_service,
-            tool_router: Self::tool_router(),
+            report_service,
+            wallet_service,
+            tax_service,
+            analytics_service,
+            webhook_service,
+            invoice_custom_section_service,
+            catalog_resources,
+            client_logger,
+            completions,
+            credential_store,
+            stdio: false,
+            tool_router,
+        }
+    }
+
+    /// Server of the stdio transport, whose client may set the session's Lago credentials.
+    pub fn stdio() -> Self {
+        Self {
+            stdio: true,
+            ..Self::new()
+        }
+    }
+
+    /// Make the credentials set for this session available to the request's tools.
+    fn apply_session_credentials(&self, context: &mut RequestContext<RoleServer>) {
+        if let Some(credentials) = self.credential_store.get() {
+            context.extensions.insert(credentials);
         }
     }
+
+    /// Report the outcome of a tool call to the client, with the error of a failed call.
+    async fn log_tool_call_finished(
+        &self,
+        peer: &Peer<RoleServer>,
+        tool_name: &str,
+        started_at: Instant,
+        result: &Result<CallToolResult, McpError>,
+    ) {
+        let duration_ms = started_at.elapsed().as_millis() as u64;
+        let error = call_error(result);
+        let level = match error {
+            Some(_) => LoggingLevel::Error,
+            None => LoggingLevel::Info,
+        };
+
+        self.client_logger
+            .log(
+                peer,
+                level,
+                serde_json::json!({
+                    "event": "tool_call_finished",
+                    "tool": tool_name,
+                    "duration_ms": duration_ms,
+                    "is_error": error.is_some(),
+                    "error": error,
+                }),
+            )
+            .await;
+    }
 }
 
 #[tool_router]
 impl LagoMcpServer {
     #[tool(
-        description = "Get a specific invoice by its Lago ID (UUID). Note: Use find_invoice_by_number if you have an invoice number like 'RAF-8142-202601-312'"
+        description = "Get a specific invoice by its Lago ID (UUID). Use include to also return its fees, subscriptions, applied taxes or credit notes. Note: Use find_invoice_by_number if you have an invoice number like 'RAF-8142-202601-312'"
     )]
     pub async fn get_invoice(
         &self,
@@ -104,7 +227,7 @@ impl LagoMcpServer {
     }
 
     #[tool(
-        description = "List invoices from Lago with optional filtering by customer, dates, status and type. Use search_term to find invoices by number."
+        description = "List invoices from Lago with optional filtering by customer, dates, status, type, billing entity and self-billing. Use search_term to find invoices by number or a number fragment. Set fetch_all to return every matching invoice instead of a single page."
     )]
     pub async fn list_invoices(
         &self,
@@ -182,7 +305,7 @@ impl LagoMcpServer {
     }
 
     #[tool(
-        description = "Trigger PDF generation for an invoice and get the download URL. Use this when a customer needs a PDF copy of their invoice."
+        description = "Trigger PDF generation for an invoice and get the download URL. Use this when a customer needs a PDF copy of their invoice. Set include_file to also receive the PDF itself as an embedded resource."
     )]
     pub async fn download_invoice(
         &self,
@@ -280,7 +403,20 @@ impl LagoMcpServer {
     }
 
     #[tool(
-        description = "List all subscriptions from Lago with optional filtering by plan code and status"
+        description = "Get the current usage of all active subscriptions of a customer in a single call. Use this to answer what a customer is using right now without knowing their subscription IDs."
+    )]
+    pub async fn get_customer_usage_summary(
+        &self,
+        parameters: Parameters<crate::tools::customer_usage::GetCustomerUsageSummaryArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.customer_usage_service
+            .get_customer_usage_summary(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "List all subscriptions from Lago with optional filtering by plan code and status. Set fetch_all to return every matching subscription instead of a single page."
     )]
     pub async fn list_subscriptions(
         &self,
@@ -399,7 +535,9 @@ impl LagoMcpServer {
             .await
     }
 
-    #[tool(description = "Get a specific activity log by its activity ID")]
+    #[tool(
+        description = "Get a specific activity log by its activity ID. The result includes a changes list with each changed field and its old and new value."
+    )]
     pub async fn get_activity_log(
         &self,
         parameters: Parameters<crate::tools::activity_log::GetActivityLogArgs>,
@@ -411,7 +549,7 @@ impl LagoMcpServer {
     }
 
     #[tool(
-        description = "List activity logs from Lago with optional filtering by activity type, source, user email, customer, subscription, resource type and date range"
+        description = "List activity logs from Lago with optional filtering by activity type, source, user email, customer, subscription, resource type and date range. Each log includes a changes list with each changed field and its old and new value."
     )]
     pub async fn list_activity_logs(
         &self,
@@ -423,7 +561,9 @@ impl LagoMcpServer {
             .await
     }
 
-    #[tool(description = "Get a specific API log by its request ID")]
+    #[tool(
+        description = "Get a specific API log by its request ID. Set include_bodies to also return the logged request and response payloads, with secrets and PII redacted."
+    )]
     pub async fn get_api_log(
         &self,
         parameters: Parameters<crate::tools::api_log::GetApiLogArgs>,
@@ -541,7 +681,7 @@ impl LagoMcpServer {
     }
 
     #[tool(
-        description = "List all usage events from Lago with optional filtering by subscription, billable metric code, and timestamp range"
+        description = "List all usage events from Lago with optional filtering by subscription, billable metric code, and timestamp range. Set fetch_all to return every matching event instead of a single page."
     )]
     pub async fn list_events(
         &self,
@@ -551,6 +691,48 @@ impl LagoMcpServer {
         self.event_service.list_events(parameters, context).await
     }
 
+    #[tool(
+        description = "Estimate the fees a usage event would generate on a pay-in-advance charge, without sending it. Use this to tell a customer what an action would cost before it happens. Only works for billable metrics with a pay-in-advance charge on the subscription's plan."
+    )]
+    pub async fn estimate_fees(
+        &self,
+        parameters: Parameters<crate::tools::event::EstimateFeesArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.event_service.estimate_fees(parameters, context).await
+    }
+
+    #[tool(
+        description = "Queue a usage event for batched ingestion instead of sending it right away. Queued events are sent to Lago's batch endpoint once a full batch is queued or after a short delay. Use this instead of create_event when reporting many events. Provide either external_customer_id or external_subscription_id."
+    )]
+    pub async fn queue_event(
+        &self,
+        parameters: Parameters<crate::tools::event::CreateEventArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.event_service.queue_event(parameters, context).await
+    }
+
+    #[tool(
+        description = "Send every event queued with queue_event to Lago now, and return the number of events sent with the queue status."
+    )]
+    pub async fn flush_events(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.event_service.flush_events(context).await
+    }
+
+    #[tool(
+        description = "Get the state of the event queue fed by queue_event: events waiting to be sent, age of the oldest one, events sent so far, and the last flush error if any."
+    )]
+    pub async fn queue_status(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.event_service.queue_status(context).await
+    }
+
     #[tool(
         description = "List credit notes from Lago with optional filtering by customer, dates, reason, status, and amount range"
     )]
@@ -564,6 +746,19 @@ impl LagoMcpServer {
             .await
     }
 
+    #[tool(
+        description = "List all credit notes issued to a specific customer with optional filtering by dates, reason and status. Set fetch_all to return every credit note instead of a single page."
+    )]
+    pub async fn list_customer_credit_notes(
+        &self,
+        parameters: Parameters<crate::tools::credit_note::ListCustomerCreditNotesArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.credit_note_service
+            .list_customer_credit_notes(parameters, context)
+            .await
+    }
+
     #[tool(description = "Get a specific credit note by its Lago ID")]
     pub async fn get_credit_note(
         &self,
@@ -688,7 +883,7 @@ impl LagoMcpServer {
     }
 
     #[tool(
-        description = "Create a manual payment for an invoice. Use this to record payments made outside of Lago's payment providers."
+        description = "Create a manual payment for an invoice. Use this to record payments made outside of Lago's payment providers. Payments larger than the invoice's remaining amount due are refused unless allow_overpayment is set."
     )]
     pub async fn create_payment(
         &self,
@@ -699,9 +894,563 @@ impl LagoMcpServer {
             .create_payment(parameters, context)
             .await
     }
+
+    #[tool(
+        description = "List active subscriptions renewing in the next N days (default 7) with their estimated renewal amounts from an invoice preview. Use this to warn customers about upcoming charges."
+    )]
+    pub async fn report_upcoming_renewals(
+        &self,
+        parameters: Parameters<crate::tools::report::ReportUpcomingRenewalsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.report_service
+            .report_upcoming_renewals(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Flag active prepaid wallets whose ongoing balance is below a threshold or is projected, from usage so far this month, to run out before the end of the month. Returns customer, balances and projected depletion date."
+    )]
+    pub async fn report_low_wallets(
+        &self,
+        parameters: Parameters<crate::tools::report::ReportLowWalletsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.report_service
+            .report_low_wallets(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Group subscriptions by start month and report how many were still active 1, 3, 6 and 12 months later. Returns a compact cohort retention matrix; milestones not reached yet are null."
+    )]
+    pub async fn report_subscription_cohorts(
+        &self,
+        parameters: Parameters<crate::tools::report::ReportSubscriptionCohortsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.report_service
+            .report_subscription_cohorts(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Rank active subscriptions by the units they consumed of a billable metric in the current or previous billing period. Use this to find who is driving usage of a metric."
+    )]
+    pub async fn report_top_metric_consumers(
+        &self,
+        parameters: Parameters<crate::tools::report::ReportTopMetricConsumersArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.report_service
+            .report_top_metric_consumers(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Sum invoiced subscription fees (before taxes) per plan code and currency over an issuing date range. Use this to find which plans earn the most."
+    )]
+    pub async fn report_revenue_by_plan(
+        &self,
+        parameters: Parameters<crate::tools::report::ReportRevenueByPlanArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.report_service
+            .report_revenue_by_plan(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Estimate a customer's lifetime value from their historical finalized invoice totals, the MRR of their active subscriptions, and their tenure. The underlying numbers are returned with the estimate."
+    )]
+    pub async fn estimate_customer_ltv(
+        &self,
+        parameters: Parameters<crate::tools::report::EstimateCustomerLtvArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.report_service
+            .estimate_customer_ltv(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Compute days sales outstanding (DSO) over a rolling window from invoice issuing dates and payment dates. Returns the metric for the current and previous window, the invoice sample size, and whether payment behavior is improving."
+    )]
+    pub async fn get_dso(
+        &self,
+        parameters: Parameters<crate::tools::report::GetDsoArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.report_service.get_dso(parameters, context).await
+    }
+
+    #[tool(
+        description = "List coupons whose expiration date falls within the next N days, with the number of active applied coupons and affected customers for each, so renewals or extensions can be offered before discounts lapse."
+    )]
+    pub async fn report_expiring_coupons(
+        &self,
+        parameters: Parameters<crate::tools::report::ReportExpiringCouponsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.report_service
+            .report_expiring_coupons(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Report this MCP server's own health since startup: per-tool call counts, error rates and average/max latency, and the hit rate of the argument completion cache."
+    )]
+    pub async fn get_server_stats(&self) -> Result<CallToolResult, rmcp::ErrorData> {
+        Ok(success_result(&stats::snapshot()))
+    }
+
+    #[tool(
+        description = "Get the remaining tool-call quota of the current credential: calls used and left in the hourly and daily windows, overall and for each write tool, with reset times. Calling this tool does not consume quota."
+    )]
+    pub async fn get_remaining_quota(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        Ok(success_result(&quota::remaining(&quota::credential_id(
+            &context,
+        ))))
+    }
+
+    #[tool(
+        description = "Switch the Lago organization used by this session, over stdio, by setting its API key and, for another region or a self-hosted Lago, its API URL. Call without api_key to go back to the server's configured credentials."
+    )]
+    pub async fn set_lago_credentials(
+        &self,
+        Parameters(args): Parameters<crate::credentials::SetLagoCredentialsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        if !self.stdio
+            || context
+                .extensions
+                .get::<axum::http::request::Parts>()
+                .is_some()
+        {
+            return Ok(error_result(
+                "set_lago_credentials is only available over stdio: HTTP clients send their API key in the X-LAGO-API-KEY header",
+            ));
+        }
+
+        let api_key = args.api_key.filter(|api_key| !api_key.trim().is_empty());
+        let Some(api_key) = api_key else {
+            self.credential_store.set(None);
+            return Ok(success_result(&serde_json::json!({
+                "credentials": "server",
+                "message": "Using the server's configured Lago credentials again",
+            })));
+        };
+
+        let session_credentials = SessionCredentials {
+            api_key: api_key.trim().to_string(),
+            api_url: args.api_url.filter(|api_url| !api_url.trim().is_empty()),
+        };
+        let api_key = credentials::mask_api_key(&session_credentials.api_key);
+        tracing::info!(%api_key, "Lago credentials set for the session");
+        let result = serde_json::json!({
+            "credentials": "session",
+            "api_key": api_key,
+            "api_url": session_credentials.api_url,
+        });
+        self.credential_store.set(Some(session_credentials));
+
+        Ok(success_result(&result))
+    }
+
+    #[tool(
+        description = "Top up a customer's wallet: purchase paid credits (an invoice is issued for them), grant free credits, or void credits. Use list_wallet_transactions to follow the transaction status."
+    )]
+    pub async fn create_wallet_transaction(
+        &self,
+        parameters: Parameters<crate::tools::wallet::CreateWalletTransactionArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.wallet_service
+            .create_wallet_transaction(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "List the transactions of a wallet with optional filtering by status (pending, settled, failed), transaction status (purchased, granted, voided, invoiced) and type (inbound, outbound)."
+    )]
+    pub async fn list_wallet_transactions(
+        &self,
+        parameters: Parameters<crate::tools::wallet::ListWalletTransactionsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.wallet_service
+            .list_wallet_transactions(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "List the taxes of the organization with their code, rate and whether they apply to every customer by default."
+    )]
+    pub async fn list_taxes(
+        &self,
+        parameters: Parameters<crate::tools::tax::ListTaxesArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.tax_service.list_taxes(parameters, context).await
+    }
+
+    #[tool(description = "Retrieve a tax by its unique code.")]
+    pub async fn get_tax(
+        &self,
+        parameters: Parameters<crate::tools::tax::GetTaxArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.tax_service.get_tax(parameters, context).await
+    }
+
+    #[tool(
+        description = "Create a tax. Its code can then be referenced in the tax_codes of plans, charges and customers."
+    )]
+    pub async fn create_tax(
+        &self,
+        parameters: Parameters<crate::tools::tax::CreateTaxArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.tax_service.create_tax(parameters, context).await
+    }
+
+    #[tool(description = "Update a tax by its code. Only the provided fields are changed.")]
+    pub async fn update_tax(
+        &self,
+        parameters: Parameters<crate::tools::tax::UpdateTaxArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.tax_service.update_tax(parameters, context).await
+    }
+
+    #[tool(
+        description = "Delete a tax by its code. It is removed from the plans, charges and customers it was applied to."
+    )]
+    pub async fn delete_tax(
+        &self,
+        parameters: Parameters<crate::tools::tax::DeleteTaxArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.tax_service.delete_tax(parameters, context).await
+    }
+
+    #[tool(
+        description = "Get Lago's invoiced usage analytics: usage-based revenue invoiced per month, broken down by billable metric code and currency. Use this to see which metrics drive usage revenue."
+    )]
+    pub async fn get_invoiced_usage(
+        &self,
+        parameters: Parameters<crate::tools::analytics::GetInvoicedUsageArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.analytics_service
+            .get_invoiced_usage(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Get the overdue balance per month from Lago's analytics: amounts of invoices past their due date and not fully paid, with their invoice IDs. Filter by external_customer_id to get one customer's overdue balance. Combine with list_invoices(payment_status=failed) for collections follow-up."
+    )]
+    pub async fn get_overdue_balance(
+        &self,
+        parameters: Parameters<crate::tools::analytics::GetOverdueBalanceArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.analytics_service
+            .get_overdue_balance(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Get the checkout URL of a customer's payment provider (Stripe, Adyen, GoCardless...), where the customer can add or update a payment method. Use it to guide a customer after a failed payment. The customer must be linked to a payment provider."
+    )]
+    pub async fn get_customer_checkout_url(
+        &self,
+        parameters: Parameters<crate::tools::customer::GetCustomerCheckoutUrlArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.customer_service
+            .get_customer_checkout_url(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Update an existing customer by external_id. Only the provided fields are changed (email, address, currency, net_payment_term, timezone...); the others are kept. Fails if the customer does not exist, unlike create_customer."
+    )]
+    pub async fn update_customer(
+        &self,
+        parameters: Parameters<crate::tools::customer::UpdateCustomerArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.customer_service
+            .update_customer(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Permanently delete a customer by external_id. IRREVERSIBLE: the customer's active subscriptions are terminated, its wallets are closed and draft invoices are finalized. Only call this after the user has explicitly confirmed the deletion, and set confirm to true."
+    )]
+    pub async fn delete_customer(
+        &self,
+        parameters: Parameters<crate::tools::customer::DeleteCustomerArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.customer_service
+            .delete_customer(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Mark the payment dispute (chargeback) of a finalized invoice as lost, by its Lago ID (UUID). The invoice's payment_dispute_lost_at is set and it can no longer be voided. If you have an invoice number, use find_invoice_by_number first to get the lago_id."
+    )]
+    pub async fn lose_invoice_dispute(
+        &self,
+        parameters: Parameters<crate::tools::invoice::LoseInvoiceDisputeArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.invoice_service
+            .lose_invoice_dispute(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Estimate a credit note before issuing it: for the given invoice fees and amounts, returns the taxes, the maximum creditable and refundable amounts, and the coupon adjustments Lago would apply. Nothing is created; use create_credit_note afterwards."
+    )]
+    pub async fn estimate_credit_note(
+        &self,
+        parameters: Parameters<crate::tools::credit_note::EstimateCreditNoteArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.credit_note_service
+            .estimate_credit_note(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Get the lifetime usage of a subscription with progressive billing: total usage invoiced and not yet invoiced, historical usage billed outside Lago, and the progress towards each usage threshold."
+    )]
+    pub async fn get_subscription_lifetime_usage(
+        &self,
+        parameters: Parameters<crate::tools::subscription::GetSubscriptionLifetimeUsageArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.subscription_service
+            .get_subscription_lifetime_usage(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Set the historical usage amount of a subscription (usage billed outside of Lago, e.g. before a migration), which counts towards its progressive billing thresholds."
+    )]
+    pub async fn update_subscription_lifetime_usage(
+        &self,
+        parameters: Parameters<crate::tools::subscription::UpdateSubscriptionLifetimeUsageArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.subscription_service
+            .update_subscription_lifetime_usage(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Get the public key Lago signs its webhooks with (base64-encoded RSA key, RS256 JWT signatures in the X-Lago-Signature header). Use it to check why a webhook receiver rejects Lago's signatures."
+    )]
+    pub async fn get_webhook_public_key(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.webhook_service.get_webhook_public_key(context).await
+    }
+
+    #[tool(
+        description = "List the invoice custom sections of the organization: extra text blocks (legal mentions, bank details...) printed on invoices."
+    )]
+    pub async fn list_invoice_custom_sections(
+        &self,
+        parameters: Parameters<crate::tools::invoice_custom_section::ListInvoiceCustomSectionsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.invoice_custom_section_service
+            .list_invoice_custom_sections(parameters, context)
+            .await
+    }
+
+    #[tool(description = "Retrieve an invoice custom section by its code.")]
+    pub async fn get_invoice_custom_section(
+        &self,
+        parameters: Parameters<crate::tools::invoice_custom_section::GetInvoiceCustomSectionArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.invoice_custom_section_service
+            .get_invoice_custom_section(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Create an invoice custom section: a text block printed on invoices, such as legally required mentions. Attach it to customers with set_customer_invoice_custom_sections."
+    )]
+    pub async fn create_invoice_custom_section(
+        &self,
+        parameters: Parameters<
+            crate::tools::invoice_custom_section::CreateInvoiceCustomSectionArgs,
+        >,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.invoice_custom_section_service
+            .create_invoice_custom_section(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Update an invoice custom section by its code. Only the provided fields are changed; invoices already issued are not modified."
+    )]
+    pub async fn update_invoice_custom_section(
+        &self,
+        parameters: Parameters<
+            crate::tools::invoice_custom_section::UpdateInvoiceCustomSectionArgs,
+        >,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.invoice_custom_section_service
+            .update_invoice_custom_section(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Delete an invoice custom section by its code. It is no longer printed on new invoices."
+    )]
+    pub async fn delete_invoice_custom_section(
+        &self,
+        parameters: Parameters<
+            crate::tools::invoice_custom_section::DeleteInvoiceCustomSectionArgs,
+        >,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.invoice_custom_section_service
+            .delete_invoice_custom_section(parameters, context)
+            .await
+    }
+
+    #[tool(
+        description = "Choose the invoice custom sections printed on a customer's invoices, replacing the current selection. An empty list falls back to the organization's default sections; skip_invoice_custom_sections prints none."
+    )]
+    pub async fn set_customer_invoice_custom_sections(
+        &self,
+        parameters: Parameters<
+            crate::tools::invoice_custom_section::SetCustomerInvoiceCustomSectionsArgs,
+        >,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.invoice_custom_section_service
+            .set_customer_invoice_custom_sections(parameters, context)
+            .await
+    }
+}
+
+/// MCP session of an HTTP request, when it has one.
+fn session_id(context: &RequestContext<RoleServer>) -> Option<&str> {
+    context
+        .extensions
+        .get::<axum::http::request::Parts>()
+        .and_then(|parts| parts.headers.get("mcp-session-id"))
+        .and_then(|value| value.to_str().ok())
+}
+
+/// Span wrapping a tool call, so every event logged by the tool layer carries its context.
+fn tool_call_span(tool_name: &str, context: &RequestContext<RoleServer>) -> tracing::Span {
+    let span = tracing::info_span!(
+        "tool_call",
+        tool = tool_name,
+        request_id = %context.id,
+        session_id = tracing::field::Empty,
+        tenant = tracing::field::Empty,
+    );
+
+    if let Some(session_id) = session_id(context) {
+        span.record("session_id", session_id);
+    }
+    if let Some(tenant) = Principal::from_context(context).and_then(|p| p.tenant.as_deref()) {
+        span.record("tenant", tenant);
+    }
+
+    span
+}
+
+/// Error message of a failed tool call, or `None` when it succeeded.
+fn call_error(result: &Result<CallToolResult, McpError>) -> Option<String> {
+    match result {
+        Ok(call_result) if call_result.is_error == Some(true) => Some(
+            call_result
+                .content
+                .first()
+                .and_then(|content| content.as_text())
+                .map(|text| text.text.clone())
+                .unwrap_or_default(),
+        ),
+        Ok(_) => None,
+        Err(e) => Some(e.message.to_string()),
+    }
+}
+
+/// Advertise the `display_timezone` argument accepted by every tool through `call_tool`.
+fn add_display_timezone_arg(tool: &mut Tool) {
+    let input_schema = std::sync::Arc::make_mut(&mut tool.input_schema);
+    let properties = input_schema
+        .entry("properties")
+        .or_insert_with(|| serde_json::json!({}));
+
+    if let Some(properties) = properties.as_object_mut() {
+        properties.insert(
+            timezone::DISPLAY_TIMEZONE_ARG.to_string(),
+            serde_json::json!({
+                "type": ["string", "null"],
+                "description": "Render timestamps of the result in this timezone next to their UTC value: 'customer' for the customer's timezone, an IANA name such as 'America/New_York', or 'utc' for UTC only. Defaults to the server setting.",
+            }),
+        );
+    }
+}
+
+/// Advertise the `fields` and `output` arguments accepted by list and get tools through
+/// `call_tool`.
+fn add_shaping_args(tool: &mut Tool) {
+    let accepts_fields = shaping::accepts_fields(&tool.name);
+    let accepts_output = shaping::accepts_output(&tool.name);
+    if !accepts_fields && !accepts_output {
+        return;
+    }
+
+    let input_schema = std::sync::Arc::make_mut(&mut tool.input_schema);
+    let properties = input_schema
+        .entry("properties")
+        .or_insert_with(|| serde_json::json!({}));
+
+    let Some(properties) = properties.as_object_mut() else {
+        return;
+    };
+    if accepts_fields {
+        properties.insert(
+            shaping::FIELDS_ARG.to_string(),
+            serde_json::json!({
+                "type": ["array", "null"],
+                "items": { "type": "string" },
+                "description": "Only return these fields of each record, to keep the result small, e.g. [\"lago_id\", \"total_amount_cents\", \"customer.external_id\"]. Dotted paths select nested fields. Defaults to every field.",
+            }),
+        );
+    }
+    if accepts_output {
+        properties.insert(
+            shaping::OUTPUT_ARG.to_string(),
+            serde_json::json!({
+                "type": ["string", "null"],
+                "enum": ["summary", "full", null],
+                "description": "'summary' returns a compact digest of each record (id, customer, amount, status, date) with the count and total amount per currency; 'full' (default) returns the records as they are.",
+            }),
+        );
+    }
 }
 
-#[tool_handler]
 impl ServerHandler for LagoMcpServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
@@ -710,6 +1459,9 @@ impl ServerHandler for LagoMcpServer {
             ),
             capabilities: ServerCapabilities::builder()
                 .enable_tools()
+                .enable_resources()
+                .enable_logging()
+                .enable_completions()
                 .build(),
             ..Default::default()
         }
@@ -717,13 +1469,270 @@ impl ServerHandler for LagoMcpServer {
 
     async fn initialize(
         &self,
-        _request: InitializeRequestParam,
+        request: InitializeRequestParam,
         context: RequestContext<RoleServer>,
     ) -> Result<InitializeResult, McpError> {
+        if context.peer.peer_info().is_none() {
+            context.peer.set_peer_info(request.clone());
+        }
         if let Some(http_request_part) = context.extensions.get::<axum::http::request::Parts>() {
             let initialize_uri = &http_request_part.uri;
             tracing::info!(%initialize_uri, "initialize from http server");
+        } else if self.stdio
+            && let Some(session_credentials) = credentials::from_initialize(&request)
+        {
+            let session_credentials =
+                session_credentials.map_err(|e| McpError::invalid_params(e, None))?;
+            tracing::info!(
+                api_key = %credentials::mask_api_key(&session_credentials.api_key),
+                "Using Lago credentials from the client's initialize request"
+            );
+            self.credential_store.set(Some(session_credentials));
         }
         Ok(self.get_info())
     }
+
+    async fn call_tool(
+        &self,
+        mut request: CallToolRequestParam,
+        mut context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.apply_session_credentials(&mut context);
+        let tool_name = request.name.clone();
+        let started_at = Instant::now();
+
+        // The display timezone is handled here for every tool rather than by each of them.
+        let display_timezone = request
+            .arguments
+            .as_mut()
+            .and_then(|arguments| arguments.remove(timezone::DISPLAY_TIMEZONE_ARG))
+            .filter(|value| !value.is_null())
+            .map(|value| DisplayTimezone::parse(value.as_str().unwrap_or_default()))
+            .transpose();
+        let shaping = Shaping::from_arguments(&tool_name, request.arguments.as_mut());
+
+        let read_only = Principal::from_context(&context).is_some_and(|p| !p.can_write);
+        let credential = quota::credential_id(&context);
+        // Wallet credits are counted at the wallet's own rate, read only when it is checked.
+        let credit_cents = if tool_name == "create_wallet_transaction"
+            && self.tool_router.has_route(&tool_name)
+            && (spending::is_capped(&tool_name) || confirmation::enabled())
+            && let Ok(config) = get_lago_api_config(&context).await
+        {
+            spending::wallet_credit_cents(&config, request.arguments.as_ref()).await
+        } else {
+            None
+        };
+        let mut reserved_cents = 0;
+        let timed_out = AtomicBool::new(false);
+        let denied_reason = if let Err(reason) = &display_timezone {
+            Some((ErrorCode::Validation, reason.clone()))
+        } else if let Err(reason) = &shaping {
+            Some((ErrorCode::Validation, reason.clone()))
+        } else if read_only && is_write_tool(&tool_name) {
+            Some((
+                ErrorCode::Auth,
+                format!(
+                    "Permission denied: {tool_name} modifies Lago data and requires the write role"
+                ),
+            ))
+        } else if let Some(reason) = api_version::unsupported_reason(&tool_name) {
+            Some((ErrorCode::Unsupported, reason))
+        } else if tool_name == "get_remaining_quota" {
+            None
+        } else if let Err(reason) = quota::consume(&credential, &tool_name) {
+            Some((ErrorCode::QuotaExceeded, reason))
+        } else {
+            match spending::reserve(
+                &credential,
+                &tool_name,
+                request.arguments.as_ref(),
+                credit_cents,
+            ) {
+                Ok(amount_cents) => {
+                    reserved_cents = amount_cents;
+                    None
+                }
+                // Exceeded caps are structured errors, other reasons are invalid amounts.
+                Err(reason) => Some((ErrorCode::Validation, reason)),
+            }
+        };
+
+        let peer = context.peer.clone();
+        let span = tool_call_span(&tool_name, &context);
+        let rejected = denied_reason.is_some();
+        let audited_arguments = audit::enabled()
+            .then(|| request.arguments.clone())
+            .flatten();
+        let session_id = session_id(&context).map(str::to_string);
+        let subject = Principal::from_context(&context).map(|p| p.subject.clone());
+        let call = async {
+            match denied_reason {
+                Some((code, reason)) => {
+                    errors::record(code);
+                    tracing::warn!(%reason, "Tool call rejected");
+                    self.client_logger
+                        .log(
+                            &peer,
+                            LoggingLevel::Warning,
+                            serde_json::json!({
+                                "event": "tool_call_rejected",
+                                "tool": tool_name,
+                                "reason": reason,
+                            }),
+                        )
+                        .await;
+                    Ok(error_result(reason))
+                }
+                None => {
+                    if let Err(reason) = confirmation::confirm(
+                        &peer,
+                        &tool_name,
+                        request.arguments.as_ref(),
+                        credit_cents,
+                    )
+                    .await
+                    {
+                        tracing::warn!(%reason, "Tool call not confirmed");
+                        errors::record(ErrorCode::NotConfirmed);
+                        return Ok(error_result(reason));
+                    }
+                    self.client_logger
+                        .log(
+                            &peer,
+                            LoggingLevel::Debug,
+                            serde_json::json!({ "event": "tool_call_started", "tool": tool_name }),
+                        )
+                        .await;
+
+                    let progress_reporter = ProgressReporter::from_context(&context);
+                    let tcc = ToolCallContext::new(self, request, context);
+                    let call = progress::scope(progress_reporter, self.tool_router.call(tcc));
+                    let call = shaping::scope(shaping.unwrap_or_default(), call);
+                    let call = timezone::scope(display_timezone.ok().flatten(), call);
+                    let call = relay::scope(call);
+
+                    let timeout = timeout::timeout_for(&tool_name);
+                    let call = backoff::scope(Instant::now() + timeout, call);
+                    match tokio::time::timeout(timeout, call).await {
+                        Ok((result, Some(rate_limited))) if call_error(&result).is_some() => {
+                            let message = call_error(&result).unwrap_or_default();
+                            Ok(error_result(backoff::rate_limited_error(
+                                &message,
+                                rate_limited,
+                            )))
+                        }
+                        Ok((result, _)) => result,
+                        Err(_) => {
+                            tracing::warn!(timeout_secs = timeout.as_secs(), "Tool call timed out");
+                            timed_out.store(true, Ordering::Relaxed);
+                            Ok(error_result(timeout::timeout_error(&tool_name, timeout)))
+                        }
+                    }
+                }
+            }
+        }
+        .instrument(span);
+        let ((result, failure), lago_request_ids) = audit::scope(errors::scope(call)).await;
+        let result = errors::structure(&tool_name, result, failure);
+
+        let is_error = !matches!(&result, Ok(call_result) if call_result.is_error != Some(true));
+        stats::record_tool_call(&tool_name, started_at.elapsed(), is_error);
+        // A call that timed out may still have been applied by Lago, so its amount stays counted.
+        if is_error && !timed_out.load(Ordering::Relaxed) {
+            spending::release(&credential, &tool_name, reserved_cents);
+        }
+        self.log_tool_call_finished(&peer, &tool_name, started_at, &result)
+            .await;
+
+        let error = call_error(&result);
+        audit::record(AuditEntry {
+            session_id: session_id.as_deref(),
+            subject: subject.as_deref(),
+            tool_name: &tool_name,
+            arguments: audited_arguments,
+            outcome: match (rejected, &error) {
+                (true, _) => "rejected",
+                (false, Some(_)) => "error",
+                (false, None) => "success",
+            },
+            error,
+            duration: started_at.elapsed(),
+            lago_request_ids,
+        });
+
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let tools = self
+            .tool_router
+            .list_all()
+            .into_iter()
+            .map(|mut tool| {
+                if let Some(reason) = api_version::unsupported_reason(&tool.name) {
+                    let description = tool.description.as_deref().unwrap_or_default();
+                    tool.description = Some(format!("[{reason}] {description}").into());
+                }
+                tool.annotations = Some(tool_annotations(&tool.name));
+                add_display_timezone_arg(&mut tool);
+                add_shaping_args(&mut tool);
+                tool
+            })
+            .collect();
+
+        Ok(ListToolsResult::with_all_items(tools))
+    }
+
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        tracing::debug!(level = ?request.level, "Client log level set");
+        self.client_logger.set_level(request.level);
+        Ok(())
+    }
+
+    async fn complete(
+        &self,
+        request: CompleteRequestParam,
+        mut context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, McpError> {
+        self.apply_session_credentials(&mut context);
+        let completion = self
+            .completions
+            .complete(&request.argument.name, &request.argument.value, &context)
+            .await;
+
+        Ok(CompleteResult { completion })
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        Ok(ListResourcesResult::with_all_items(
+            self.catalog_resources.list(),
+        ))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        mut context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        self.apply_session_credentials(&mut context);
+        let progress_reporter = ProgressReporter::from_context(&context);
+        progress::scope(
+            progress_reporter,
+            self.catalog_resources.read(&request.uri, &context),
+        )
+        .await
+    }
 }
@@ -0,0 +1,213 @@
+use std::sync::Arc;
+
+use futures::Stream;
+use redis::AsyncCommands;
+use rmcp::{
+    ServiceExt,
+    model::{
+        ClientJsonRpcMessage, ClientNotification, InitializedNotification, ServerJsonRpcMessage,
+    },
+    transport::{
+        WorkerTransport,
+        common::server_side_http::{ServerSseMessage, SessionId},
+        streamable_http_server::{
+            SessionManager,
+            session::local::{
+                LocalSessionManager, LocalSessionManagerError, LocalSessionWorker,
+                create_local_session,
+            },
+        },
+    },
+};
+use tokio::sync::Mutex;
+
+use lago_mcp_server::{EXPERIMENTAL_CAPABILITY, LagoMcpServer};
+
+/// Default number of seconds an idle session is kept in Redis.
+pub const DEFAULT_SESSION_TTL_SECS: u64 = 3600;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RedisSessionManagerError {
+    #[error(transparent)]
+    Local(#[from] LocalSessionManagerError),
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("Failed to (de)serialize session: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Session manager that shares sessions across replicas through Redis.
+///
+/// Live sessions are still served by a [`LocalSessionManager`]. The initialize message of
+/// every session is stored in Redis, so that a replica receiving a request for a session it
+/// does not know (after a restart or a load balancer hop) can rebuild it locally by replaying
+/// the MCP handshake. In-flight SSE streams are not migrated: clients reconnect and carry on.
+pub struct RedisSessionManager {
+    local: Arc<LocalSessionManager>,
+    redis: redis::aio::ConnectionManager,
+    key_prefix: String,
+    ttl_secs: u64,
+    restore_lock: Mutex<()>,
+}
+
+impl RedisSessionManager {
+    pub async fn connect(redis_url: &str, ttl_secs: u64) -> Result<Self, redis::RedisError> {
+        let client = redis::Client::open(redis_url)?;
+        let redis = redis::aio::ConnectionManager::new(client).await?;
+
+        Ok(Self {
+            local: Arc::new(LocalSessionManager::default()),
+            redis,
+            key_prefix: "lago-mcp:session:".to_string(),
+            ttl_secs,
+            restore_lock: Mutex::new(()),
+        })
+    }
+
+    fn key(&self, id: &SessionId) -> String {
+        format!("{}{}", self.key_prefix, id)
+    }
+
+    /// Rebuild a session known to Redis but not to this replica.
+    ///
+    /// Returns `false` when the session does not exist anywhere.
+    async fn restore_session(&self, id: &SessionId) -> Result<bool, RedisSessionManagerError> {
+        let _guard = self.restore_lock.lock().await;
+
+        if self.local.has_session(id).await? {
+            return Ok(true);
+        }
+
+        let stored: Option<String> = self.redis.clone().get(self.key(id)).await?;
+        let Some(stored) = stored else {
+            return Ok(false);
+        };
+        let initialize_message: ClientJsonRpcMessage = serde_json::from_str(&stored)?;
+
+        let (handle, worker) = create_local_session(id.clone(), self.local.session_config.clone());
+        self.local.sessions.write().await.insert(id.clone(), handle);
+        self.spawn_server(id.clone(), WorkerTransport::spawn(worker));
+
+        self.local
+            .initialize_session(id, initialize_message)
+            .await?;
+        self.local
+            .accept_message(
+                id,
+                ClientJsonRpcMessage::notification(ClientNotification::InitializedNotification(
+                    InitializedNotification {
+                        method: Default::default(),
+                        extensions: Default::default(),
+                    },
+                )),
+            )
+            .await?;
+
+        tracing::info!(session_id = %id, "Restored MCP session from Redis");
+        Ok(true)
+    }
+
+    fn spawn_server(&self, id: SessionId, transport: WorkerTransport<LocalSessionWorker>) {
+        let local = self.local.clone();
+
+        tokio::spawn(async move {
+            match LagoMcpServer::new().serve(transport).await {
+                Ok(service) => {
+                    let _ = service.waiting().await;
+                }
+                Err(e) => tracing::error!("Failed to restore session {id}: {e}"),
+            }
+
+            let _ = local.close_session(&id).await;
+        });
+    }
+}
+
+/// The initialize message to store in Redis, without the Lago credentials a client may send
+/// in its experimental capabilities: HTTP sessions don't use them, and they shouldn't be
+/// stored, nor replayed when the session is restored.
+fn without_credentials(message: &ClientJsonRpcMessage) -> Result<String, serde_json::Error> {
+    let mut message = serde_json::to_value(message)?;
+    if let Some(experimental) = message
+        .pointer_mut("/params/capabilities/experimental")
+        .and_then(serde_json::Value::as_object_mut)
+    {
+        experimental.remove(EXPERIMENTAL_CAPABILITY);
+    }
+    serde_json::to_string(&message)
+}
+
+impl SessionManager for RedisSessionManager {
+    type Error = RedisSessionManagerError;
+    type Transport = WorkerTransport<LocalSessionWorker>;
+
+    async fn create_session(&self) -> Result<(SessionId, Self::Transport), Self::Error> {
+        Ok(self.local.create_session().await?)
+    }
+
+    async fn initialize_session(
+        &self,
+        id: &SessionId,
+        message: ClientJsonRpcMessage,
+    ) -> Result<ServerJsonRpcMessage, Self::Error> {
+        let serialized = without_credentials(&message)?;
+        let response = self.local.initialize_session(id, message).await?;
+
+        let _: () = self
+            .redis
+            .clone()
+            .set_ex(self.key(id), serialized, self.ttl_secs)
+            .await?;
+
+        Ok(response)
+    }
+
+    async fn has_session(&self, id: &SessionId) -> Result<bool, Self::Error> {
+        if self.local.has_session(id).await? {
+            let _: bool = self
+                .redis
+                .clone()
+                .expire(self.key(id), self.ttl_secs as i64)
+                .await?;
+            return Ok(true);
+        }
+
+        self.restore_session(id).await
+    }
+
+    async fn close_session(&self, id: &SessionId) -> Result<(), Self::Error> {
+        let _: () = self.redis.clone().del(self.key(id)).await?;
+        Ok(self.local.close_session(id).await?)
+    }
+
+    async fn create_stream(
+        &self,
+        id: &SessionId,
+        message: ClientJsonRpcMessage,
+    ) -> Result<impl Stream<Item = ServerSseMessage> + Send + Sync + 'static, Self::Error> {
+        Ok(self.local.create_stream(id, message).await?)
+    }
+
+    async fn accept_message(
+        &self,
+        id: &SessionId,
+        message: ClientJsonRpcMessage,
+    ) -> Result<(), Self::Error> {
+        Ok(self.local.accept_message(id, message).await?)
+    }
+
+    async fn create_standalone_stream(
+        &self,
+        id: &SessionId,
+    ) -> Result<impl Stream<Item = ServerSseMessage> + Send + Sync + 'static, Self::Error> {
+        Ok(self.local.create_standalone_stream(id).await?)
+    }
+
+    async fn resume(
+        &self,
+        id: &SessionId,
+        last_event_id: String,
+    ) -> Result<impl Stream<Item = ServerSseMessage> + Send + Sync + 'static, Self::Error> {
+        Ok(self.local.resume(id, last_event_id).await?)
+    }
+}
@@ -0,0 +1,44 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{LazyLock, OnceLock, RwLock},
+};
+
+/// Values of the configuration file, by environment variable.
+static FILE: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+
+/// Values the server sets itself at startup, such as the sandbox URL, by environment variable.
+static OVERRIDES: LazyLock<RwLock<HashMap<&'static str, String>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Use the configuration file's values for the settings not set in the environment.
+///
+/// Must be called at startup, before any setting is read.
+pub fn init(file: HashMap<&'static str, String>) {
+    if FILE.set(file).is_err() {
+        tracing::warn!("Configuration file settings were already initialized");
+    }
+}
+
+/// Use `value` for the setting `name`, over the environment and the configuration file.
+pub fn set(name: &'static str, value: String) {
+    OVERRIDES
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name, value);
+}
+
+/// Value of the setting read from the environment variable `name`, falling back to the
+/// configuration file.
+pub fn var(name: &str) -> Option<String> {
+    if let Some(value) = OVERRIDES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+    {
+        return Some(value.clone());
+    }
+    env::var(name)
+        .ok()
+        .or_else(|| FILE.get()?.get(name).cloned())
+}
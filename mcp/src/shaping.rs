@@ -0,0 +1,334 @@
+use std::{collections::BTreeMap, future::Future};
+
+use serde_json::{Map, Value};
+
+/// Tool argument restricting the result of a list or get tool to some fields.
+pub const FIELDS_ARG: &str = "fields";
+
+/// Tool argument choosing between full records and a summary for list tools.
+pub const OUTPUT_ARG: &str = "output";
+
+/// Top-level keys of a result that describe the listing rather than the records.
+const LISTING_KEYS: [&str; 4] = ["pagination", "meta", "total_count", "truncated"];
+
+/// Fields identifying a record, by order of preference.
+const ID_FIELDS: [&str; 5] = ["lago_id", "external_id", "transaction_id", "code", "number"];
+/// Fields naming the customer of a record, by order of preference.
+const CUSTOMER_FIELDS: [&str; 2] = ["external_customer_id", "customer_external_id"];
+/// Fields holding the main amount of a record, by order of preference.
+const AMOUNT_FIELDS: [&str; 4] = [
+    "total_amount_cents",
+    "amount_cents",
+    "balance_cents",
+    "total_due_amount_cents",
+];
+/// Fields holding the status of a record, by order of preference.
+const STATUS_FIELDS: [&str; 3] = ["status", "payment_status", "credit_status"];
+/// Fields holding the main date of a record, by order of preference.
+const DATE_FIELDS: [&str; 5] = [
+    "issuing_date",
+    "timestamp",
+    "started_at",
+    "created_at",
+    "updated_at",
+];
+
+/// How the result of a tool call is shaped before being returned.
+#[derive(Debug, Clone, Default)]
+pub struct Shaping {
+    /// Dotted paths of the fields to keep in each record.
+    fields: Option<Vec<String>>,
+    /// Replace the records of a list by digests and aggregates.
+    summary: bool,
+}
+
+/// Whether `tool_name` returns records that can be projected with `fields`.
+pub fn accepts_fields(tool_name: &str) -> bool {
+    ["list_", "get_", "find_"]
+        .iter()
+        .any(|prefix| tool_name.starts_with(prefix))
+}
+
+/// Whether `tool_name` returns a list that can be summarized with `output`.
+pub fn accepts_output(tool_name: &str) -> bool {
+    tool_name.starts_with("list_")
+}
+
+impl Shaping {
+    /// Take the shaping arguments a tool accepts out of its call arguments.
+    pub fn from_arguments(
+        tool_name: &str,
+        arguments: Option<&mut Map<String, Value>>,
+    ) -> Result<Self, String> {
+        let Some(arguments) = arguments else {
+            return Ok(Self::default());
+        };
+        let mut take = |name: &str, accepted: bool| {
+            accepted
+                .then(|| arguments.remove(name))
+                .flatten()
+                .filter(|value| !value.is_null())
+        };
+
+        let fields = take(FIELDS_ARG, accepts_fields(tool_name))
+            .map(parse_fields)
+            .transpose()?;
+        let summary = match take(OUTPUT_ARG, accepts_output(tool_name)) {
+            None => false,
+            Some(value) => match value.as_str() {
+                Some("full") => false,
+                Some("summary") => true,
+                _ => return Err("Invalid output: expected \"summary\" or \"full\"".to_string()),
+            },
+        };
+
+        Ok(Self { fields, summary })
+    }
+}
+
+/// Parse the `fields` argument: a list of dotted paths such as `customer.external_id`.
+fn parse_fields(value: Value) -> Result<Vec<String>, String> {
+    let invalid = || {
+        "Invalid fields: expected a list of field names such as [\"lago_id\", \"customer.external_id\"]".to_string()
+    };
+
+    let Value::Array(items) = value else {
+        return Err(invalid());
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::String(field) if !field.trim().is_empty() => Ok(field.trim().to_string()),
+            _ => Err(invalid()),
+        })
+        .collect()
+}
+
+tokio::task_local! {
+    static CALL_SHAPING: Shaping;
+}
+
+/// Run a tool call whose result is shaped as requested.
+pub async fn scope<F: Future>(shaping: Shaping, call: F) -> F::Output {
+    CALL_SHAPING.scope(shaping, call).await
+}
+
+/// Shape the result of a tool call as requested for it, in place.
+///
+/// A summary replaces the records of a list and takes precedence over `fields`.
+pub fn shape(value: &mut Value) {
+    let Ok(shaping) = CALL_SHAPING.try_with(Clone::clone) else {
+        return;
+    };
+
+    if shaping.summary {
+        summarize(value);
+    } else if let Some(fields) = &shaping.fields {
+        project_fields(value, fields);
+    }
+}
+
+/// Keep only the requested fields of the records of a tool result.
+///
+/// Records are the objects under the top-level keys (`invoice`, or each item of `invoices`);
+/// pagination and counts are kept as they are. Paths go through arrays, so `fees.amount_cents`
+/// keeps the amount of every fee. The `_display` and `_local` renderings of a kept field are
+/// kept with it.
+fn project_fields(value: &mut Value, fields: &[String]) {
+    let paths: Vec<Vec<&str>> = fields
+        .iter()
+        .map(|field| field.split('.').collect())
+        .collect();
+
+    let Value::Object(result) = value else {
+        return;
+    };
+    for (key, records) in result.iter_mut() {
+        if LISTING_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        match records {
+            Value::Array(items) => {
+                for item in items.iter_mut().filter(|item| item.is_object()) {
+                    *item = project(item, &paths);
+                }
+            }
+            Value::Object(_) => *records = project(records, &paths),
+            _ => {}
+        }
+    }
+}
+
+/// Replace every list of records of a tool result by one digest per record, and add the
+/// number of records and their total amount per currency.
+fn summarize(value: &mut Value) {
+    let Value::Object(result) = value else {
+        return;
+    };
+
+    let mut summary = Map::new();
+    for (key, records) in result.iter_mut() {
+        let Value::Array(items) = records else {
+            continue;
+        };
+        if LISTING_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+
+        let mut totals: BTreeMap<String, i64> = BTreeMap::new();
+        let digests: Vec<Value> = items
+            .iter()
+            .filter_map(Value::as_object)
+            .map(|record| {
+                let digest = digest(record);
+                if let (Some(amount), Some(currency)) = (
+                    digest.get("amount_cents").and_then(Value::as_i64),
+                    digest.get("currency").and_then(Value::as_str),
+                ) {
+                    *totals.entry(currency.to_string()).or_default() += amount;
+                }
+                Value::Object(digest)
+            })
+            .collect();
+
+        summary.insert(
+            key.clone(),
+            serde_json::json!({
+                "count": digests.len(),
+                "total_amount_cents_by_currency": totals,
+            }),
+        );
+        *records = Value::Array(digests);
+    }
+
+    if !summary.is_empty() {
+        result.insert("summary".to_string(), Value::Object(summary));
+    }
+}
+
+/// Compact view of a record: id, customer, amount, status and date.
+fn digest(record: &Map<String, Value>) -> Map<String, Value> {
+    let first = |fields: &[&str]| {
+        fields
+            .iter()
+            .find_map(|field| record.get(*field).filter(|value| !value.is_null()))
+            .cloned()
+    };
+    let mut digest = Map::new();
+
+    if let Some(id) = first(&ID_FIELDS) {
+        digest.insert("id".to_string(), id);
+    }
+
+    let customer = first(&CUSTOMER_FIELDS).or_else(|| {
+        record
+            .get("customer")
+            .and_then(|customer| customer.get("external_id"))
+            .cloned()
+    });
+    if let Some(customer) = customer {
+        digest.insert("customer".to_string(), customer);
+    }
+
+    if let Some(field) = AMOUNT_FIELDS
+        .iter()
+        .find(|field| record.get(**field).is_some_and(|value| !value.is_null()))
+    {
+        digest.insert("amount_cents".to_string(), record[*field].clone());
+        let prefix = field.strip_suffix("_cents").unwrap_or(field);
+        if let Some(display) = record.get(&format!("{prefix}_display")) {
+            digest.insert("amount_display".to_string(), display.clone());
+        }
+        let currency = record
+            .get(&format!("{prefix}_currency"))
+            .or_else(|| record.get("currency"));
+        if let Some(currency) = currency {
+            digest.insert("currency".to_string(), currency.clone());
+        }
+    }
+
+    if let Some(status) = first(&STATUS_FIELDS) {
+        digest.insert("status".to_string(), status);
+    }
+    if let Some(date) = first(&DATE_FIELDS) {
+        digest.insert("date".to_string(), date);
+    }
+
+    digest
+}
+
+fn project(value: &Value, paths: &[Vec<&str>]) -> Value {
+    match value {
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| project(item, paths)).collect())
+        }
+        Value::Object(object) => {
+            let mut projected = Map::new();
+
+            for path in paths {
+                let Some((first, rest)) = path.split_first() else {
+                    continue;
+                };
+                let Some(field) = object.get(*first) else {
+                    continue;
+                };
+
+                if rest.is_empty() {
+                    projected.insert(first.to_string(), field.clone());
+                    let renderings = [
+                        first
+                            .strip_suffix("_cents")
+                            .map(|prefix| format!("{prefix}_display")),
+                        Some(format!("{first}_local")),
+                    ];
+                    for rendering in renderings.into_iter().flatten() {
+                        if let Some(rendered) = object.get(&rendering) {
+                            projected.insert(rendering, rendered.clone());
+                        }
+                    }
+                    continue;
+                }
+
+                let nested = project(field, &[rest.to_vec()]);
+                match projected.get_mut(*first) {
+                    Some(existing) => merge(existing, nested),
+                    None => {
+                        projected.insert(first.to_string(), nested);
+                    }
+                }
+            }
+
+            if let Some(local_timezone) = object
+                .get("local_timezone")
+                .filter(|_| projected.keys().any(|key| key.ends_with("_local")))
+            {
+                projected.insert("local_timezone".to_string(), local_timezone.clone());
+            }
+
+            Value::Object(projected)
+        }
+        other => other.clone(),
+    }
+}
+
+/// Merge two projections of the same value, for paths sharing a prefix.
+fn merge(existing: &mut Value, other: Value) {
+    match (existing, other) {
+        (Value::Object(existing), Value::Object(other)) => {
+            for (key, value) in other {
+                match existing.get_mut(&key) {
+                    Some(current) => merge(current, value),
+                    None => {
+                        existing.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(existing), Value::Array(other)) => {
+            for (current, value) in existing.iter_mut().zip(other) {
+                merge(current, value);
+            }
+        }
+        _ => {}
+    }
+}
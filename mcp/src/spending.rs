@@ -0,0 +1,390 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::money;
+use crate::settings;
+use crate::tools::{LagoApiConfig, lago_request};
+
+/// Default location of the persisted spending counters.
+const DEFAULT_STATE_PATH: &str = "lago-mcp-spending.json";
+
+/// Caps of one tool, in cents of the amounts it sends to Lago. Unset caps are unlimited.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+struct SpendingCap {
+    daily: Option<i64>,
+    monthly: Option<i64>,
+}
+
+/// Caps per tool, applying to each credential, from `LAGO_MCP_SPENDING_CAPS`, e.g.
+/// `{"create_payment": {"daily": 100000, "monthly": 1000000}}`.
+static CAPS: LazyLock<HashMap<String, SpendingCap>> = LazyLock::new(|| {
+    let Some(caps) = settings::var("LAGO_MCP_SPENDING_CAPS") else {
+        return HashMap::new();
+    };
+
+    let caps: HashMap<String, SpendingCap> = serde_json::from_str(&caps).unwrap_or_else(|e| {
+        tracing::error!(error = %e, "Ignoring invalid LAGO_MCP_SPENDING_CAPS");
+        HashMap::new()
+    });
+    for tool_name in caps.keys() {
+        if amount_cents(tool_name, &serde_json::Map::new(), None).is_none() {
+            tracing::warn!(tool_name, "Spending caps are not supported for this tool");
+        }
+    }
+
+    caps
+});
+
+static STATE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    settings::var("LAGO_MCP_SPENDING_STATE_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_STATE_PATH))
+});
+
+/// Amounts spent by one tool with one credential in the current day and month (UTC).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Spent {
+    day: Option<NaiveDate>,
+    day_cents: i64,
+    month: Option<NaiveDate>,
+    month_cents: i64,
+}
+
+impl Spent {
+    /// Drop the counters of a day or month that is over.
+    fn roll(&mut self, today: NaiveDate) {
+        let month = today.with_day(1).unwrap_or(today);
+        if self.day != Some(today) {
+            self.day = Some(today);
+            self.day_cents = 0;
+        }
+        if self.month != Some(month) {
+            self.month = Some(month);
+            self.month_cents = 0;
+        }
+    }
+}
+
+/// Credential (as identified for quotas) -> tool -> amounts spent.
+type SpentByCredential = HashMap<String, HashMap<String, Spent>>;
+
+static SPENT: LazyLock<Mutex<SpentByCredential>> = LazyLock::new(|| {
+    let spent = match std::fs::read_to_string(&*STATE_PATH) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::error!(path = %STATE_PATH.display(), error = %e, "Ignoring unreadable spending counters");
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    };
+    Mutex::new(spent)
+});
+
+/// Amount in cents a call of `tool_name` sends to Lago, or `None` for tools that don't
+/// move money. Wallet top-ups count their paid and granted credits at `credit_cents`, the
+/// value of a credit of the wallet from [`wallet_credit_cents`].
+///
+/// Fails when the amount is missing, not a number or negative, or when credits are added to
+/// a wallet whose credit value is unknown, so that a call can't slip past a cap uncounted.
+pub fn amount_cents(
+    tool_name: &str,
+    arguments: &serde_json::Map<String, Value>,
+    credit_cents: Option<f64>,
+) -> Option<Result<i64, String>> {
+    match tool_name {
+        "create_payment" => Some(cents(
+            tool_name,
+            "amount_cents",
+            arguments.get("amount_cents"),
+        )),
+        // Events without a precise amount don't move money by themselves.
+        "create_event" => Some(match arguments.get("precise_total_amount_cents") {
+            None | Some(Value::Null) => Ok(0),
+            amount => cents(tool_name, "precise_total_amount_cents", amount),
+        }),
+        "create_invoice" => {
+            let Some(fees) = arguments.get("fees").and_then(Value::as_array) else {
+                return Some(Err(
+                    "create_invoice amounts are checked: fees are required".to_string()
+                ));
+            };
+            let total = fees.iter().try_fold(0i64, |total, fee| {
+                let unit_amount_cents = cents(
+                    tool_name,
+                    "every fee's unit_amount_cents",
+                    fee.get("unit_amount_cents"),
+                )?;
+                let units = non_negative(tool_name, "every fee's units", fee.get("units"))?;
+                Ok(total + (unit_amount_cents as f64 * units).round() as i64)
+            });
+            Some(total)
+        }
+        "create_wallet_transaction" => {
+            let credits = ["paid_credits", "granted_credits"]
+                .into_iter()
+                .filter_map(|field| {
+                    let credits = arguments.get(field).filter(|value| !value.is_null())?;
+                    Some(non_negative(tool_name, field, Some(credits)))
+                })
+                .sum::<Result<f64, String>>();
+            Some(credits.and_then(|credits| match credit_cents {
+                _ if credits == 0.0 => Ok(0),
+                Some(credit_cents) => Ok((credits * credit_cents).round() as i64),
+                None => Err(format!(
+                    "{tool_name} amounts are checked: the value of the wallet's credits (its rate_amount) could not be read from Lago"
+                )),
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// A non-negative whole number of cents.
+fn cents(tool_name: &str, field: &str, value: Option<&Value>) -> Result<i64, String> {
+    value
+        .and_then(Value::as_i64)
+        .filter(|cents| *cents >= 0)
+        .ok_or_else(|| {
+            format!("{tool_name} amounts are checked: {field} must be a non-negative integer")
+        })
+}
+
+/// A non-negative number, given as a JSON number or a decimal string such as `"100.0"`.
+fn non_negative(tool_name: &str, field: &str, value: Option<&Value>) -> Result<f64, String> {
+    let number = match value {
+        Some(Value::Number(number)) => number.as_f64(),
+        Some(Value::String(number)) => number.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    number
+        .filter(|number| number.is_finite() && *number >= 0.0)
+        .ok_or_else(|| {
+            format!("{tool_name} amounts are checked: {field} must be a non-negative number")
+        })
+}
+
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+/// Value in cents of one credit of the wallet a `create_wallet_transaction` call credits: the
+/// wallet's `rate_amount` in its currency. `None` when the wallet can't be read.
+pub async fn wallet_credit_cents(
+    config: &LagoApiConfig,
+    arguments: Option<&serde_json::Map<String, Value>>,
+) -> Option<f64> {
+    let wallet_id = arguments?.get("wallet_id")?.as_str()?;
+    let path = format!("/wallets/{}", urlencoding::encode(wallet_id));
+    let response = lago_request(&HTTP_CLIENT, config, reqwest::Method::GET, &path, &[], None)
+        .await
+        .inspect_err(
+            |e| tracing::warn!(wallet_id, error = %e, "Failed to read the wallet's credit value"),
+        )
+        .ok()?;
+
+    let wallet = &response["wallet"];
+    let rate_amount = match &wallet["rate_amount"] {
+        Value::String(rate_amount) => rate_amount.trim().parse::<f64>().ok()?,
+        rate_amount => rate_amount.as_f64()?,
+    };
+    let currency = wallet["currency"].as_str()?.to_uppercase();
+    let credit_cents = rate_amount * 10f64.powi(money::minor_unit_exponent(&currency) as i32);
+    (credit_cents.is_finite() && credit_cents >= 0.0).then_some(credit_cents)
+}
+
+/// Whether calls of `tool_name` count against spending caps.
+pub fn is_capped(tool_name: &str) -> bool {
+    CAPS.contains_key(tool_name)
+}
+
+/// Version of the counters, bumped on every change, so that an older snapshot written late
+/// doesn't replace a newer one.
+static VERSION: AtomicU64 = AtomicU64::new(0);
+
+/// Version of the counters last written to the state file.
+static SAVED_VERSION: Mutex<u64> = Mutex::new(0);
+
+/// Write the counters to the state file, off the async runtime and outside of the counters'
+/// lock, which is only held to take a snapshot.
+fn save(spent: &SpentByCredential) {
+    let version = VERSION.fetch_add(1, Ordering::Relaxed) + 1;
+    let contents = match serde_json::to_vec_pretty(spent) {
+        Ok(contents) => contents,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to serialize spending counters");
+            return;
+        }
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let mut saved_version = SAVED_VERSION.lock().unwrap_or_else(|e| e.into_inner());
+        if *saved_version > version {
+            return;
+        }
+        let temporary_path = STATE_PATH.with_extension("json.tmp");
+        let result = std::fs::write(&temporary_path, contents)
+            .and_then(|_| std::fs::rename(&temporary_path, &*STATE_PATH));
+        match result {
+            Ok(()) => *saved_version = version,
+            Err(e) => {
+                tracing::error!(path = %STATE_PATH.display(), error = %e, "Failed to persist spending counters")
+            }
+        }
+    });
+}
+
+/// Count the amount of a call made with `credential` against its tool's caps.
+///
+/// Returns the amount reserved, to [`release`] if the call fails before reaching Lago, or a
+/// JSON error explaining the remaining allowance when a cap would be exceeded.
+pub fn reserve(
+    credential: &str,
+    tool_name: &str,
+    arguments: Option<&serde_json::Map<String, Value>>,
+    credit_cents: Option<f64>,
+) -> Result<i64, String> {
+    let Some(cap) = CAPS.get(tool_name) else {
+        return Ok(0);
+    };
+    let empty = serde_json::Map::new();
+    let amount = match amount_cents(tool_name, arguments.unwrap_or(&empty), credit_cents) {
+        Some(amount) => amount?,
+        None => return Ok(0),
+    };
+
+    let today = Utc::now().date_naive();
+    let mut spent = SPENT.lock().unwrap_or_else(|e| e.into_inner());
+    let tool_spent = spent
+        .entry(credential.to_string())
+        .or_default()
+        .entry(tool_name.to_string())
+        .or_default();
+    tool_spent.roll(today);
+
+    let windows = [
+        ("daily", cap.daily, tool_spent.day_cents, today.succ_opt()),
+        (
+            "monthly",
+            cap.monthly,
+            tool_spent.month_cents,
+            today
+                .with_day(1)
+                .and_then(|month| month.checked_add_months(Months::new(1))),
+        ),
+    ];
+    for (window, cap_cents, spent_cents, resets_on) in windows {
+        let Some(cap_cents) = cap_cents else {
+            continue;
+        };
+        if spent_cents + amount > cap_cents {
+            let error = serde_json::json!({
+                "error": "spending_cap_exceeded",
+                "message": format!("{tool_name} would exceed its {window} spending cap"),
+                "tool": tool_name,
+                "window": window,
+                "cap_cents": cap_cents,
+                "spent_cents": spent_cents,
+                "requested_cents": amount,
+                "remaining_cents": (cap_cents - spent_cents).max(0),
+                "resets_on": resets_on,
+            });
+            return Err(serde_json::to_string_pretty(&error).unwrap_or_default());
+        }
+    }
+
+    tool_spent.day_cents += amount;
+    tool_spent.month_cents += amount;
+    save(&spent);
+
+    Ok(amount)
+}
+
+/// Give back the amount reserved for a call that failed without being applied by Lago.
+pub fn release(credential: &str, tool_name: &str, amount: i64) {
+    if amount == 0 {
+        return;
+    }
+
+    let mut spent = SPENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(tool_spent) = spent
+        .get_mut(credential)
+        .and_then(|tools| tools.get_mut(tool_name))
+    {
+        tool_spent.day_cents = (tool_spent.day_cents - amount).max(0);
+        tool_spent.month_cents = (tool_spent.month_cents - amount).max(0);
+        save(&spent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn arguments(value: Value) -> serde_json::Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[tokio::test]
+    async fn caps_apply_to_each_credential() {
+        let state_path =
+            std::env::temp_dir().join(format!("lago-mcp-spending-{}.json", std::process::id()));
+        settings::set(
+            "LAGO_MCP_SPENDING_STATE_PATH",
+            state_path.display().to_string(),
+        );
+        settings::set(
+            "LAGO_MCP_SPENDING_CAPS",
+            r#"{"create_payment": {"daily": 1000}}"#.to_string(),
+        );
+        let payment = arguments(json!({ "amount_cents": 800 }));
+
+        assert_eq!(
+            reserve("tenant:acme", "create_payment", Some(&payment), None),
+            Ok(800)
+        );
+        assert!(reserve("tenant:acme", "create_payment", Some(&payment), None).is_err());
+        assert_eq!(
+            reserve("tenant:globex", "create_payment", Some(&payment), None),
+            Ok(800)
+        );
+
+        release("tenant:acme", "create_payment", 800);
+        assert_eq!(
+            reserve("tenant:acme", "create_payment", Some(&payment), None),
+            Ok(800)
+        );
+        let _ = std::fs::remove_file(state_path);
+    }
+
+    #[test]
+    fn wallet_credits_are_counted_at_the_wallet_rate() {
+        let top_up = arguments(json!({
+            "wallet_id": "wallet_1",
+            "paid_credits": "2.0",
+            "granted_credits": "1",
+        }));
+        let void = arguments(json!({ "wallet_id": "wallet_1", "voided_credits": "5" }));
+
+        assert_eq!(
+            amount_cents("create_wallet_transaction", &top_up, Some(250.0)),
+            Some(Ok(750))
+        );
+        assert!(matches!(
+            amount_cents("create_wallet_transaction", &top_up, None),
+            Some(Err(_))
+        ));
+        assert_eq!(
+            amount_cents("create_wallet_transaction", &void, None),
+            Some(Ok(0))
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    collections::BTreeMap,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+
+static STARTED_AT: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+static TOOL_STATS: LazyLock<Mutex<BTreeMap<String, ToolStats>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Cache name -> (hits, misses).
+static CACHE_STATS: LazyLock<Mutex<BTreeMap<&'static str, (u64, u64)>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+#[derive(Debug, Default)]
+struct ToolStats {
+    calls: u64,
+    errors: u64,
+    total_latency: Duration,
+    max_latency: Duration,
+}
+
+/// Start the uptime clock. Called once when the server starts.
+pub fn init() {
+    LazyLock::force(&STARTED_AT);
+}
+
+/// Record the outcome of a tool call.
+///
+/// Calls returning a tool error result count as errors, as do protocol-level failures.
+pub fn record_tool_call(tool_name: &str, latency: Duration, is_error: bool) {
+    let mut tool_stats = TOOL_STATS.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = tool_stats.entry(tool_name.to_string()).or_default();
+
+    stats.calls += 1;
+    if is_error {
+        stats.errors += 1;
+    }
+    stats.total_latency += latency;
+    stats.max_latency = stats.max_latency.max(latency);
+}
+
+/// Record a lookup in the cache `cache`, answered from it when `hit`.
+pub fn record_cache_lookup(cache: &'static str, hit: bool) {
+    let mut cache_stats = CACHE_STATS.lock().unwrap_or_else(|e| e.into_inner());
+    let (hits, misses) = cache_stats.entry(cache).or_default();
+    if hit {
+        *hits += 1;
+    } else {
+        *misses += 1;
+    }
+}
+
+/// Per-tool call counts, error rates and latencies, and cache hit rates, since startup.
+pub fn snapshot() -> Value {
+    let tool_stats = TOOL_STATS.lock().unwrap_or_else(|e| e.into_inner());
+    let cache_stats = CACHE_STATS.lock().unwrap_or_else(|e| e.into_inner());
+
+    let tools: Vec<Value> = tool_stats
+        .iter()
+        .map(|(name, stats)| {
+            serde_json::json!({
+                "name": name,
+                "calls": stats.calls,
+                "errors": stats.errors,
+                "error_rate": stats.errors as f64 / stats.calls as f64,
+                "average_latency_ms": stats.total_latency.as_millis() as u64 / stats.calls,
+                "max_latency_ms": stats.max_latency.as_millis() as u64,
+            })
+        })
+        .collect();
+
+    let caches: Vec<Value> = cache_stats
+        .iter()
+        .map(|(name, (hits, misses))| {
+            serde_json::json!({
+                "name": name,
+                "hits": hits,
+                "misses": misses,
+                "hit_rate": *hits as f64 / (hits + misses) as f64,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "uptime_secs": STARTED_AT.elapsed().as_secs(),
+        "total_calls": tool_stats.values().map(|stats| stats.calls).sum::<u64>(),
+        "total_errors": tool_stats.values().map(|stats| stats.errors).sum::<u64>(),
+        "tools": tools,
+        "caches": caches,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_hit_rates_are_reported() {
+        record_cache_lookup("test_cache", false);
+        record_cache_lookup("test_cache", true);
+        record_cache_lookup("test_cache", true);
+        record_cache_lookup("test_cache", true);
+
+        let snapshot = snapshot();
+        let cache = snapshot["caches"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|cache| cache["name"] == "test_cache")
+            .unwrap();
+        assert_eq!(cache["hits"], 3);
+        assert_eq!(cache["misses"], 1);
+        assert_eq!(cache["hit_rate"], 0.75);
+    }
+}
@@ -0,0 +1,90 @@
+use std::{collections::HashMap, sync::LazyLock, time::Duration};
+
+use crate::settings;
+use crate::tools::is_write_tool;
+
+/// Timeout of a tool call when neither its tool nor its class has one configured.
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Tools that aggregate many Lago requests, in the `analytics` class.
+const ANALYTICS_PREFIXES: &[&str] = &["report_", "estimate_"];
+const ANALYTICS_TOOLS: &[&str] = &["get_invoiced_usage", "get_overdue_balance", "get_dso"];
+
+/// Timeout of every tool call, from `LAGO_MCP_TOOL_TIMEOUT_SECS`.
+static DEFAULT_TIMEOUT: LazyLock<Duration> = LazyLock::new(|| {
+    let Some(value) = settings::var("LAGO_MCP_TOOL_TIMEOUT_SECS") else {
+        return Duration::from_secs(DEFAULT_TIMEOUT_SECS);
+    };
+    match value.trim().parse::<u64>() {
+        Ok(secs) if secs > 0 => Duration::from_secs(secs),
+        _ => {
+            tracing::warn!(value, "Ignoring invalid LAGO_MCP_TOOL_TIMEOUT_SECS");
+            Duration::from_secs(DEFAULT_TIMEOUT_SECS)
+        }
+    }
+});
+
+/// Timeouts in seconds per tool class (`get`, `list`, `analytics`, `write`) or tool name, from
+/// `LAGO_MCP_TOOL_TIMEOUTS`, e.g. `{"analytics": 180, "get": 15, "flush_events": 120}`.
+static TIMEOUTS: LazyLock<HashMap<String, u64>> = LazyLock::new(|| {
+    let Some(timeouts) = settings::var("LAGO_MCP_TOOL_TIMEOUTS") else {
+        return HashMap::new();
+    };
+
+    let mut timeouts: HashMap<String, u64> = serde_json::from_str(&timeouts).unwrap_or_else(|e| {
+        tracing::error!(error = %e, "Ignoring invalid LAGO_MCP_TOOL_TIMEOUTS");
+        HashMap::new()
+    });
+    timeouts.retain(|key, secs| {
+        if *secs == 0 {
+            tracing::warn!(key, "Ignoring a zero tool timeout");
+        }
+        *secs > 0
+    });
+
+    timeouts
+});
+
+/// Class of a tool, for timeouts configured per class.
+fn tool_class(tool_name: &str) -> Option<&'static str> {
+    if ANALYTICS_TOOLS.contains(&tool_name)
+        || ANALYTICS_PREFIXES
+            .iter()
+            .any(|prefix| tool_name.starts_with(prefix))
+    {
+        Some("analytics")
+    } else if is_write_tool(tool_name) {
+        Some("write")
+    } else if tool_name.starts_with("get_") || tool_name.starts_with("find_") {
+        Some("get")
+    } else if tool_name.starts_with("list_") {
+        Some("list")
+    } else {
+        None
+    }
+}
+
+/// Time a call of `tool_name` may take: its own timeout, else its class's, else the default.
+pub fn timeout_for(tool_name: &str) -> Duration {
+    TIMEOUTS
+        .get(tool_name)
+        .or_else(|| tool_class(tool_name).and_then(|class| TIMEOUTS.get(class)))
+        .map(|secs| Duration::from_secs(*secs))
+        .unwrap_or(*DEFAULT_TIMEOUT)
+}
+
+/// JSON error returned for a call that didn't finish in time.
+pub fn timeout_error(tool_name: &str, timeout: Duration) -> String {
+    let mut message = format!("{tool_name} timed out after {}s", timeout.as_secs());
+    if is_write_tool(tool_name) {
+        message.push_str("; the change may still have been applied in Lago, check before retrying");
+    }
+
+    let error = serde_json::json!({
+        "error": "timeout",
+        "message": message,
+        "tool": tool_name,
+        "timeout_secs": timeout.as_secs(),
+    });
+    serde_json::to_string_pretty(&error).unwrap_or_default()
+}
@@ -0,0 +1,145 @@
+use std::{future::Future, sync::LazyLock};
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde_json::{Map, Value};
+
+use crate::settings;
+
+/// Tool argument overriding the display timezone for one call.
+pub const DISPLAY_TIMEZONE_ARG: &str = "display_timezone";
+
+/// Timezone in which timestamps of tool results are rendered, next to their UTC value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayTimezone {
+    /// The `applicable_timezone` of the customer a resource belongs to, which Lago falls
+    /// back to the organization's timezone for.
+    Customer,
+    Fixed(Tz),
+}
+
+impl DisplayTimezone {
+    /// Parse `customer`, an IANA timezone name, or `utc` (timestamps are left as they are).
+    pub fn parse(value: &str) -> Result<Option<Self>, String> {
+        let value = value.trim();
+
+        if value.is_empty() || value.eq_ignore_ascii_case("utc") {
+            return Ok(None);
+        }
+        if value.eq_ignore_ascii_case("customer") {
+            return Ok(Some(Self::Customer));
+        }
+
+        value
+            .parse::<Tz>()
+            .map(|tz| Some(Self::Fixed(tz)))
+            .map_err(|_| {
+                format!("Invalid display timezone {value}: expected utc, customer, or an IANA timezone name such as Europe/Paris")
+            })
+    }
+}
+
+/// Display timezone from `LAGO_MCP_DISPLAY_TIMEZONE`; timestamps stay in UTC when unset.
+static DEFAULT_DISPLAY_TIMEZONE: LazyLock<Option<DisplayTimezone>> = LazyLock::new(|| {
+    let value = settings::var("LAGO_MCP_DISPLAY_TIMEZONE").unwrap_or_default();
+
+    DisplayTimezone::parse(&value).unwrap_or_else(|e| {
+        tracing::warn!("{e}, rendering timestamps in UTC");
+        None
+    })
+});
+
+tokio::task_local! {
+    static CALL_DISPLAY_TIMEZONE: Option<DisplayTimezone>;
+}
+
+/// Run a tool call with the display timezone requested for it.
+///
+/// `None` keeps the server default; `Some(None)` renders this call in UTC only.
+pub async fn scope<F: Future>(
+    display_timezone: Option<Option<DisplayTimezone>>,
+    call: F,
+) -> F::Output {
+    match display_timezone {
+        Some(display_timezone) => CALL_DISPLAY_TIMEZONE.scope(display_timezone, call).await,
+        None => call.await,
+    }
+}
+
+fn current_display_timezone() -> Option<DisplayTimezone> {
+    CALL_DISPLAY_TIMEZONE
+        .try_with(|display_timezone| *display_timezone)
+        .unwrap_or(*DEFAULT_DISPLAY_TIMEZONE)
+}
+
+/// Add a local-time rendering next to every UTC timestamp of a JSON value, in place.
+///
+/// `created_at` gets a `created_at_local` sibling and the object a `local_timezone` field
+/// naming the zone used. Date-only fields such as `issuing_date` are already expressed in
+/// the customer's timezone by Lago and are left alone.
+pub fn localize_timestamps(value: &mut Value) {
+    let Some(display_timezone) = current_display_timezone() else {
+        return;
+    };
+
+    localize(value, display_timezone, None);
+}
+
+/// Timezone of the customer an object describes or belongs to.
+fn customer_timezone(map: &Map<String, Value>) -> Option<Tz> {
+    map.get("applicable_timezone")
+        .or_else(|| {
+            map.get("customer")
+                .and_then(|c| c.get("applicable_timezone"))
+        })
+        .and_then(Value::as_str)
+        .and_then(|tz| tz.parse().ok())
+}
+
+/// `inherited` is the customer timezone of the closest enclosing object that has one.
+fn localize(value: &mut Value, display_timezone: DisplayTimezone, inherited: Option<Tz>) {
+    match value {
+        Value::Object(map) => {
+            let timezone = match display_timezone {
+                DisplayTimezone::Fixed(tz) => Some(tz),
+                DisplayTimezone::Customer => customer_timezone(map).or(inherited),
+            };
+
+            if let Some(tz) = timezone {
+                localize_object(map, tz);
+            }
+            for value in map.values_mut() {
+                localize(value, display_timezone, timezone);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                localize(item, display_timezone, inherited);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn localize_object(map: &mut Map<String, Value>, tz: Tz) {
+    let local_values: Vec<(String, String)> = map
+        .iter()
+        .filter(|(key, _)| !key.ends_with("_local"))
+        .filter_map(|(key, value)| {
+            let timestamp = DateTime::parse_from_rfc3339(value.as_str()?).ok()?;
+            let local = timestamp.with_timezone(&Utc).with_timezone(&tz);
+            Some((format!("{key}_local"), local.to_rfc3339()))
+        })
+        .collect();
+
+    if local_values.is_empty() {
+        return;
+    }
+    for (key, value) in local_values {
+        map.insert(key, Value::String(value));
+    }
+    map.insert(
+        "local_timezone".to_string(),
+        Value::String(tz.name().to_string()),
+    );
+}
@@ -1,4 +1,5 @@
 pub mod activity_log;
+pub mod analytics;
 pub mod api_log;
 pub mod applied_coupon;
 pub mod billable_metric;
@@ -8,20 +9,115 @@ pub mod customer;
 pub mod customer_usage;
 pub mod event;
 pub mod invoice;
+pub mod invoice_custom_section;
 pub mod payment;
 pub mod plan;
+pub mod report;
 pub mod subscription;
+pub mod tax;
+pub mod wallet;
+pub mod webhook;
 
-use lago_client::{
-    Config, Credentials, EnvironmentRegionProvider, LagoClient, Region, RegionProvider,
-};
+use lago_client::{Config, Credentials, LagoClient, Region};
 use rmcp::{
     RoleServer,
-    model::{CallToolResult, Content},
+    model::{CallToolResult, Content, ToolAnnotations},
     service::RequestContext,
 };
 use serde::Serialize;
-use std::env;
+use serde_json::Value;
+
+use crate::credentials::SessionCredentials;
+use crate::errors::{self, ErrorCode};
+use crate::money;
+use crate::outbound;
+use crate::principal::Principal;
+use crate::progress;
+use crate::redaction;
+use crate::relay;
+use crate::settings;
+use crate::shaping;
+use crate::timezone;
+
+/// Tools that create, modify or delete Lago data.
+pub const WRITE_TOOLS: &[&str] = &[
+    "create_invoice",
+    "update_invoice",
+    "refresh_invoice",
+    "retry_invoice",
+    "retry_invoice_payment",
+    "void_invoice",
+    "lose_invoice_dispute",
+    "create_customer",
+    "update_customer",
+    "delete_customer",
+    "create_subscription",
+    "update_subscription",
+    "delete_subscription",
+    "update_subscription_lifetime_usage",
+    "create_billable_metric",
+    "update_billable_metric",
+    "apply_coupon",
+    "create_coupon",
+    "update_coupon",
+    "delete_coupon",
+    "create_event",
+    "queue_event",
+    "flush_events",
+    "create_credit_note",
+    "update_credit_note",
+    "create_plan",
+    "update_plan",
+    "delete_plan",
+    "create_payment",
+    "create_wallet_transaction",
+    "create_tax",
+    "update_tax",
+    "delete_tax",
+    "create_invoice_custom_section",
+    "update_invoice_custom_section",
+    "delete_invoice_custom_section",
+    "set_customer_invoice_custom_sections",
+];
+
+pub fn is_write_tool(tool_name: &str) -> bool {
+    WRITE_TOOLS.contains(&tool_name)
+}
+
+/// Write tools that only add data; the others may change or remove existing data.
+const ADDITIVE_WRITE_PREFIXES: &[&str] = &["create_", "apply_", "queue_", "flush_", "retry_"];
+
+/// Write tools that have no further effect when repeated with the same arguments.
+const IDEMPOTENT_WRITE_PREFIXES: &[&str] =
+    &["update_", "delete_", "set_", "void_", "lose_", "refresh_"];
+
+/// MCP annotations describing the effect of a tool, for clients' approval policies.
+pub fn tool_annotations(tool_name: &str) -> ToolAnnotations {
+    let has_prefix = |prefixes: &[&str]| prefixes.iter().any(|p| tool_name.starts_with(p));
+    // Lago creates customers by upserting them on their external_id.
+    let upsert = tool_name == "create_customer";
+
+    let (read_only, destructive, idempotent) = if !is_write_tool(tool_name) {
+        (true, false, true)
+    } else {
+        (
+            false,
+            upsert || !has_prefix(ADDITIVE_WRITE_PREFIXES),
+            upsert || has_prefix(IDEMPOTENT_WRITE_PREFIXES),
+        )
+    };
+
+    ToolAnnotations {
+        title: None,
+        read_only_hint: Some(read_only),
+        destructive_hint: Some(destructive),
+        idempotent_hint: Some(idempotent),
+        open_world_hint: Some(false),
+    }
+}
+
+/// Hard cap on the number of records a `fetch_all` listing walks through.
+pub const FETCH_ALL_MAX_RECORDS: usize = 1000;
 
 pub struct LagoApiConfig {
     pub api_key: String,
@@ -31,77 +127,295 @@ pub struct LagoApiConfig {
 pub async fn get_lago_api_config(
     context: &RequestContext<RoleServer>,
 ) -> Result<LagoApiConfig, CallToolResult> {
-    let (header_key, header_url) = context
-        .extensions
-        .get::<axum::http::request::Parts>()
-        .map(|parts| {
-            let key = parts
-                .headers
-                .get("X-LAGO-API-KEY")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string());
-            let url = env::var("LAGO_API_URL").ok();
-            (key, url)
-        })
-        .unwrap_or((None, None));
+    resolve_api_config(
+        context.extensions.get::<axum::http::request::Parts>(),
+        context.extensions.get::<SessionCredentials>(),
+    )
+    .map_err(error_result)
+}
 
-    if let (Some(key), Some(url)) = (header_key, header_url) {
+/// Lago credentials of a call: the key mapped from the caller's identity or sent in
+/// `X-LAGO-API-KEY`, else the session's, else the server's.
+///
+/// A caller's own key is never swapped for the server's, which belongs to another tenant.
+fn resolve_api_config(
+    parts: Option<&axum::http::request::Parts>,
+    session: Option<&SessionCredentials>,
+) -> Result<LagoApiConfig, String> {
+    let caller_key = parts.and_then(|parts| {
+        parts
+            .extensions
+            .get::<Principal>()
+            .and_then(|principal| principal.api_key.clone())
+            .or_else(|| {
+                parts
+                    .headers
+                    .get("X-LAGO-API-KEY")
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string())
+            })
+    });
+    if let Some(api_key) = caller_key {
+        redaction::register_secret(&api_key);
         return Ok(LagoApiConfig {
-            api_key: key,
-            base_url: url,
+            api_key,
+            base_url: default_api_url(),
         });
     }
 
-    let api_key = env::var("LAGO_API_KEY")
-        .map_err(|_| error_result("LAGO_API_KEY environment variable not set"))?;
-    let region = EnvironmentRegionProvider::new()
-        .provider_region()
-        .map_err(|e| error_result(format!("Failed to resolve region: {e}")))?;
-    let base_url = region.endpoint().to_string();
+    if let Some(credentials) = session {
+        let base_url = match &credentials.api_url {
+            Some(api_url) => api_url.clone(),
+            None => default_api_url(),
+        };
+        return Ok(LagoApiConfig {
+            api_key: credentials.api_key.clone(),
+            base_url,
+        });
+    }
+
+    lago_api_config_from_env()
+}
+
+/// Lago region configured by `LAGO_REGION` (`us`, `eu` or an endpoint URL), else
+/// `LAGO_API_URL`, else the default region.
+fn configured_region() -> Region {
+    match settings::var("LAGO_REGION") {
+        Some(region) => match region.to_lowercase().as_str() {
+            "us" => Region::Us,
+            "eu" => Region::Eu,
+            _ => Region::Custom(region),
+        },
+        None => settings::var("LAGO_API_URL")
+            .map(Region::Custom)
+            .unwrap_or_default(),
+    }
+}
+
+/// Lago API URL configured in the environment, or the default region's.
+pub fn default_api_url() -> String {
+    configured_region().endpoint().to_string()
+}
+
+/// Resolve the Lago API credentials and endpoint from the environment only.
+pub fn lago_api_config_from_env() -> Result<LagoApiConfig, String> {
+    let api_key = settings::var("LAGO_API_KEY").ok_or_else(|| {
+        errors::record(ErrorCode::Auth);
+        "LAGO_API_KEY environment variable not set".to_string()
+    })?;
+    let base_url = default_api_url();
 
     Ok(LagoApiConfig { api_key, base_url })
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum LagoHttpError {
+    #[error("HTTP {status}: {body}")]
+    Status {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+    #[error("{0}")]
+    Transport(#[from] reqwest::Error),
+    #[error("Invalid JSON in the Lago API response: {0}")]
+    InvalidJson(serde_json::Error),
+}
+
+/// Send a request to the Lago API and decode its JSON body.
+///
+/// Used for endpoints that are not covered by `lago-client`, and by tools that need
+/// the raw payloads. Empty success bodies decode to `Value::Null`, other bodies that are not
+/// JSON are an error. Requests rate limited by
+/// Lago are retried after the delay it asks for, as long as the tool call has time left.
+pub async fn lago_request(
+    http_client: &reqwest::Client,
+    config: &LagoApiConfig,
+    method: reqwest::Method,
+    path: &str,
+    query: &[(&str, String)],
+    body: Option<&Value>,
+) -> Result<Value, LagoHttpError> {
+    let url = format!("{}{}", config.base_url, path);
+    let build_request = || {
+        let mut request = http_client
+            .request(method.clone(), &url)
+            .bearer_auth(&config.api_key)
+            .query(query);
+        request = outbound::with_headers(request);
+
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        request
+    };
+
+    let (response, _permit) = outbound::send(build_request).await.inspect_err(|_| {
+        errors::record(ErrorCode::Network);
+    })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        errors::record_lago_failure(status.as_u16(), &body);
+        return Err(LagoHttpError::Status { status, body });
+    }
+
+    let bytes = response.bytes().await.inspect_err(|_| {
+        errors::record(ErrorCode::Network);
+    })?;
+    if bytes.is_empty() {
+        return Ok(Value::Null);
+    }
+
+    serde_json::from_slice(&bytes).map_err(|e| {
+        errors::record(ErrorCode::Error);
+        LagoHttpError::InvalidJson(e)
+    })
+}
+
+/// Walk every page of a Lago list endpoint and collect the items stored under `key`.
+///
+/// Stops after `max_records` items; the returned flag is true when more pages remained.
+pub async fn lago_get_all(
+    http_client: &reqwest::Client,
+    config: &LagoApiConfig,
+    path: &str,
+    query: &[(&str, String)],
+    key: &str,
+    max_records: usize,
+) -> Result<(Vec<Value>, bool), LagoHttpError> {
+    let mut items = Vec::new();
+    let mut page = 1;
+
+    loop {
+        let mut page_query = query.to_vec();
+        page_query.push(("page", page.to_string()));
+        page_query.push(("per_page", "100".to_string()));
+
+        let response = lago_request(
+            http_client,
+            config,
+            reqwest::Method::GET,
+            path,
+            &page_query,
+            None,
+        )
+        .await?;
+
+        if let Some(page_items) = response[key].as_array() {
+            items.extend(page_items.iter().cloned());
+        }
+
+        let total_count = response["meta"]["total_count"]
+            .as_u64()
+            .map(|total_count| (total_count as usize).min(max_records));
+        let fetched = items.len().min(max_records);
+        progress::report(fetched, total_count, format!("Fetched {fetched} {key}")).await;
+
+        match response["meta"]["next_page"].as_i64() {
+            Some(next_page) if items.len() < max_records => page = next_page,
+            next_page => {
+                let truncated = next_page.is_some() || items.len() > max_records;
+                items.truncate(max_records);
+                return Ok((items, truncated));
+            }
+        }
+    }
+}
+
 pub async fn create_lago_client(
     context: &RequestContext<RoleServer>,
 ) -> Result<LagoClient, CallToolResult> {
-    let (header_key, header_url) = context
-        .extensions
-        .get::<axum::http::request::Parts>()
-        .map(|parts| {
-            let key = parts
-                .headers
-                .get("X-LAGO-API-KEY")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string());
-            let url = env::var("LAGO_API_URL").ok();
-            (key, url)
-        })
-        .unwrap_or((None, None));
-
-    if let (Some(key), Some(url)) = (header_key, header_url) {
-        let credentials = Credentials::new(key);
-        let region = Region::Custom(url);
-        let config = Config::builder()
-            .credentials(credentials)
-            .region(region)
-            .build();
-        return Ok(LagoClient::new(config));
-    }
+    let config = resolve_api_config(
+        context.extensions.get::<axum::http::request::Parts>(),
+        context.extensions.get::<SessionCredentials>(),
+    )
+    .map_err(|e| error_result(format!("Failed to create lago client: {e}")))?;
+    Ok(lago_client(config.api_key, Region::Custom(config.base_url)).await)
+}
 
-    LagoClient::from_env().map_err(|e| {
-        let error_message = format!("Failed to create lago client: {e}");
-        error_result(error_message)
-    })
+/// A `lago-client` for `region`, whose requests are relayed through the current tool call.
+async fn lago_client(api_key: String, region: Region) -> LagoClient {
+    let endpoint = relay::endpoint(region.endpoint().to_string()).await;
+    let config = Config::builder()
+        .credentials(Credentials::new(api_key))
+        .region(Region::Custom(endpoint))
+        .build();
+    LagoClient::new(config)
 }
 
+/// Serialize a tool's result, with a display value added next to every amount in cents
+/// and, when a display timezone is set, a local rendering next to every timestamp.
 pub fn success_result<T: Serialize>(data: &T) -> CallToolResult {
-    CallToolResult::success(vec![Content::text(
-        serde_json::to_string_pretty(data)
-            .unwrap_or_else(|_| "Failed to serialize result".to_string()),
-    )])
+    let text = serde_json::to_value(data)
+        .and_then(|mut value| {
+            money::annotate_amounts(&mut value);
+            timezone::localize_timestamps(&mut value);
+            shaping::shape(&mut value);
+            serde_json::to_string_pretty(&value)
+        })
+        .unwrap_or_else(|_| "Failed to serialize result".to_string());
+
+    CallToolResult::success(vec![Content::text(text)])
 }
 
+/// Error result returned to the client, with secrets and customer PII redacted.
 pub fn error_result(message: impl Into<String>) -> CallToolResult {
-    CallToolResult::error(vec![Content::text(message.into())])
+    let message = redaction::redact_text(&message.into());
+    CallToolResult::error(vec![Content::text(message)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal_parts(api_key: Option<&str>) -> axum::http::request::Parts {
+        let (mut parts, ()) = axum::http::Request::new(()).into_parts();
+        parts.extensions.insert(Principal {
+            subject: "jwt:alice".to_string(),
+            tenant: Some("acme".to_string()),
+            roles: Vec::new(),
+            api_key: api_key.map(str::to_string),
+            can_write: true,
+        });
+        parts
+    }
+
+    #[test]
+    fn tenant_key_is_used_without_a_configured_api_url() {
+        assert!(settings::var("LAGO_API_URL").is_none());
+        settings::set("LAGO_API_KEY", "operator-key".to_string());
+
+        let parts = principal_parts(Some("tenant-key"));
+        let config = resolve_api_config(Some(&parts), None).unwrap();
+
+        assert_eq!(config.api_key, "tenant-key");
+        assert_eq!(config.base_url, default_api_url());
+    }
+
+    #[test]
+    fn header_key_is_used_without_a_configured_api_url() {
+        settings::set("LAGO_API_KEY", "operator-key".to_string());
+
+        let (mut parts, ()) = axum::http::Request::new(()).into_parts();
+        parts
+            .headers
+            .insert("X-LAGO-API-KEY", "header-key".parse().unwrap());
+        let config = resolve_api_config(Some(&parts), None).unwrap();
+
+        assert_eq!(config.api_key, "header-key");
+    }
+
+    #[test]
+    fn principal_without_a_tenant_key_uses_the_server_key() {
+        settings::set("LAGO_API_KEY", "operator-key".to_string());
+
+        let parts = principal_parts(None);
+        let config = resolve_api_config(Some(&parts), None).unwrap();
+
+        assert_eq!(config.api_key, "operator-key");
+    }
 }
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use rmcp::{RoleServer, handler::server::tool::Parameters, model::*, service::RequestContext};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use lago_types::{
     filters::activity_log::ActivityLogFilters,
@@ -8,6 +9,7 @@ use lago_types::{
     requests::activity_log::{GetActivityLogRequest, ListActivityLogsRequest},
 };
 
+use crate::outbound;
 use crate::tools::{create_lago_client, error_result, success_result};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -45,6 +47,71 @@ pub struct GetActivityLogArgs {
 #[derive(Clone)]
 pub struct ActivityLogService;
 
+/// Flatten the `activity_object_changes` of an activity log into a field-level diff.
+///
+/// Lago records changes as `{"field": [old_value, new_value]}`. Nested objects are
+/// expanded into dotted field paths so each entry describes a single changed value.
+fn compute_changes(activity_log: &Value) -> Vec<Value> {
+    let mut changes = Vec::new();
+
+    if let Some(object_changes) = activity_log
+        .get("activity_object_changes")
+        .and_then(Value::as_object)
+    {
+        for (field, change) in object_changes {
+            match change.as_array().map(Vec::as_slice) {
+                Some([old_value, new_value]) => {
+                    diff_values(field, old_value, new_value, &mut changes)
+                }
+                _ => changes.push(serde_json::json!({
+                    "field": field,
+                    "old_value": Value::Null,
+                    "new_value": change,
+                })),
+            }
+        }
+    }
+
+    changes
+}
+
+fn diff_values(path: &str, old_value: &Value, new_value: &Value, changes: &mut Vec<Value>) {
+    match (old_value, new_value) {
+        (Value::Object(old_fields), Value::Object(new_fields)) => {
+            let mut keys: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                diff_values(
+                    &format!("{path}.{key}"),
+                    old_fields.get(key).unwrap_or(&Value::Null),
+                    new_fields.get(key).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        _ if old_value != new_value => changes.push(serde_json::json!({
+            "field": path,
+            "old_value": old_value,
+            "new_value": new_value,
+        })),
+        _ => {}
+    }
+}
+
+/// Serialize an activity log and attach its computed `changes` diff.
+fn with_changes<T: Serialize>(activity_log: &T) -> Value {
+    let mut activity_log = serde_json::json!(activity_log);
+    let changes = compute_changes(&activity_log);
+
+    if let Some(fields) = activity_log.as_object_mut() {
+        fields.insert("changes".to_string(), Value::Array(changes));
+    }
+
+    activity_log
+}
+
 impl ActivityLogService {
     pub fn new() -> Self {
         Self
@@ -119,10 +186,12 @@ impl ActivityLogService {
         };
         let request = self.build_list_request(&args);
 
-        match client.list_activity_logs(Some(request)).await {
+        match outbound::limited(client.list_activity_logs(Some(request))).await {
             Ok(response) => {
+                let activity_logs: Vec<Value> =
+                    response.activity_logs.iter().map(with_changes).collect();
                 let result = serde_json::json!({
-                    "activity_logs": response.activity_logs,
+                    "activity_logs": activity_logs,
                     "pagination": response.meta,
                 });
 
@@ -147,10 +216,10 @@ impl ActivityLogService {
         };
         let request = GetActivityLogRequest::new(args.activity_id);
 
-        match client.get_activity_log(request).await {
+        match outbound::limited(client.get_activity_log(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
-                    "activity_log": response.activity_log,
+                    "activity_log": with_changes(&response.activity_log),
                 });
 
                 Ok(success_result(&result))
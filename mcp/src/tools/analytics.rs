@@ -0,0 +1,125 @@
+use rmcp::{RoleServer, handler::server::tool::Parameters, model::*, service::RequestContext};
+use serde::{Deserialize, Serialize};
+
+use crate::tools::{error_result, get_lago_api_config, lago_request, success_result};
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetInvoicedUsageArgs {
+    /// Only include amounts in this currency (e.g., "USD").
+    pub currency: Option<String>,
+    /// Number of months to return, counting back from the current one (default: 12).
+    pub months: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetOverdueBalanceArgs {
+    /// Only include the overdue balance of this customer.
+    pub external_customer_id: Option<String>,
+    /// Only include amounts in this currency (e.g., "USD").
+    pub currency: Option<String>,
+    /// Number of months to return, counting back from the current one (default: 12).
+    pub months: Option<i32>,
+}
+
+#[derive(Clone)]
+pub struct AnalyticsService {
+    http_client: reqwest::Client,
+}
+
+impl AnalyticsService {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn get_invoiced_usage(
+        &self,
+        Parameters(args): Parameters<GetInvoicedUsageArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(currency) = &args.currency {
+            query.push(("currency", currency.clone()));
+        }
+        if let Some(months) = args.months {
+            query.push(("months", months.to_string()));
+        }
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            "/analytics/invoiced_usage",
+            &query,
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "invoiced_usages": response["invoiced_usages"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to get invoiced usage: {e}");
+                tracing::error!("{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
+    pub async fn get_overdue_balance(
+        &self,
+        Parameters(args): Parameters<GetOverdueBalanceArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(external_customer_id) = &args.external_customer_id {
+            query.push(("external_customer_id", external_customer_id.clone()));
+        }
+        if let Some(currency) = &args.currency {
+            query.push(("currency", currency.clone()));
+        }
+        if let Some(months) = args.months {
+            query.push(("months", months.to_string()));
+        }
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            "/analytics/overdue_balance",
+            &query,
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "external_customer_id": args.external_customer_id,
+                    "overdue_balances": response["overdue_balances"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to get overdue balance: {e}");
+                tracing::error!("{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+}
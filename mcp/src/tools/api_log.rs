@@ -8,8 +8,13 @@ use lago_types::{
     requests::api_log::{GetApiLogRequest, ListApiLogsRequest},
 };
 
+use crate::outbound;
+use crate::redaction::redact_value;
 use crate::tools::{create_lago_client, error_result, success_result};
 
+/// API log fields holding the logged request and response payloads.
+const API_LOG_BODY_FIELDS: [&str; 2] = ["request_body", "request_response"];
+
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ListApiLogsArgs {
     /// Filter by HTTP methods: "post", "put", or "delete"
@@ -34,6 +39,9 @@ pub struct ListApiLogsArgs {
 pub struct GetApiLogArgs {
     /// The unique request ID of the API log
     pub request_id: String,
+    /// Include the logged request and response bodies. API keys and customer PII
+    /// (emails, phone numbers, tax ids, addresses) are redacted. Defaults to false.
+    pub include_bodies: Option<bool>,
 }
 
 #[derive(Clone)]
@@ -115,7 +123,7 @@ impl ApiLogService {
         };
         let request = self.build_list_request(&args);
 
-        match client.list_api_logs(Some(request)).await {
+        match outbound::limited(client.list_api_logs(Some(request))).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "api_logs": response.api_logs,
@@ -143,10 +151,24 @@ impl ApiLogService {
         };
         let request = GetApiLogRequest::new(args.request_id);
 
-        match client.get_api_log(request).await {
+        match outbound::limited(client.get_api_log(request)).await {
             Ok(response) => {
+                let mut api_log = serde_json::json!(response.api_log);
+
+                if let Some(fields) = api_log.as_object_mut() {
+                    for name in API_LOG_BODY_FIELDS {
+                        if args.include_bodies.unwrap_or(false) {
+                            if let Some(body) = fields.get_mut(name) {
+                                redact_value(body);
+                            }
+                        } else {
+                            fields.remove(name);
+                        }
+                    }
+                }
+
                 let result = serde_json::json!({
-                    "api_log": response.api_log,
+                    "api_log": api_log,
                 });
 
                 Ok(success_result(&result))
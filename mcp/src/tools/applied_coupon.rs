@@ -8,6 +8,7 @@ use lago_types::{
     requests::applied_coupon::{ApplyCouponInput, ApplyCouponRequest, ListAppliedCouponsRequest},
 };
 
+use crate::outbound;
 use crate::tools::{create_lago_client, error_result, success_result};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -80,7 +81,7 @@ impl AppliedCouponService {
         };
         let request = self.build_list_request(&args);
 
-        match client.list_applied_coupons(Some(request)).await {
+        match outbound::limited(client.list_applied_coupons(Some(request))).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "applied_coupons": response.applied_coupons,
@@ -130,7 +131,7 @@ impl AppliedCouponService {
             Err(error_result) => return Ok(error_result),
         };
 
-        match client.apply_coupon(request).await {
+        match outbound::limited(client.apply_coupon(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "applied_coupon": response.applied_coupon,
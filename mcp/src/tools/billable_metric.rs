@@ -14,6 +14,7 @@ use lago_types::{
     },
 };
 
+use crate::outbound;
 use crate::tools::{create_lago_client, error_result, success_result};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -121,7 +122,7 @@ impl BillableMetricService {
         };
         let request = self.build_list_request(&args);
 
-        match client.list_billable_metrics(Some(request)).await {
+        match outbound::limited(client.list_billable_metrics(Some(request))).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "billable_metrics": response.billable_metrics,
@@ -149,7 +150,7 @@ impl BillableMetricService {
         };
         let request = GetBillableMetricRequest::new(args.code);
 
-        match client.get_billable_metric(request).await {
+        match outbound::limited(client.get_billable_metric(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "billable_metric": response.billable_metric,
@@ -239,7 +240,7 @@ impl BillableMetricService {
             Err(error_result) => return Ok(error_result),
         };
 
-        match client.create_billable_metric(request).await {
+        match outbound::limited(client.create_billable_metric(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "billable_metric": response.billable_metric,
@@ -322,7 +323,7 @@ impl BillableMetricService {
 
         let request = UpdateBillableMetricRequest::new(args.code, input);
 
-        match client.update_billable_metric(request).await {
+        match outbound::limited(client.update_billable_metric(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "billable_metric": response.billable_metric,
@@ -10,6 +10,7 @@ use lago_types::{
     },
 };
 
+use crate::outbound;
 use crate::tools::{create_lago_client, error_result, success_result};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -137,7 +138,7 @@ impl CouponService {
 
         let request = ListCouponsRequest::new().with_pagination(pagination);
 
-        match client.list_coupons(Some(request)).await {
+        match outbound::limited(client.list_coupons(Some(request))).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "coupons": response.coupons,
@@ -165,7 +166,7 @@ impl CouponService {
 
         let request = GetCouponRequest::new(args.code);
 
-        match client.get_coupon(request).await {
+        match outbound::limited(client.get_coupon(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "coupon": response.coupon,
@@ -284,7 +285,7 @@ impl CouponService {
 
         let request = CreateCouponRequest::new(input);
 
-        match client.create_coupon(request).await {
+        match outbound::limited(client.create_coupon(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "coupon": response.coupon,
@@ -380,7 +381,7 @@ impl CouponService {
 
         let request = UpdateCouponRequest::new(args.code, input);
 
-        match client.update_coupon(request).await {
+        match outbound::limited(client.update_coupon(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "coupon": response.coupon,
@@ -407,7 +408,7 @@ impl CouponService {
 
         let request = DeleteCouponRequest::new(args.code);
 
-        match client.delete_coupon(request).await {
+        match outbound::limited(client.delete_coupon(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "coupon": response.coupon,
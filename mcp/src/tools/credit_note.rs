@@ -9,7 +9,12 @@ use lago_types::requests::credit_note::{
     GetCreditNoteRequest, ListCreditNotesRequest, UpdateCreditNoteInput, UpdateCreditNoteRequest,
 };
 
-use crate::tools::{create_lago_client, error_result, success_result};
+use crate::outbound;
+use crate::progress;
+use crate::tools::{
+    FETCH_ALL_MAX_RECORDS, create_lago_client, error_result, get_lago_api_config, lago_request,
+    success_result,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ListCreditNotesArgs {
@@ -41,6 +46,30 @@ pub struct ListCreditNotesArgs {
     pub amount_to: Option<i64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ListCustomerCreditNotesArgs {
+    /// The external customer ID to list credit notes for
+    pub external_customer_id: String,
+    /// Filter by issuing date from (ISO 8601 date, e.g., "2024-01-01")
+    pub issuing_date_from: Option<String>,
+    /// Filter by issuing date to (ISO 8601 date, e.g., "2024-12-31")
+    pub issuing_date_to: Option<String>,
+    /// Filter by currency (ISO 4217 code, e.g., "USD")
+    pub currency: Option<String>,
+    /// Filter by reason (duplicated_charge, product_unsatisfactory, order_change, order_cancellation, fraudulent_charge, other)
+    pub reason: Option<String>,
+    /// Filter by credit status (available, consumed, voided)
+    pub credit_status: Option<String>,
+    /// Filter by refund status (pending, succeeded, failed)
+    pub refund_status: Option<String>,
+    /// Page number for pagination (starting from 1)
+    pub page: Option<i32>,
+    /// Number of items per page (max 100)
+    pub per_page: Option<i32>,
+    /// Walk all pages and return every credit note (capped at 1000) instead of a single page
+    pub fetch_all: Option<bool>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct GetCreditNoteArgs {
     /// The Lago ID of the credit note to retrieve
@@ -79,24 +108,27 @@ pub struct UpdateCreditNoteArgs {
     pub refund_status: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct EstimateCreditNoteArgs {
+    /// The Lago ID of the invoice to credit
+    pub invoice_id: String,
+    /// The fees to credit and the amount to credit for each of them
+    pub items: Vec<CreditNoteItemArg>,
+}
+
 #[derive(Clone)]
-pub struct CreditNoteService;
+pub struct CreditNoteService {
+    http_client: reqwest::Client,
+}
 
 impl CreditNoteService {
     pub fn new() -> Self {
-        Self
+        Self {
+            http_client: reqwest::Client::new(),
+        }
     }
 
-    pub async fn list_credit_notes(
-        &self,
-        Parameters(args): Parameters<ListCreditNotesArgs>,
-        context: RequestContext<RoleServer>,
-    ) -> Result<CallToolResult, rmcp::ErrorData> {
-        let client = match create_lago_client(&context).await {
-            Ok(client) => client,
-            Err(error_result) => return Ok(error_result),
-        };
-
+    fn build_list_request(&self, args: &ListCreditNotesArgs) -> ListCreditNotesRequest {
         let mut request = ListCreditNotesRequest::new();
 
         // Apply pagination
@@ -110,22 +142,22 @@ impl CreditNoteService {
         // Build filter
         let mut filter = CreditNoteFilter::new();
 
-        if let Some(customer_id) = args.external_customer_id {
-            filter = filter.with_external_customer_id(customer_id);
+        if let Some(customer_id) = &args.external_customer_id {
+            filter = filter.with_external_customer_id(customer_id.clone());
         }
-        if let Some(from) = args.issuing_date_from {
-            filter = filter.with_issuing_date_from(from);
+        if let Some(from) = &args.issuing_date_from {
+            filter = filter.with_issuing_date_from(from.clone());
         }
-        if let Some(to) = args.issuing_date_to {
-            filter = filter.with_issuing_date_to(to);
+        if let Some(to) = &args.issuing_date_to {
+            filter = filter.with_issuing_date_to(to.clone());
         }
-        if let Some(term) = args.search_term {
-            request = request.with_search_term(term);
+        if let Some(term) = &args.search_term {
+            request = request.with_search_term(term.clone());
         }
-        if let Some(currency) = args.currency {
-            filter = filter.with_currency(currency);
+        if let Some(currency) = &args.currency {
+            filter = filter.with_currency(currency.clone());
         }
-        if let Some(reason_str) = args.reason
+        if let Some(reason_str) = &args.reason
             && let Ok(reason) = reason_str.parse::<CreditNoteReason>()
         {
             filter = filter.with_reason(reason);
@@ -140,8 +172,8 @@ impl CreditNoteService {
         {
             filter = filter.with_refund_status(status);
         }
-        if let Some(number) = args.invoice_number {
-            filter = filter.with_invoice_number(number);
+        if let Some(number) = &args.invoice_number {
+            filter = filter.with_invoice_number(number.clone());
         }
         if let Some(amount) = args.amount_from {
             filter = filter.with_amount_from(amount);
@@ -150,9 +182,22 @@ impl CreditNoteService {
             filter = filter.with_amount_to(amount);
         }
 
-        request = request.with_filters(filter);
+        request.with_filters(filter)
+    }
+
+    pub async fn list_credit_notes(
+        &self,
+        Parameters(args): Parameters<ListCreditNotesArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let client = match create_lago_client(&context).await {
+            Ok(client) => client,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let request = self.build_list_request(&args);
 
-        match client.list_credit_notes(Some(request)).await {
+        match outbound::limited(client.list_credit_notes(Some(request))).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "credit_notes": response.credit_notes,
@@ -169,6 +214,90 @@ impl CreditNoteService {
         }
     }
 
+    pub async fn list_customer_credit_notes(
+        &self,
+        Parameters(args): Parameters<ListCustomerCreditNotesArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let client = match create_lago_client(&context).await {
+            Ok(client) => client,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let fetch_all = args.fetch_all.unwrap_or(false);
+        let mut list_args = ListCreditNotesArgs {
+            page: args.page,
+            per_page: args.per_page,
+            external_customer_id: Some(args.external_customer_id.clone()),
+            issuing_date_from: args.issuing_date_from,
+            issuing_date_to: args.issuing_date_to,
+            search_term: None,
+            currency: args.currency,
+            reason: args.reason,
+            credit_status: args.credit_status,
+            refund_status: args.refund_status,
+            invoice_number: None,
+            amount_from: None,
+            amount_to: None,
+        };
+
+        if fetch_all {
+            list_args.page = Some(1);
+            list_args.per_page = Some(100);
+        }
+
+        let mut credit_notes = Vec::new();
+
+        loop {
+            let request = self.build_list_request(&list_args);
+
+            match outbound::limited(client.list_credit_notes(Some(request))).await {
+                Ok(response) => {
+                    if !fetch_all {
+                        let result = serde_json::json!({
+                            "credit_notes": response.credit_notes,
+                            "pagination": response.meta,
+                        });
+
+                        return Ok(success_result(&result));
+                    }
+
+                    credit_notes.extend(response.credit_notes);
+                    let fetched = credit_notes.len().min(FETCH_ALL_MAX_RECORDS);
+                    progress::report(fetched, None, format!("Fetched {fetched} credit notes"))
+                        .await;
+
+                    match response.meta.next_page {
+                        Some(next_page) if credit_notes.len() < FETCH_ALL_MAX_RECORDS => {
+                            list_args.page = Some(next_page);
+                        }
+                        next_page => {
+                            let truncated = next_page.is_some();
+                            credit_notes.truncate(FETCH_ALL_MAX_RECORDS);
+
+                            let result = serde_json::json!({
+                                "credit_notes": credit_notes,
+                                "total_count": credit_notes.len(),
+                                "truncated": truncated,
+                            });
+
+                            return Ok(success_result(&result));
+                        }
+                    }
+                }
+                Err(e) => {
+                    let error_message = format!("Failed to list customer credit notes: {e}");
+                    tracing::error!(
+                        external_customer_id = %args.external_customer_id,
+                        error = %e,
+                        "{error_message}"
+                    );
+                    return Ok(error_result(error_message));
+                }
+            }
+        }
+    }
+
     pub async fn get_credit_note(
         &self,
         Parameters(args): Parameters<GetCreditNoteArgs>,
@@ -181,7 +310,7 @@ impl CreditNoteService {
 
         let request = GetCreditNoteRequest::new(args.lago_id.clone());
 
-        match client.get_credit_note(request).await {
+        match outbound::limited(client.get_credit_note(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "credit_note": response.credit_note,
@@ -243,7 +372,7 @@ impl CreditNoteService {
 
         let request = CreateCreditNoteRequest::new(input);
 
-        match client.create_credit_note(request).await {
+        match outbound::limited(client.create_credit_note(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "credit_note": response.credit_note,
@@ -287,7 +416,7 @@ impl CreditNoteService {
         let input = UpdateCreditNoteInput::new().with_refund_status(refund_status);
         let request = UpdateCreditNoteRequest::new(args.lago_id.clone(), input);
 
-        match client.update_credit_note(request).await {
+        match outbound::limited(client.update_credit_note(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "credit_note": response.credit_note,
@@ -306,4 +435,50 @@ impl CreditNoteService {
             }
         }
     }
+
+    pub async fn estimate_credit_note(
+        &self,
+        Parameters(args): Parameters<EstimateCreditNoteArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        if args.items.is_empty() {
+            return Ok(error_result("At least one item must be provided"));
+        }
+
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let body = serde_json::json!({
+            "credit_note": {
+                "invoice_id": args.invoice_id,
+                "items": args.items,
+            }
+        });
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::POST,
+            "/credit_notes/estimate",
+            &[],
+            Some(&body),
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "estimated_credit_note": response["estimated_credit_note"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to estimate credit note: {e}");
+                tracing::error!(invoice_id = %args.invoice_id, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use rmcp::{RoleServer, handler::server::tool::Parameters, model::*, service::RequestContext};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use lago_types::{
     filters::customer::CustomerFilter,
@@ -10,7 +11,11 @@ use lago_types::{
     },
 };
 
-use crate::tools::{create_lago_client, error_result, success_result};
+use crate::errors::{self, ErrorCode};
+use crate::outbound;
+use crate::tools::{
+    create_lago_client, error_result, get_lago_api_config, lago_request, success_result,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ListCustomersArgs {
@@ -50,12 +55,74 @@ pub struct CreateCustomerArgs {
     pub finalize_zero_amount_invoice: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UpdateCustomerArgs {
+    /// The external ID of the customer to update. The customer must already exist.
+    pub external_id: String,
+    pub name: Option<String>,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub email: Option<String>,
+    pub address_line1: Option<String>,
+    pub address_line2: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub state: Option<String>,
+    pub zipcode: Option<String>,
+    pub phone: Option<String>,
+    pub url: Option<String>,
+    pub legal_name: Option<String>,
+    pub legal_number: Option<String>,
+    pub logo_url: Option<String>,
+    pub tax_identification_number: Option<String>,
+    /// IANA timezone of the customer (e.g., "Europe/Paris").
+    pub timezone: Option<String>,
+    /// Currency of the customer (ISO 4217 code). Lago refuses to change it once the
+    /// customer has subscriptions or invoices in another currency.
+    pub currency: Option<String>,
+    pub net_payment_term: Option<i32>,
+    /// "individual" or "company".
+    pub customer_type: Option<String>,
+    /// "inherit", "finalize" or "skip".
+    pub finalize_zero_amount_invoice: Option<String>,
+}
+
+impl UpdateCustomerArgs {
+    /// The fields to change, leaving out the ones that were not provided.
+    fn customer_fields(&self) -> serde_json::Map<String, Value> {
+        let mut fields = match serde_json::to_value(self) {
+            Ok(Value::Object(fields)) => fields,
+            _ => serde_json::Map::new(),
+        };
+        fields.retain(|_, value| !value.is_null());
+        fields
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DeleteCustomerArgs {
+    /// The external ID of the customer to delete.
+    pub external_id: String,
+    /// Must be true to confirm the deletion, which cannot be undone.
+    pub confirm: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetCustomerCheckoutUrlArgs {
+    /// The external ID of the customer.
+    pub external_customer_id: String,
+}
+
 #[derive(Clone)]
-pub struct CustomerService;
+pub struct CustomerService {
+    http_client: reqwest::Client,
+}
 
 impl CustomerService {
     pub fn new() -> Self {
-        Self
+        Self {
+            http_client: reqwest::Client::new(),
+        }
     }
 
     fn build_request(&self, params: &ListCustomersArgs) -> ListCustomersRequest {
@@ -90,7 +157,7 @@ impl CustomerService {
         };
         let request = self.build_request(&args);
 
-        match client.list_customers(Some(request)).await {
+        match outbound::limited(client.list_customers(Some(request))).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "customers": response.customers,
@@ -118,7 +185,7 @@ impl CustomerService {
         };
         let request = GetCustomerRequest::new(args.external_customer_id);
 
-        match client.get_customer(request).await {
+        match outbound::limited(client.get_customer(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "customer": response.customer,
@@ -228,7 +295,7 @@ impl CustomerService {
             Err(error_result) => return Ok(error_result),
         };
 
-        match client.create_customer(request).await {
+        match outbound::limited(client.create_customer(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "customer": response.customer,
@@ -243,4 +310,152 @@ impl CustomerService {
             }
         }
     }
+
+    /// Update only the provided fields of an existing customer.
+    ///
+    /// Lago's customer endpoint upserts, so the customer is looked up first to avoid creating
+    /// one from a mistyped external ID.
+    pub async fn update_customer(
+        &self,
+        Parameters(args): Parameters<UpdateCustomerArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let fields = args.customer_fields();
+        if fields.len() <= 1 {
+            return Ok(error_result("No customer fields to update were provided"));
+        }
+
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let path = format!("/customers/{}", urlencoding::encode(&args.external_id));
+        if let Err(e) = lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            &path,
+            &[],
+            None,
+        )
+        .await
+        {
+            let error_message = format!("Failed to find customer to update: {e}");
+            tracing::error!(external_id = %args.external_id, "{error_message}");
+            return Ok(error_result(error_message));
+        }
+
+        let body = serde_json::json!({ "customer": fields });
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::POST,
+            "/customers",
+            &[],
+            Some(&body),
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "customer": response["customer"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to update customer: {e}");
+                tracing::error!(external_id = %args.external_id, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
+    pub async fn delete_customer(
+        &self,
+        Parameters(args): Parameters<DeleteCustomerArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        if !args.confirm {
+            errors::record(ErrorCode::NotConfirmed);
+            return Ok(error_result(
+                "Deleting a customer cannot be undone: its subscriptions are terminated and its wallets closed. Ask the user to confirm, then call delete_customer again with confirm set to true",
+            ));
+        }
+
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+        let path = format!("/customers/{}", urlencoding::encode(&args.external_id));
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::DELETE,
+            &path,
+            &[],
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                tracing::info!(external_id = %args.external_id, "Deleted customer");
+                let result = serde_json::json!({
+                    "customer": response["customer"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to delete customer: {e}");
+                tracing::error!(external_id = %args.external_id, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
+    pub async fn get_customer_checkout_url(
+        &self,
+        Parameters(args): Parameters<GetCustomerCheckoutUrlArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+        let path = format!(
+            "/customers/{}/checkout_url",
+            urlencoding::encode(&args.external_customer_id)
+        );
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            &path,
+            &[],
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "customer": response["customer"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to get customer checkout URL: {e}");
+                tracing::error!(
+                    external_customer_id = %args.external_customer_id,
+                    "{error_message}"
+                );
+                Ok(error_result(error_message))
+            }
+        }
+    }
 }
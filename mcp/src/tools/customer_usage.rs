@@ -2,8 +2,16 @@ use anyhow::Result;
 use rmcp::{RoleServer, handler::server::tool::Parameters, model::*, service::RequestContext};
 use serde::{Deserialize, Serialize};
 
-use lago_types::requests::customer_usage::GetCustomerCurrentUsageRequest;
+use lago_types::{
+    filters::subscription::SubscriptionFilters,
+    models::{PaginationParams, SubscriptionStatus},
+    requests::{
+        customer_usage::GetCustomerCurrentUsageRequest,
+        subscription::ListCustomerSubscriptionsRequest,
+    },
+};
 
+use crate::outbound;
 use crate::tools::{create_lago_client, error_result, success_result};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -16,6 +24,14 @@ pub struct GetCustomerCurrentUsageArgs {
     pub apply_taxes: Option<bool>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetCustomerUsageSummaryArgs {
+    /// The external unique identifier of the customer (provided by your own application).
+    pub external_customer_id: String,
+    /// Optional flag to determine if taxes should be applied. Defaults to true if not provided.
+    pub apply_taxes: Option<bool>,
+}
+
 #[derive(Clone)]
 pub struct CustomerUsageService;
 
@@ -43,7 +59,7 @@ impl CustomerUsageService {
             request = request.with_apply_taxes(apply_taxes);
         }
 
-        match client.get_customer_current_usage(request).await {
+        match outbound::limited(client.get_customer_current_usage(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "customer_usage": response.customer_usage,
@@ -58,4 +74,84 @@ impl CustomerUsageService {
             }
         }
     }
+
+    /// Return the current usage of every active subscription of a customer in one call.
+    pub async fn get_customer_usage_summary(
+        &self,
+        Parameters(args): Parameters<GetCustomerUsageSummaryArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let client = match create_lago_client(&context).await {
+            Ok(client) => client,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut subscriptions = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let filters =
+                SubscriptionFilters::new().with_statuses(vec![SubscriptionStatus::Active]);
+            let pagination = PaginationParams::new().with_page(page).with_per_page(100);
+            let request = ListCustomerSubscriptionsRequest::new(args.external_customer_id.clone())
+                .with_filters(filters)
+                .with_pagination(pagination);
+
+            match outbound::limited(client.list_customer_subscriptions(request)).await {
+                Ok(response) => {
+                    subscriptions.extend(response.subscriptions);
+                    match response.meta.next_page {
+                        Some(next_page) => page = next_page,
+                        None => break,
+                    }
+                }
+                Err(e) => {
+                    let error_message = format!("Failed to list customer subscriptions: {e}");
+                    tracing::error!("{error_message}");
+                    return Ok(error_result(error_message));
+                }
+            }
+        }
+
+        let mut usages = Vec::with_capacity(subscriptions.len());
+
+        for subscription in &subscriptions {
+            let mut request = GetCustomerCurrentUsageRequest::new(
+                args.external_customer_id.clone(),
+                subscription.external_id.clone(),
+            );
+
+            if let Some(apply_taxes) = args.apply_taxes {
+                request = request.with_apply_taxes(apply_taxes);
+            }
+
+            match outbound::limited(client.get_customer_current_usage(request)).await {
+                Ok(response) => usages.push(serde_json::json!({
+                    "external_subscription_id": subscription.external_id,
+                    "plan_code": subscription.plan_code,
+                    "customer_usage": response.customer_usage,
+                })),
+                Err(e) => {
+                    tracing::error!(
+                        external_subscription_id = %subscription.external_id,
+                        error = %e,
+                        "Failed to get customer current usage"
+                    );
+                    usages.push(serde_json::json!({
+                        "external_subscription_id": subscription.external_id,
+                        "plan_code": subscription.plan_code,
+                        "error": format!("Failed to get customer current usage: {e}"),
+                    }));
+                }
+            }
+        }
+
+        let result = serde_json::json!({
+            "external_customer_id": args.external_customer_id,
+            "active_subscriptions_count": subscriptions.len(),
+            "usages": usages,
+        });
+
+        Ok(success_result(&result))
+    }
 }
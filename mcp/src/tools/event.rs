@@ -4,7 +4,13 @@ use serde_json::Value;
 
 use lago_types::requests::event::{CreateEventInput, CreateEventRequest};
 
-use crate::tools::{create_lago_client, error_result, get_lago_api_config, success_result};
+use crate::event_dedup;
+use crate::event_queue;
+use crate::outbound;
+use crate::tools::{
+    FETCH_ALL_MAX_RECORDS, create_lago_client, error_result, get_lago_api_config, lago_get_all,
+    lago_request, success_result,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ListEventsArgs {
@@ -22,6 +28,8 @@ pub struct ListEventsArgs {
     pub page: Option<i32>,
     /// Number of items per page (default: 20).
     pub per_page: Option<i32>,
+    /// Walk all pages and return every matching event (capped at 1000) instead of a single page.
+    pub fetch_all: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -48,6 +56,16 @@ pub struct CreateEventArgs {
     pub precise_total_amount_cents: Option<i64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct EstimateFeesArgs {
+    /// External subscription ID the event would be sent for
+    pub external_subscription_id: String,
+    /// Billable metric code of a pay-in-advance charge
+    pub code: String,
+    /// Custom properties of the event (e.g., {"gb": 10, "region": "us-east"})
+    pub properties: Option<Value>,
+}
+
 #[derive(Clone)]
 pub struct EventService {
     http_client: reqwest::Client,
@@ -70,48 +88,23 @@ impl EventService {
             Err(error_result) => return Ok(error_result),
         };
 
-        let encoded_id = urlencoding::encode(&args.transaction_id);
-        let url = format!("{}/events/{}", config.base_url, encoded_id);
+        let path = format!("/events/{}", urlencoding::encode(&args.transaction_id));
 
-        match self
-            .http_client
-            .get(&url)
-            .bearer_auth(&config.api_key)
-            .send()
-            .await
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            &path,
+            &[],
+            None,
+        )
+        .await
         {
-            Ok(response) if response.status().is_success() => {
-                match response.json::<Value>().await {
-                    Ok(json) => Ok(success_result(&json)),
-                    Err(e) => {
-                        let error_message = format!("Failed to parse event response: {e}");
-                        tracing::error!(
-                            transaction_id = %args.transaction_id,
-                            error = %e,
-                            "{error_message}"
-                        );
-                        Ok(error_result(error_message))
-                    }
-                }
-            }
-            Ok(response) => {
-                let status = response.status();
-                let body = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Unknown error".to_string());
-                let error_message = format!("Failed to get event (HTTP {status}): {body}");
-                tracing::error!(
-                    transaction_id = %args.transaction_id,
-                    "{error_message}"
-                );
-                Ok(error_result(error_message))
-            }
+            Ok(event) => Ok(success_result(&event)),
             Err(e) => {
                 let error_message = format!("Failed to get event: {e}");
                 tracing::error!(
                     transaction_id = %args.transaction_id,
-                    error = %e,
                     "{error_message}"
                 );
                 Ok(error_result(error_message))
@@ -131,6 +124,10 @@ impl EventService {
             ));
         }
 
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
         let client = match create_lago_client(&context).await {
             Ok(client) => client,
             Err(error_result) => return Ok(error_result),
@@ -168,7 +165,12 @@ impl EventService {
 
         let request = CreateEventRequest::new(event_input);
 
-        match client.create_event(request).await {
+        if let Err(error_message) = event_dedup::reserve(&config, &args.transaction_id) {
+            tracing::warn!(transaction_id = %args.transaction_id, "{error_message}");
+            return Ok(error_result(error_message));
+        }
+
+        match outbound::limited(client.create_event(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "event": response.event,
@@ -177,6 +179,7 @@ impl EventService {
                 Ok(success_result(&result))
             }
             Err(e) => {
+                event_dedup::release(&config, &args.transaction_id);
                 let error_message = format!("Failed to create event: {e}");
                 tracing::error!(
                     transaction_id = %args.transaction_id,
@@ -199,12 +202,13 @@ impl EventService {
             Err(error_result) => return Ok(error_result),
         };
 
+        let fetch_all = args.fetch_all.unwrap_or(false);
         let mut params: Vec<(&str, String)> = Vec::new();
 
-        if let Some(page) = args.page {
+        if let Some(page) = args.page.filter(|_| !fetch_all) {
             params.push(("page", page.to_string()));
         }
-        if let Some(per_page) = args.per_page {
+        if let Some(per_page) = args.per_page.filter(|_| !fetch_all) {
             params.push(("per_page", per_page.to_string()));
         }
         if let Some(ref external_subscription_id) = args.external_subscription_id {
@@ -226,41 +230,183 @@ impl EventService {
             params.push(("timestamp_to", timestamp_to.clone()));
         }
 
-        let url = format!("{}/events", config.base_url);
-
-        match self
-            .http_client
-            .get(&url)
-            .bearer_auth(&config.api_key)
-            .query(&params)
-            .send()
+        if fetch_all {
+            return match lago_get_all(
+                &self.http_client,
+                &config,
+                "/events",
+                &params,
+                "events",
+                FETCH_ALL_MAX_RECORDS,
+            )
             .await
-        {
-            Ok(response) if response.status().is_success() => {
-                match response.json::<Value>().await {
-                    Ok(json) => Ok(success_result(&json)),
-                    Err(e) => {
-                        let error_message = format!("Failed to parse events response: {e}");
-                        tracing::error!("{error_message}");
-                        Ok(error_result(error_message))
-                    }
+            {
+                Ok((events, truncated)) => {
+                    let result = serde_json::json!({
+                        "events": events,
+                        "total_count": events.len(),
+                        "truncated": truncated,
+                    });
+
+                    Ok(success_result(&result))
                 }
-            }
-            Ok(response) => {
-                let status = response.status();
-                let body = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Unknown error".to_string());
-                let error_message = format!("Failed to list events (HTTP {status}): {body}");
+                Err(e) => {
+                    let error_message = format!("Failed to list events: {e}");
+                    tracing::error!("{error_message}");
+                    Ok(error_result(error_message))
+                }
+            };
+        }
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            "/events",
+            &params,
+            None,
+        )
+        .await
+        {
+            Ok(events) => Ok(success_result(&events)),
+            Err(e) => {
+                let error_message = format!("Failed to list events: {e}");
                 tracing::error!("{error_message}");
                 Ok(error_result(error_message))
             }
+        }
+    }
+
+    pub async fn estimate_fees(
+        &self,
+        Parameters(args): Parameters<EstimateFeesArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut event = serde_json::json!({
+            "code": args.code,
+            "external_subscription_id": args.external_subscription_id,
+        });
+        if let Some(properties) = args.properties {
+            event["properties"] = properties;
+        }
+        let body = serde_json::json!({ "event": event });
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::POST,
+            "/events/estimate_fees",
+            &[],
+            Some(&body),
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "fees": response["fees"],
+                });
+
+                Ok(success_result(&result))
+            }
             Err(e) => {
-                let error_message = format!("Failed to list events: {e}");
+                let error_message = format!("Failed to estimate fees: {e}");
+                tracing::error!(
+                    code = %args.code,
+                    external_subscription_id = %args.external_subscription_id,
+                    "{error_message}"
+                );
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
+    pub async fn queue_event(
+        &self,
+        Parameters(args): Parameters<CreateEventArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        if args.external_customer_id.is_none() && args.external_subscription_id.is_none() {
+            return Ok(error_result(
+                "Either external_customer_id or external_subscription_id must be provided",
+            ));
+        }
+
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut event = serde_json::json!({
+            "transaction_id": args.transaction_id,
+            "code": args.code,
+            "timestamp": args.timestamp.unwrap_or_else(|| chrono::Utc::now().timestamp()),
+        });
+        if let Some(external_customer_id) = args.external_customer_id {
+            event["external_customer_id"] = Value::String(external_customer_id);
+        }
+        if let Some(external_subscription_id) = args.external_subscription_id {
+            event["external_subscription_id"] = Value::String(external_subscription_id);
+        }
+        if let Some(properties) = args.properties {
+            event["properties"] = properties;
+        }
+        if let Some(precise_amount) = args.precise_total_amount_cents {
+            event["precise_total_amount_cents"] = precise_amount.into();
+        }
+
+        if let Err(error_message) = event_dedup::reserve(&config, &args.transaction_id) {
+            tracing::warn!(transaction_id = %args.transaction_id, "{error_message}");
+            return Ok(error_result(error_message));
+        }
+
+        match event_queue::enqueue(&config, event).await {
+            Ok(queued) => Ok(success_result(&serde_json::json!({
+                "transaction_id": args.transaction_id,
+                "queued": queued,
+            }))),
+            Err(error_message) => {
+                event_dedup::release(&config, &args.transaction_id);
+                tracing::error!(transaction_id = %args.transaction_id, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
+    pub async fn flush_events(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        match event_queue::flush(&config).await {
+            Ok(sent) => Ok(success_result(&serde_json::json!({
+                "sent": sent,
+                "status": event_queue::status(&config),
+            }))),
+            Err(error_message) => {
                 tracing::error!("{error_message}");
                 Ok(error_result(error_message))
             }
         }
     }
+
+    pub async fn queue_status(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        Ok(success_result(&event_queue::status(&config)))
+    }
 }
@@ -1,10 +1,13 @@
 use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use rmcp::{RoleServer, handler::server::tool::Parameters, model::*, service::RequestContext};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use lago_types::{
-    filters::invoice::InvoiceFilters,
+    filters::{credit_note::CreditNoteFilter, invoice::InvoiceFilters},
     models::{InvoicePaymentStatus, InvoiceStatus, InvoiceType, PaginationParams},
+    requests::credit_note::ListCreditNotesRequest,
     requests::invoice::{
         BillingTime, CreateInvoiceFeeInput, CreateInvoiceInput, CreateInvoiceRequest,
         DownloadInvoiceRequest, GetInvoiceRequest, InvoicePreviewCoupon, InvoicePreviewCustomer,
@@ -15,7 +18,15 @@ use lago_types::{
     },
 };
 
-use crate::tools::{create_lago_client, error_result, success_result};
+use crate::errors::{self, ErrorCode};
+use crate::outbound;
+use crate::tools::{
+    FETCH_ALL_MAX_RECORDS, create_lago_client, error_result, get_lago_api_config, lago_get_all,
+    lago_request, success_result,
+};
+
+/// Related data that can be requested alongside an invoice with `include`.
+const INVOICE_INCLUDES: [&str; 4] = ["fees", "subscriptions", "applied_taxes", "credit_notes"];
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ListInvoicesArgs {
@@ -35,10 +46,16 @@ pub struct ListInvoicesArgs {
     pub payment_status: Option<String>,
     /// Filter by invoice type: subscription, add_on, credit, one_off, advance_charges, progressive_billing.
     pub invoice_type: Option<String>,
+    /// Only return self-billed invoices (true) or exclude them (false).
+    pub self_billed: Option<bool>,
+    /// Only return invoices issued by this billing entity (billing entity code).
+    pub billing_entity_code: Option<String>,
     /// Page number for pagination.
     pub page: Option<i32>,
     /// Number of results per page (default: 20, max: 100).
     pub per_page: Option<i32>,
+    /// Walk all pages and return every matching invoice (capped at 1000) instead of a single page.
+    pub fetch_all: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -46,6 +63,9 @@ pub struct GetInvoiceArgs {
     /// The Lago ID (UUID) of the invoice. Note: This is NOT the invoice number.
     /// To find an invoice by its number (e.g., "RAF-8142-202601-312"), use find_invoice_by_number instead.
     pub invoice_id: String,
+    /// Optional related data to return with the invoice, so a single call can explain it.
+    /// Possible values: fees, subscriptions, applied_taxes, credit_notes.
+    pub include: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -156,6 +176,9 @@ pub struct RefreshInvoiceArgs {
 pub struct DownloadInvoiceArgs {
     /// The Lago ID (UUID) of the invoice to download.
     pub lago_id: String,
+    /// Fetch the generated PDF server-side and return it as an embedded base64 resource,
+    /// for clients that cannot follow the download URL themselves. Defaults to false.
+    pub include_file: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -177,12 +200,69 @@ pub struct VoidInvoiceArgs {
     pub lago_id: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct LoseInvoiceDisputeArgs {
+    /// The Lago ID (UUID) of the finalized invoice whose payment dispute was lost.
+    pub lago_id: String,
+}
+
 #[derive(Clone)]
-pub struct InvoiceService;
+pub struct InvoiceService {
+    http_client: reqwest::Client,
+}
 
 impl InvoiceService {
     pub fn new() -> Self {
-        Self
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Download a generated invoice file.
+    async fn fetch_file(&self, file_url: &str) -> Result<Vec<u8>, String> {
+        let response = self.http_client.get(file_url).send().await.map_err(|e| {
+            errors::record(ErrorCode::Network);
+            format!("Failed to fetch invoice PDF: {e}")
+        })?;
+
+        if !response.status().is_success() {
+            errors::record(ErrorCode::from_status(response.status().as_u16()));
+            return Err(format!(
+                "Failed to fetch invoice PDF (HTTP {})",
+                response.status()
+            ));
+        }
+
+        response
+            .bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| {
+                errors::record(ErrorCode::Network);
+                format!("Failed to read invoice PDF: {e}")
+            })
+    }
+
+    /// Fetch the raw invoice payload, which carries the fee breakdown, subscriptions
+    /// and applied taxes that are not exposed on the typed invoice model.
+    async fn fetch_invoice_json(
+        &self,
+        invoice_id: &str,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<Value, CallToolResult> {
+        let config = get_lago_api_config(context).await?;
+        let path = format!("/invoices/{}", urlencoding::encode(invoice_id));
+
+        lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            &path,
+            &[],
+            None,
+        )
+        .await
+        .map_err(|e| error_result(format!("Failed to get invoice details: {e}")))
     }
 
     #[allow(clippy::collapsible_if)]
@@ -240,6 +320,114 @@ impl InvoiceService {
 
         request
     }
+
+    /// Query parameters of `GET /invoices`, for the filters `lago-client` doesn't cover.
+    fn build_query(&self, args: &ListInvoicesArgs) -> Vec<(&'static str, String)> {
+        let mut query = Vec::new();
+
+        if let Some(search_term) = &args.search_term {
+            query.push(("search_term", search_term.clone()));
+        }
+        if let Some(customer_external_id) = &args.customer_external_id {
+            query.push(("external_customer_id", customer_external_id.clone()));
+        }
+        if let Some(from_date) = &args.issuing_date_from {
+            query.push(("issuing_date_from", from_date.clone()));
+        }
+        if let Some(to_date) = &args.issuing_date_to {
+            query.push(("issuing_date_to", to_date.clone()));
+        }
+        if let Some(status) = &args.status {
+            query.push(("status", status.clone()));
+        }
+        if let Some(payment_status) = &args.payment_status {
+            query.push(("payment_status", payment_status.clone()));
+        }
+        if let Some(invoice_type) = &args.invoice_type {
+            query.push(("invoice_type", invoice_type.clone()));
+        }
+        if let Some(self_billed) = args.self_billed {
+            query.push(("self_billed", self_billed.to_string()));
+        }
+        if let Some(billing_entity_code) = &args.billing_entity_code {
+            query.push(("billing_entity_codes[]", billing_entity_code.clone()));
+        }
+        if let Some(page) = args.page {
+            query.push(("page", page.to_string()));
+        }
+        if let Some(per_page) = args.per_page {
+            query.push(("per_page", per_page.to_string()));
+        }
+
+        query
+    }
+
+    async fn list_invoices_raw(
+        &self,
+        args: &ListInvoicesArgs,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+        let mut query = self.build_query(args);
+
+        if args.fetch_all.unwrap_or(false) {
+            query.retain(|(name, _)| !matches!(*name, "page" | "per_page"));
+
+            return match lago_get_all(
+                &self.http_client,
+                &config,
+                "/invoices",
+                &query,
+                "invoices",
+                FETCH_ALL_MAX_RECORDS,
+            )
+            .await
+            {
+                Ok((invoices, truncated)) => {
+                    let result = serde_json::json!({
+                        "invoices": invoices,
+                        "total_count": invoices.len(),
+                        "truncated": truncated,
+                    });
+
+                    Ok(success_result(&result))
+                }
+                Err(e) => {
+                    let error_message = format!("Failed to list invoices: {e}");
+                    tracing::error!("{error_message}");
+                    Ok(error_result(error_message))
+                }
+            };
+        }
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            "/invoices",
+            &query,
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "invoices": response["invoices"],
+                    "pagination": response["meta"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to list invoices: {e}");
+                tracing::error!("{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
 }
 
 impl InvoiceService {
@@ -248,13 +436,20 @@ impl InvoiceService {
         Parameters(args): Parameters<ListInvoicesArgs>,
         context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, rmcp::ErrorData> {
+        if args.self_billed.is_some()
+            || args.billing_entity_code.is_some()
+            || args.fetch_all.unwrap_or(false)
+        {
+            return self.list_invoices_raw(&args, &context).await;
+        }
+
         let client = match create_lago_client(&context).await {
             Ok(client) => client,
             Err(error_result) => return Ok(error_result),
         };
         let request = self.build_request(&args);
 
-        match client.list_invoices(Some(request)).await {
+        match outbound::limited(client.list_invoices(Some(request))).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "invoices": response.invoices,
@@ -280,14 +475,58 @@ impl InvoiceService {
             Ok(client) => client,
             Err(error_result) => return Ok(error_result),
         };
-        let request = GetInvoiceRequest::new(args.invoice_id);
+        let includes = args.include.unwrap_or_default();
+        if let Some(unknown) = includes
+            .iter()
+            .find(|include| !INVOICE_INCLUDES.contains(&include.as_str()))
+        {
+            return Ok(error_result(format!(
+                "Invalid include '{unknown}'. Must be one of: {}",
+                INVOICE_INCLUDES.join(", ")
+            )));
+        }
 
-        match client.get_invoice(request).await {
+        let request = GetInvoiceRequest::new(args.invoice_id.clone());
+
+        match outbound::limited(client.get_invoice(request)).await {
             Ok(response) => {
-                let result = serde_json::json!({
+                let mut result = serde_json::json!({
                     "invoice": response.invoice,
                 });
 
+                let wants = |name: &str| includes.iter().any(|include| include == name);
+
+                if wants("fees") || wants("subscriptions") || wants("applied_taxes") {
+                    let details = match self.fetch_invoice_json(&args.invoice_id, &context).await {
+                        Ok(details) => details,
+                        Err(error_result) => return Ok(error_result),
+                    };
+
+                    for name in ["fees", "subscriptions", "applied_taxes"] {
+                        if wants(name) {
+                            result[name] = details["invoice"][name].clone();
+                        }
+                    }
+                }
+
+                if wants("credit_notes") {
+                    let filter = CreditNoteFilter::new()
+                        .with_invoice_number(response.invoice.number.clone());
+                    let request = ListCreditNotesRequest::new().with_filters(filter);
+
+                    match outbound::limited(client.list_credit_notes(Some(request))).await {
+                        Ok(credit_notes) => {
+                            result["credit_notes"] = serde_json::json!(credit_notes.credit_notes);
+                        }
+                        Err(e) => {
+                            let error_message =
+                                format!("Failed to list credit notes for invoice: {e}");
+                            tracing::error!("{error_message}");
+                            return Ok(error_result(error_message));
+                        }
+                    }
+                }
+
                 Ok(success_result(&result))
             }
             Err(e) => {
@@ -313,7 +552,7 @@ impl InvoiceService {
         // Use search_term to find the invoice by number
         let request = ListInvoicesRequest::new().with_search_term(args.invoice_number.clone());
 
-        match client.list_invoices(Some(request)).await {
+        match outbound::limited(client.list_invoices(Some(request))).await {
             Ok(response) => {
                 // Find exact match by invoice number
                 let exact_match = response
@@ -475,7 +714,7 @@ impl InvoiceService {
 
         let request = self.build_preview_request(&args);
 
-        match client.preview_invoice(request).await {
+        match outbound::limited(client.preview_invoice(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "invoice": response.invoice,
@@ -522,7 +761,7 @@ impl InvoiceService {
         let input = CreateInvoiceInput::new(args.external_customer_id, args.currency, fees);
         let request = CreateInvoiceRequest::new(input);
 
-        match client.create_invoice(request).await {
+        match outbound::limited(client.create_invoice(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "invoice": response.invoice,
@@ -570,7 +809,7 @@ impl InvoiceService {
 
         let request = UpdateInvoiceRequest::new(args.lago_id, input);
 
-        match client.update_invoice(request).await {
+        match outbound::limited(client.update_invoice(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "invoice": response.invoice,
@@ -609,7 +848,7 @@ impl InvoiceService {
             request = request.with_pagination(pagination);
         }
 
-        match client.list_customer_invoices(request).await {
+        match outbound::limited(client.list_customer_invoices(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "invoices": response.invoices,
@@ -638,7 +877,7 @@ impl InvoiceService {
 
         let request = RefreshInvoiceRequest::new(args.lago_id);
 
-        match client.refresh_invoice(request).await {
+        match outbound::limited(client.refresh_invoice(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "invoice": response.invoice,
@@ -666,13 +905,41 @@ impl InvoiceService {
 
         let request = DownloadInvoiceRequest::new(args.lago_id);
 
-        match client.download_invoice(request).await {
+        match outbound::limited(client.download_invoice(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "invoice": response.invoice,
                 });
 
-                Ok(success_result(&result))
+                if !args.include_file.unwrap_or(false) {
+                    return Ok(success_result(&result));
+                }
+
+                let Some(file_url) = response.invoice.file_url.clone() else {
+                    errors::record(ErrorCode::Error);
+                    return Ok(error_result(
+                        "The invoice PDF is not generated yet. Try download_invoice again in a few seconds.",
+                    ));
+                };
+
+                match self.fetch_file(&file_url).await {
+                    Ok(bytes) => {
+                        let mut result = success_result(&result);
+                        result.content.push(Content::resource(
+                            ResourceContents::BlobResourceContents {
+                                uri: file_url,
+                                mime_type: Some("application/pdf".to_string()),
+                                blob: BASE64.encode(bytes),
+                            },
+                        ));
+
+                        Ok(result)
+                    }
+                    Err(error_message) => {
+                        tracing::error!("{error_message}");
+                        Ok(error_result(error_message))
+                    }
+                }
             }
             Err(e) => {
                 let error_message = format!("Failed to download invoice: {e}");
@@ -694,7 +961,7 @@ impl InvoiceService {
 
         let request = RetryInvoiceRequest::new(args.lago_id);
 
-        match client.retry_invoice(request).await {
+        match outbound::limited(client.retry_invoice(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "invoice": response.invoice,
@@ -722,7 +989,7 @@ impl InvoiceService {
 
         let request = RetryInvoicePaymentRequest::new(args.lago_id);
 
-        match client.retry_invoice_payment(request).await {
+        match outbound::limited(client.retry_invoice_payment(request)).await {
             Ok(_) => {
                 let result = serde_json::json!({
                     "success": true,
@@ -751,7 +1018,7 @@ impl InvoiceService {
 
         let request = VoidInvoiceRequest::new(args.lago_id);
 
-        match client.void_invoice(request).await {
+        match outbound::limited(client.void_invoice(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "invoice": response.invoice,
@@ -766,4 +1033,43 @@ impl InvoiceService {
             }
         }
     }
+
+    pub async fn lose_invoice_dispute(
+        &self,
+        Parameters(args): Parameters<LoseInvoiceDisputeArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+        let path = format!(
+            "/invoices/{}/lose_dispute",
+            urlencoding::encode(&args.lago_id)
+        );
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::POST,
+            &path,
+            &[],
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "invoice": response["invoice"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to mark invoice dispute as lost: {e}");
+                tracing::error!(lago_id = %args.lago_id, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
 }
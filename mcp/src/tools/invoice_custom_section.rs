@@ -0,0 +1,291 @@
+use rmcp::{RoleServer, handler::server::tool::Parameters, model::*, service::RequestContext};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::tools::{error_result, get_lago_api_config, lago_request, success_result};
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ListInvoiceCustomSectionsArgs {
+    /// Page number for pagination (default: 1).
+    pub page: Option<i32>,
+    /// Number of items per page (default: 20).
+    pub per_page: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetInvoiceCustomSectionArgs {
+    /// The unique code of the invoice custom section.
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CreateInvoiceCustomSectionArgs {
+    /// Internal name of the section.
+    pub name: String,
+    /// Unique code of the section, used to attach it to customers.
+    pub code: String,
+    /// Title printed on the invoice above the details.
+    pub display_name: Option<String>,
+    /// Text printed on the invoice (e.g., legal mentions or bank details).
+    pub details: Option<String>,
+    /// Internal description of the section.
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UpdateInvoiceCustomSectionArgs {
+    /// The code of the section to update.
+    pub code: String,
+    /// New internal name of the section.
+    pub name: Option<String>,
+    /// New title printed on the invoice.
+    pub display_name: Option<String>,
+    /// New text printed on the invoice.
+    pub details: Option<String>,
+    /// New internal description of the section.
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DeleteInvoiceCustomSectionArgs {
+    /// The code of the section to delete.
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SetCustomerInvoiceCustomSectionsArgs {
+    /// The external ID of the customer.
+    pub external_customer_id: String,
+    /// Codes of the sections printed on the customer's invoices, replacing the current ones.
+    /// An empty list makes the customer use the organization's default sections.
+    pub section_codes: Vec<String>,
+    /// Print no custom section at all on the customer's invoices (default: false).
+    pub skip_invoice_custom_sections: Option<bool>,
+}
+
+#[derive(Clone)]
+pub struct InvoiceCustomSectionService {
+    http_client: reqwest::Client,
+}
+
+impl InvoiceCustomSectionService {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn list_invoice_custom_sections(
+        &self,
+        Parameters(args): Parameters<ListInvoiceCustomSectionsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(page) = args.page {
+            query.push(("page", page.to_string()));
+        }
+        if let Some(per_page) = args.per_page {
+            query.push(("per_page", per_page.to_string()));
+        }
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            "/invoice_custom_sections",
+            &query,
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "invoice_custom_sections": response["invoice_custom_sections"],
+                    "pagination": response["meta"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to list invoice custom sections: {e}");
+                tracing::error!("{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
+    pub async fn get_invoice_custom_section(
+        &self,
+        Parameters(args): Parameters<GetInvoiceCustomSectionArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let path = format!(
+            "/invoice_custom_sections/{}",
+            urlencoding::encode(&args.code)
+        );
+        self.send_section_request(reqwest::Method::GET, &path, None, "get", &context)
+            .await
+    }
+
+    pub async fn create_invoice_custom_section(
+        &self,
+        Parameters(args): Parameters<CreateInvoiceCustomSectionArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let mut section = serde_json::json!({
+            "name": args.name,
+            "code": args.code,
+        });
+        if let Some(display_name) = args.display_name {
+            section["display_name"] = Value::String(display_name);
+        }
+        if let Some(details) = args.details {
+            section["details"] = Value::String(details);
+        }
+        if let Some(description) = args.description {
+            section["description"] = Value::String(description);
+        }
+        let body = serde_json::json!({ "invoice_custom_section": section });
+
+        self.send_section_request(
+            reqwest::Method::POST,
+            "/invoice_custom_sections",
+            Some(&body),
+            "create",
+            &context,
+        )
+        .await
+    }
+
+    pub async fn update_invoice_custom_section(
+        &self,
+        Parameters(args): Parameters<UpdateInvoiceCustomSectionArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let mut section = serde_json::Map::new();
+        if let Some(name) = args.name {
+            section.insert("name".to_string(), Value::String(name));
+        }
+        if let Some(display_name) = args.display_name {
+            section.insert("display_name".to_string(), Value::String(display_name));
+        }
+        if let Some(details) = args.details {
+            section.insert("details".to_string(), Value::String(details));
+        }
+        if let Some(description) = args.description {
+            section.insert("description".to_string(), Value::String(description));
+        }
+
+        if section.is_empty() {
+            return Ok(error_result(
+                "No invoice custom section fields to update were provided",
+            ));
+        }
+        let body = serde_json::json!({ "invoice_custom_section": section });
+        let path = format!(
+            "/invoice_custom_sections/{}",
+            urlencoding::encode(&args.code)
+        );
+
+        self.send_section_request(reqwest::Method::PUT, &path, Some(&body), "update", &context)
+            .await
+    }
+
+    pub async fn delete_invoice_custom_section(
+        &self,
+        Parameters(args): Parameters<DeleteInvoiceCustomSectionArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let path = format!(
+            "/invoice_custom_sections/{}",
+            urlencoding::encode(&args.code)
+        );
+        self.send_section_request(reqwest::Method::DELETE, &path, None, "delete", &context)
+            .await
+    }
+
+    pub async fn set_customer_invoice_custom_sections(
+        &self,
+        Parameters(args): Parameters<SetCustomerInvoiceCustomSectionsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let body = serde_json::json!({
+            "customer": {
+                "external_id": args.external_customer_id,
+                "invoice_custom_section_codes": args.section_codes,
+                "skip_invoice_custom_sections": args.skip_invoice_custom_sections.unwrap_or(false),
+            }
+        });
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::POST,
+            "/customers",
+            &[],
+            Some(&body),
+        )
+        .await
+        {
+            Ok(response) => {
+                let customer = &response["customer"];
+                let result = serde_json::json!({
+                    "external_customer_id": customer["external_id"],
+                    "skip_invoice_custom_sections": customer["skip_invoice_custom_sections"],
+                    "applicable_invoice_custom_sections": customer["applicable_invoice_custom_sections"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message =
+                    format!("Failed to set the customer's invoice custom sections: {e}");
+                tracing::error!(
+                    external_customer_id = %args.external_customer_id,
+                    "{error_message}"
+                );
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
+    /// Send a request returning a single invoice custom section.
+    async fn send_section_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&Value>,
+        action: &str,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        match lago_request(&self.http_client, &config, method, path, &[], body).await {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "invoice_custom_section": response["invoice_custom_section"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to {action} invoice custom section: {e}");
+                tracing::error!(path, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+}
@@ -11,7 +11,10 @@ use lago_types::{
     },
 };
 
-use crate::tools::{create_lago_client, error_result, success_result};
+use crate::outbound;
+use crate::tools::{
+    create_lago_client, error_result, get_lago_api_config, lago_request, success_result,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ListPaymentsArgs {
@@ -53,14 +56,83 @@ pub struct CreatePaymentArgs {
     pub reference: String,
     /// The date the payment was made (YYYY-MM-DD format).
     pub paid_at: Option<String>,
+    /// Skip the check refusing payments larger than the invoice's remaining amount due
+    /// (default: false).
+    pub allow_overpayment: Option<bool>,
 }
 
 #[derive(Clone)]
-pub struct PaymentService;
+pub struct PaymentService {
+    http_client: reqwest::Client,
+}
 
 impl PaymentService {
     pub fn new() -> Self {
-        Self
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Refuse a payment exceeding the amount still due on its invoice, or whose invoice's
+    /// amount due can't be read.
+    ///
+    /// Returns the error result to send back when the payment must not be created.
+    async fn check_remaining_balance(
+        &self,
+        args: &CreatePaymentArgs,
+        context: &RequestContext<RoleServer>,
+    ) -> Option<CallToolResult> {
+        let config = match get_lago_api_config(context).await {
+            Ok(config) => config,
+            Err(error_result) => return Some(error_result),
+        };
+        let path = format!("/invoices/{}", urlencoding::encode(&args.invoice_id));
+
+        let invoice = match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            &path,
+            &[],
+            None,
+        )
+        .await
+        {
+            Ok(response) => response["invoice"].clone(),
+            Err(e) => {
+                let error_message = format!(
+                    "Failed to get invoice to check its remaining balance: {e}. Set allow_overpayment to true to skip this check."
+                );
+                tracing::error!("{error_message}");
+                return Some(error_result(error_message));
+            }
+        };
+
+        let amount_due_cents = invoice["total_due_amount_cents"].as_i64().or_else(|| {
+            let total = invoice["total_amount_cents"].as_i64()?;
+            let paid = invoice["total_paid_amount_cents"].as_i64().unwrap_or(0);
+            Some(total - paid)
+        });
+        let Some(amount_due_cents) = amount_due_cents else {
+            let error_message = format!(
+                "Refusing to create a payment of {} cents: the invoice's remaining amount due could not be read. Set allow_overpayment to true to record it anyway.",
+                args.amount_cents
+            );
+            tracing::warn!("{error_message}");
+            return Some(error_result(error_message));
+        };
+
+        if args.amount_cents > amount_due_cents {
+            let currency = invoice["currency"].as_str().unwrap_or_default();
+            let error_message = format!(
+                "Refusing to create a payment of {} cents: the invoice's remaining amount due is {amount_due_cents} cents {currency}. Set allow_overpayment to true to record it anyway.",
+                args.amount_cents
+            );
+            tracing::warn!("{error_message}");
+            return Some(error_result(error_message));
+        }
+
+        None
     }
 
     pub async fn list_payments(
@@ -100,7 +172,7 @@ impl PaymentService {
             }
         }
 
-        match client.list_payments(Some(request)).await {
+        match outbound::limited(client.list_payments(Some(request))).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "payments": response.payments,
@@ -139,7 +211,7 @@ impl PaymentService {
 
         let request = GetPaymentRequest::new(lago_id);
 
-        match client.get_payment(request).await {
+        match outbound::limited(client.get_payment(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "payment": response.payment,
@@ -189,7 +261,7 @@ impl PaymentService {
             }
         }
 
-        match client.list_customer_payments(request).await {
+        match outbound::limited(client.list_customer_payments(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "payments": response.payments,
@@ -211,6 +283,12 @@ impl PaymentService {
         Parameters(args): Parameters<CreatePaymentArgs>,
         context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, rmcp::ErrorData> {
+        if !args.allow_overpayment.unwrap_or(false)
+            && let Some(error_result) = self.check_remaining_balance(&args, &context).await
+        {
+            return Ok(error_result);
+        }
+
         let client = match create_lago_client(&context).await {
             Ok(client) => client,
             Err(error_result) => return Ok(error_result),
@@ -224,7 +302,7 @@ impl PaymentService {
 
         let request = CreatePaymentRequest::new(input);
 
-        match client.create_payment(request).await {
+        match outbound::limited(client.create_payment(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "payment": response.payment,
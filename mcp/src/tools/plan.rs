@@ -11,6 +11,7 @@ use lago_types::{
     },
 };
 
+use crate::outbound;
 use crate::tools::{create_lago_client, error_result, success_result};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -275,7 +276,7 @@ impl PlanService {
 
         let request = ListPlansRequest::new().with_pagination(pagination);
 
-        match client.list_plans(Some(request)).await {
+        match outbound::limited(client.list_plans(Some(request))).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "plans": response.plans,
@@ -304,7 +305,7 @@ impl PlanService {
 
         let request = GetPlanRequest::new(args.code);
 
-        match client.get_plan(request).await {
+        match outbound::limited(client.get_plan(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "plan": response.plan,
@@ -384,7 +385,7 @@ impl PlanService {
 
         let request = CreatePlanRequest::new(input);
 
-        match client.create_plan(request).await {
+        match outbound::limited(client.create_plan(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "plan": response.plan,
@@ -472,7 +473,7 @@ impl PlanService {
 
         let request = UpdatePlanRequest::new(args.code, input);
 
-        match client.update_plan(request).await {
+        match outbound::limited(client.update_plan(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "plan": response.plan,
@@ -500,7 +501,7 @@ impl PlanService {
 
         let request = DeletePlanRequest::new(args.code);
 
-        match client.delete_plan(request).await {
+        match outbound::limited(client.delete_plan(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "plan": response.plan,
@@ -0,0 +1,1215 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use rmcp::{RoleServer, handler::server::tool::Parameters, model::*, service::RequestContext};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::tools::{
+    FETCH_ALL_MAX_RECORDS, error_result, get_lago_api_config, lago_get_all, lago_request,
+    success_result,
+};
+
+/// Maximum number of invoice previews issued by a single renewals report.
+const MAX_RENEWAL_PREVIEWS: usize = 50;
+
+/// (started_at, terminated_at) of the subscriptions of each monthly cohort.
+type Cohorts = BTreeMap<String, Vec<(DateTime<Utc>, Option<DateTime<Utc>>)>>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ReportUpcomingRenewalsArgs {
+    /// Number of days ahead to look for renewals (default: 7, max: 90).
+    pub days_ahead: Option<i64>,
+    /// Only include subscriptions on this plan code.
+    pub plan_code: Option<String>,
+    /// Estimate each renewal amount with an invoice preview (default: true).
+    /// At most 50 renewals are estimated per report.
+    pub include_estimates: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ReportLowWalletsArgs {
+    /// Flag wallets whose ongoing balance (in cents) is below this amount.
+    pub threshold_cents: Option<i64>,
+    /// Only scan the wallets of this customer. When omitted, all customers are scanned.
+    pub external_customer_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ReportSubscriptionCohortsArgs {
+    /// Only include subscriptions on this plan code.
+    pub plan_code: Option<String>,
+    /// First cohort month to include (format: YYYY-MM).
+    pub from_month: Option<String>,
+    /// Last cohort month to include (format: YYYY-MM).
+    pub to_month: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ReportTopMetricConsumersArgs {
+    /// The code of the billable metric to rank consumers by.
+    pub billable_metric_code: String,
+    /// Billing period to analyze: "current" (default) or "previous".
+    pub period: Option<String>,
+    /// Number of top subscriptions to return (default: 10, max: 100).
+    pub limit: Option<usize>,
+    /// Only include subscriptions on this plan code.
+    pub plan_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ReportRevenueByPlanArgs {
+    /// Include invoices issued from this date (format: YYYY-MM-DD).
+    pub issuing_date_from: String,
+    /// Include invoices issued until this date (format: YYYY-MM-DD).
+    pub issuing_date_to: String,
+    /// Only include invoices in this currency (ISO 4217 code, e.g., "USD").
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct EstimateCustomerLtvArgs {
+    /// The external unique identifier of the customer.
+    pub external_customer_id: String,
+    /// Number of future months of current MRR to add to the historical total (default: 12).
+    pub projection_months: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetDsoArgs {
+    /// Length of the rolling window in days (default: 90, max: 365).
+    pub window_days: Option<i64>,
+    /// Only include invoices of this customer.
+    pub external_customer_id: Option<String>,
+    /// Only include invoices in this currency (ISO 4217 code, e.g., "USD").
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ReportExpiringCouponsArgs {
+    /// Number of days ahead to look for expiring coupons (default: 30, max: 365).
+    pub days_ahead: Option<i64>,
+}
+
+/// Number of billing intervals per month, used to normalize plan amounts to MRR.
+fn intervals_per_month(interval: &str) -> Option<f64> {
+    match interval {
+        "weekly" => Some(52.0 / 12.0),
+        "monthly" => Some(1.0),
+        "quarterly" => Some(1.0 / 3.0),
+        "semiannual" => Some(1.0 / 6.0),
+        "yearly" => Some(1.0 / 12.0),
+        _ => None,
+    }
+}
+
+/// Maximum number of invoices whose fees are fetched by a single revenue report.
+const MAX_INVOICE_LOOKUPS: usize = 200;
+
+/// Maximum number of subscription usage lookups performed by a single consumers report.
+const MAX_USAGE_LOOKUPS: usize = 200;
+
+/// Months after the cohort start at which retention is measured.
+const COHORT_MILESTONES: [u32; 4] = [1, 3, 6, 12];
+
+/// Start and end of the calendar month containing `now`, used as the projection window.
+fn month_bounds(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = now
+        .date_naive()
+        .with_day(1)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc())
+        .unwrap_or(now);
+    let end = start.checked_add_months(Months::new(1)).unwrap_or(now);
+
+    (start, end)
+}
+
+/// Read a numeric field that Lago may serialize either as a number or as a string.
+fn as_f64(value: &Value) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|text| text.parse().ok()))
+}
+
+/// Parse a Lago timestamp, accepting both ISO 8601 datetimes and plain YYYY-MM-DD dates.
+fn parse_datetime(value: &Value) -> Option<DateTime<Utc>> {
+    let text = value.as_str()?;
+
+    DateTime::parse_from_rfc3339(text)
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|datetime| datetime.and_utc())
+        })
+}
+
+/// Compute DSO over the invoices issued in `[from, to)`.
+///
+/// Paid invoices count the days between issuing and payment; unpaid invoices count the
+/// days they have been outstanding as of `now`. Each invoice weighs the same so that
+/// amounts in different currencies are never mixed.
+fn dso_window(
+    invoices: &[Value],
+    paid_at: &BTreeMap<String, DateTime<Utc>>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Value {
+    let mut paid_days = Vec::new();
+    let mut outstanding_days = Vec::new();
+
+    for invoice in invoices {
+        let Some(issued_at) = parse_datetime(&invoice["issuing_date"]) else {
+            continue;
+        };
+        if issued_at < from || issued_at >= to {
+            continue;
+        }
+
+        if invoice["payment_status"].as_str() == Some("succeeded") {
+            let settled_at = invoice["lago_id"]
+                .as_str()
+                .and_then(|lago_id| paid_at.get(lago_id).copied())
+                .or_else(|| parse_datetime(&invoice["updated_at"]))
+                .unwrap_or(issued_at);
+            paid_days.push((settled_at - issued_at).num_days().max(0) as f64);
+        } else {
+            outstanding_days.push((now - issued_at).num_days().max(0) as f64);
+        }
+    }
+
+    let average = |days: &[f64]| {
+        (!days.is_empty())
+            .then(|| (days.iter().sum::<f64>() / days.len() as f64 * 10.0).round() / 10.0)
+    };
+    let all_days: Vec<f64> = paid_days.iter().chain(&outstanding_days).copied().collect();
+
+    serde_json::json!({
+        "from": from.date_naive().to_string(),
+        "to": to.date_naive().to_string(),
+        "dso_days": average(&all_days),
+        "average_days_to_pay": average(&paid_days),
+        "average_days_outstanding": average(&outstanding_days),
+        "sample_size": all_days.len(),
+        "paid_invoices_count": paid_days.len(),
+        "unpaid_invoices_count": outstanding_days.len(),
+    })
+}
+
+#[derive(Clone)]
+pub struct ReportService {
+    http_client: reqwest::Client,
+}
+
+impl ReportService {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn report_upcoming_renewals(
+        &self,
+        Parameters(args): Parameters<ReportUpcomingRenewalsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let days_ahead = args.days_ahead.unwrap_or(7).clamp(1, 90);
+        let now = Utc::now();
+        let horizon = now + Duration::days(days_ahead);
+
+        let mut query = vec![("status[]", "active".to_string())];
+        if let Some(plan_code) = &args.plan_code {
+            query.push(("plan_code", plan_code.clone()));
+        }
+
+        let (subscriptions, truncated) = match lago_get_all(
+            &self.http_client,
+            &config,
+            "/subscriptions",
+            &query,
+            "subscriptions",
+            FETCH_ALL_MAX_RECORDS,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                let error_message = format!("Failed to list subscriptions: {e}");
+                tracing::error!("{error_message}");
+                return Ok(error_result(error_message));
+            }
+        };
+
+        let mut renewals: Vec<(DateTime<Utc>, &Value)> = subscriptions
+            .iter()
+            .filter_map(|subscription| {
+                let renews_at = parse_datetime(&subscription["current_billing_period_ending_at"])?;
+                let ends_before_renewal = parse_datetime(&subscription["ending_at"])
+                    .is_some_and(|ending_at| ending_at <= renews_at);
+
+                (renews_at >= now && renews_at <= horizon && !ends_before_renewal)
+                    .then_some((renews_at, subscription))
+            })
+            .collect();
+        renewals.sort_by_key(|(renews_at, _)| *renews_at);
+
+        let include_estimates = args.include_estimates.unwrap_or(true);
+        let mut rows = Vec::with_capacity(renewals.len());
+
+        for (index, (renews_at, subscription)) in renewals.iter().enumerate() {
+            let mut row = serde_json::json!({
+                "external_subscription_id": subscription["external_id"],
+                "external_customer_id": subscription["external_customer_id"],
+                "plan_code": subscription["plan_code"],
+                "renews_at": renews_at.to_rfc3339(),
+            });
+
+            if include_estimates && index < MAX_RENEWAL_PREVIEWS {
+                let body = serde_json::json!({
+                    "customer": { "external_id": subscription["external_customer_id"] },
+                    "subscriptions": { "external_ids": [subscription["external_id"]] },
+                });
+
+                match lago_request(
+                    &self.http_client,
+                    &config,
+                    reqwest::Method::POST,
+                    "/invoices/preview",
+                    &[],
+                    Some(&body),
+                )
+                .await
+                {
+                    Ok(preview) => {
+                        row["estimated_amount_cents"] =
+                            preview["invoice"]["total_amount_cents"].clone();
+                        row["currency"] = preview["invoice"]["currency"].clone();
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            external_subscription_id = %subscription["external_id"],
+                            error = %e,
+                            "Failed to preview renewal invoice"
+                        );
+                        row["estimate_error"] = Value::String(e.to_string());
+                    }
+                }
+            }
+
+            rows.push(row);
+        }
+
+        let result = serde_json::json!({
+            "days_ahead": days_ahead,
+            "renewals_count": rows.len(),
+            "renewals": rows,
+            "estimates_capped": include_estimates && renewals.len() > MAX_RENEWAL_PREVIEWS,
+            "truncated": truncated,
+        });
+
+        Ok(success_result(&result))
+    }
+
+    pub async fn report_low_wallets(
+        &self,
+        Parameters(args): Parameters<ReportLowWalletsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let (customer_ids, truncated) = match &args.external_customer_id {
+            Some(external_customer_id) => (vec![external_customer_id.clone()], false),
+            None => match lago_get_all(
+                &self.http_client,
+                &config,
+                "/customers",
+                &[],
+                "customers",
+                FETCH_ALL_MAX_RECORDS,
+            )
+            .await
+            {
+                Ok((customers, truncated)) => (
+                    customers
+                        .iter()
+                        .filter_map(|customer| customer["external_id"].as_str())
+                        .map(str::to_string)
+                        .collect(),
+                    truncated,
+                ),
+                Err(e) => {
+                    let error_message = format!("Failed to list customers: {e}");
+                    tracing::error!("{error_message}");
+                    return Ok(error_result(error_message));
+                }
+            },
+        };
+
+        let now = Utc::now();
+        let (period_start, period_end) = month_bounds(now);
+        let elapsed_days = ((now - period_start).num_minutes() as f64 / 1440.0).max(1.0 / 24.0);
+
+        let mut scanned_wallets = 0;
+        let mut flagged = Vec::new();
+
+        for external_customer_id in &customer_ids {
+            let query = [("external_customer_id", external_customer_id.clone())];
+            let wallets = match lago_get_all(
+                &self.http_client,
+                &config,
+                "/wallets",
+                &query,
+                "wallets",
+                FETCH_ALL_MAX_RECORDS,
+            )
+            .await
+            {
+                Ok((wallets, _)) => wallets,
+                Err(e) => {
+                    tracing::warn!(
+                        external_customer_id = %external_customer_id,
+                        error = %e,
+                        "Failed to list customer wallets"
+                    );
+                    continue;
+                }
+            };
+
+            for wallet in wallets.iter().filter(|wallet| wallet["status"] == "active") {
+                scanned_wallets += 1;
+
+                let ongoing_balance_cents = as_f64(&wallet["ongoing_balance_cents"])
+                    .or_else(|| as_f64(&wallet["balance_cents"]))
+                    .unwrap_or(0.0);
+                let usage_cents = as_f64(&wallet["ongoing_usage_balance_cents"]).unwrap_or(0.0);
+                let daily_usage_cents = usage_cents / elapsed_days;
+
+                let projected_depletion_at = (daily_usage_cents > 0.0).then(|| {
+                    let days_left = ongoing_balance_cents.max(0.0) / daily_usage_cents;
+                    now + Duration::minutes((days_left * 1440.0) as i64)
+                });
+
+                let mut reasons = Vec::new();
+                if args
+                    .threshold_cents
+                    .is_some_and(|threshold| ongoing_balance_cents < threshold as f64)
+                {
+                    reasons.push("below_threshold");
+                }
+                if projected_depletion_at.is_some_and(|depletion_at| depletion_at < period_end) {
+                    reasons.push("projected_depletion_before_period_end");
+                }
+
+                if reasons.is_empty() {
+                    continue;
+                }
+
+                flagged.push(serde_json::json!({
+                    "external_customer_id": external_customer_id,
+                    "lago_wallet_id": wallet["lago_id"],
+                    "name": wallet["name"],
+                    "currency": wallet["currency"],
+                    "balance_cents": wallet["balance_cents"],
+                    "ongoing_balance_cents": wallet["ongoing_balance_cents"],
+                    "ongoing_usage_balance_cents": wallet["ongoing_usage_balance_cents"],
+                    "projected_depletion_at": projected_depletion_at.map(|at| at.to_rfc3339()),
+                    "reasons": reasons,
+                }));
+            }
+        }
+
+        let result = serde_json::json!({
+            "period_start": period_start.to_rfc3339(),
+            "period_end": period_end.to_rfc3339(),
+            "scanned_customers": customer_ids.len(),
+            "scanned_wallets": scanned_wallets,
+            "low_wallets_count": flagged.len(),
+            "low_wallets": flagged,
+            "truncated": truncated,
+        });
+
+        Ok(success_result(&result))
+    }
+
+    pub async fn report_subscription_cohorts(
+        &self,
+        Parameters(args): Parameters<ReportSubscriptionCohortsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut query = vec![
+            ("status[]", "active".to_string()),
+            ("status[]", "terminated".to_string()),
+        ];
+        if let Some(plan_code) = &args.plan_code {
+            query.push(("plan_code", plan_code.clone()));
+        }
+
+        let (subscriptions, truncated) = match lago_get_all(
+            &self.http_client,
+            &config,
+            "/subscriptions",
+            &query,
+            "subscriptions",
+            FETCH_ALL_MAX_RECORDS,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                let error_message = format!("Failed to list subscriptions: {e}");
+                tracing::error!("{error_message}");
+                return Ok(error_result(error_message));
+            }
+        };
+
+        let now = Utc::now();
+        let mut cohorts: Cohorts = BTreeMap::new();
+
+        for subscription in &subscriptions {
+            let Some(started_at) = parse_datetime(&subscription["started_at"]) else {
+                continue;
+            };
+            let cohort = started_at.format("%Y-%m").to_string();
+
+            if args.from_month.as_ref().is_some_and(|from| cohort < *from)
+                || args.to_month.as_ref().is_some_and(|to| cohort > *to)
+            {
+                continue;
+            }
+
+            let terminated_at = parse_datetime(&subscription["terminated_at"]);
+            cohorts
+                .entry(cohort)
+                .or_default()
+                .push((started_at, terminated_at));
+        }
+
+        let rows: Vec<Value> = cohorts
+            .iter()
+            .map(|(cohort, members)| {
+                let mut retained = serde_json::Map::new();
+                let mut retention_rate = serde_json::Map::new();
+
+                for months in COHORT_MILESTONES {
+                    let observable: Vec<_> = members
+                        .iter()
+                        .filter_map(|(started_at, terminated_at)| {
+                            let milestone = started_at.checked_add_months(Months::new(months))?;
+                            (milestone <= now).then_some((milestone, terminated_at))
+                        })
+                        .collect();
+
+                    let key = format!("month_{months}");
+                    if observable.is_empty() {
+                        retained.insert(key.clone(), Value::Null);
+                        retention_rate.insert(key, Value::Null);
+                        continue;
+                    }
+
+                    let still_active = observable
+                        .iter()
+                        .filter(|(milestone, terminated_at)| {
+                            terminated_at.is_none_or(|terminated_at| terminated_at > *milestone)
+                        })
+                        .count();
+                    let rate = still_active as f64 / observable.len() as f64;
+
+                    retained.insert(key.clone(), serde_json::json!(still_active));
+                    retention_rate.insert(key, serde_json::json!((rate * 1000.0).round() / 10.0));
+                }
+
+                serde_json::json!({
+                    "cohort": cohort,
+                    "size": members.len(),
+                    "retained": retained,
+                    "retention_rate_percent": retention_rate,
+                })
+            })
+            .collect();
+
+        let result = serde_json::json!({
+            "milestones_months": COHORT_MILESTONES,
+            "cohorts": rows,
+            "subscriptions_analyzed": subscriptions.len(),
+            "truncated": truncated,
+        });
+
+        Ok(success_result(&result))
+    }
+
+    pub async fn report_top_metric_consumers(
+        &self,
+        Parameters(args): Parameters<ReportTopMetricConsumersArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let previous_period = match args.period.as_deref().unwrap_or("current") {
+            "current" => false,
+            "previous" => true,
+            other => {
+                return Ok(error_result(format!(
+                    "Invalid period '{other}'. Must be one of: current, previous"
+                )));
+            }
+        };
+
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut query = vec![("status[]", "active".to_string())];
+        if let Some(plan_code) = &args.plan_code {
+            query.push(("plan_code", plan_code.clone()));
+        }
+
+        let (subscriptions, truncated) = match lago_get_all(
+            &self.http_client,
+            &config,
+            "/subscriptions",
+            &query,
+            "subscriptions",
+            MAX_USAGE_LOOKUPS,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                let error_message = format!("Failed to list subscriptions: {e}");
+                tracing::error!("{error_message}");
+                return Ok(error_result(error_message));
+            }
+        };
+
+        let mut consumers = Vec::new();
+
+        for subscription in &subscriptions {
+            let (Some(external_customer_id), Some(external_subscription_id)) = (
+                subscription["external_customer_id"].as_str(),
+                subscription["external_id"].as_str(),
+            ) else {
+                continue;
+            };
+
+            let customer_path = urlencoding::encode(external_customer_id);
+            let mut usage_query = vec![(
+                "external_subscription_id",
+                external_subscription_id.to_string(),
+            )];
+            let path = if previous_period {
+                usage_query.push(("billable_metric_code", args.billable_metric_code.clone()));
+                usage_query.push(("periods_count", "1".to_string()));
+                format!("/customers/{customer_path}/past_usage")
+            } else {
+                format!("/customers/{customer_path}/current_usage")
+            };
+
+            let usage = match lago_request(
+                &self.http_client,
+                &config,
+                reqwest::Method::GET,
+                &path,
+                &usage_query,
+                None,
+            )
+            .await
+            {
+                Ok(response) if previous_period => response["usage_periods"][0].clone(),
+                Ok(response) => response["customer_usage"].clone(),
+                Err(e) => {
+                    tracing::warn!(
+                        external_subscription_id = %external_subscription_id,
+                        error = %e,
+                        "Failed to get subscription usage"
+                    );
+                    continue;
+                }
+            };
+
+            let (units, amount_cents) = usage["charges_usage"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|charge| {
+                    charge["billable_metric"]["code"] == args.billable_metric_code.as_str()
+                })
+                .fold((0.0, 0.0), |(units, amount_cents), charge| {
+                    (
+                        units + as_f64(&charge["units"]).unwrap_or(0.0),
+                        amount_cents + as_f64(&charge["amount_cents"]).unwrap_or(0.0),
+                    )
+                });
+
+            if units > 0.0 {
+                consumers.push(serde_json::json!({
+                    "external_customer_id": external_customer_id,
+                    "external_subscription_id": external_subscription_id,
+                    "plan_code": subscription["plan_code"],
+                    "units": units,
+                    "amount_cents": amount_cents,
+                    "currency": usage["currency"],
+                    "from_datetime": usage["from_datetime"],
+                    "to_datetime": usage["to_datetime"],
+                }));
+            }
+        }
+
+        consumers.sort_by(|a, b| {
+            let units = |consumer: &Value| consumer["units"].as_f64().unwrap_or(0.0);
+            units(b).total_cmp(&units(a))
+        });
+        let consumers_count = consumers.len();
+        consumers.truncate(args.limit.unwrap_or(10).clamp(1, 100));
+
+        let result = serde_json::json!({
+            "billable_metric_code": args.billable_metric_code,
+            "period": if previous_period { "previous" } else { "current" },
+            "subscriptions_analyzed": subscriptions.len(),
+            "consumers_count": consumers_count,
+            "top_consumers": consumers,
+            "truncated": truncated,
+        });
+
+        Ok(success_result(&result))
+    }
+
+    pub async fn report_revenue_by_plan(
+        &self,
+        Parameters(args): Parameters<ReportRevenueByPlanArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut query = vec![
+            ("issuing_date_from", args.issuing_date_from.clone()),
+            ("issuing_date_to", args.issuing_date_to.clone()),
+            ("status", "finalized".to_string()),
+            ("invoice_type", "subscription".to_string()),
+        ];
+        if let Some(currency) = &args.currency {
+            query.push(("currency", currency.clone()));
+        }
+
+        let (invoices, truncated) = match lago_get_all(
+            &self.http_client,
+            &config,
+            "/invoices",
+            &query,
+            "invoices",
+            MAX_INVOICE_LOOKUPS,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                let error_message = format!("Failed to list invoices: {e}");
+                tracing::error!("{error_message}");
+                return Ok(error_result(error_message));
+            }
+        };
+
+        // (plan_code, currency) -> (fees amount in cents, invoice ids)
+        let mut revenue: BTreeMap<(String, String), (f64, Vec<String>)> = BTreeMap::new();
+
+        for invoice in &invoices {
+            let Some(lago_id) = invoice["lago_id"].as_str() else {
+                continue;
+            };
+
+            let details = match lago_request(
+                &self.http_client,
+                &config,
+                reqwest::Method::GET,
+                &format!("/invoices/{}", urlencoding::encode(lago_id)),
+                &[],
+                None,
+            )
+            .await
+            {
+                Ok(details) => details,
+                Err(e) => {
+                    tracing::warn!(lago_id = %lago_id, error = %e, "Failed to get invoice");
+                    continue;
+                }
+            };
+
+            let plan_codes: BTreeMap<&str, &str> = details["invoice"]["subscriptions"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|subscription| {
+                    Some((
+                        subscription["lago_id"].as_str()?,
+                        subscription["plan_code"].as_str()?,
+                    ))
+                })
+                .collect();
+
+            for fee in details["invoice"]["fees"].as_array().into_iter().flatten() {
+                let plan_code = fee["lago_subscription_id"]
+                    .as_str()
+                    .and_then(|subscription_id| plan_codes.get(subscription_id))
+                    .copied()
+                    .unwrap_or("unknown");
+                let currency = fee["amount_currency"]
+                    .as_str()
+                    .or(invoice["currency"].as_str())
+                    .unwrap_or("unknown");
+
+                let entry = revenue
+                    .entry((plan_code.to_string(), currency.to_string()))
+                    .or_default();
+                entry.0 += as_f64(&fee["amount_cents"]).unwrap_or(0.0);
+                if !entry.1.iter().any(|id| id == lago_id) {
+                    entry.1.push(lago_id.to_string());
+                }
+            }
+        }
+
+        let mut plans: Vec<Value> = revenue
+            .into_iter()
+            .map(|((plan_code, currency), (amount_cents, invoice_ids))| {
+                serde_json::json!({
+                    "plan_code": plan_code,
+                    "currency": currency,
+                    "amount_cents": amount_cents.round() as i64,
+                    "invoices_count": invoice_ids.len(),
+                })
+            })
+            .collect();
+        plans.sort_by_key(|plan| std::cmp::Reverse(plan["amount_cents"].as_i64().unwrap_or(0)));
+
+        let result = serde_json::json!({
+            "issuing_date_from": args.issuing_date_from,
+            "issuing_date_to": args.issuing_date_to,
+            "invoices_analyzed": invoices.len(),
+            "revenue_by_plan": plans,
+            "truncated": truncated,
+        });
+
+        Ok(success_result(&result))
+    }
+
+    pub async fn estimate_customer_ltv(
+        &self,
+        Parameters(args): Parameters<EstimateCustomerLtvArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let customer_path = format!(
+            "/customers/{}",
+            urlencoding::encode(&args.external_customer_id)
+        );
+        let customer = match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            &customer_path,
+            &[],
+            None,
+        )
+        .await
+        {
+            Ok(response) => response["customer"].clone(),
+            Err(e) => {
+                let error_message = format!("Failed to get customer: {e}");
+                tracing::error!("{error_message}");
+                return Ok(error_result(error_message));
+            }
+        };
+
+        let customer_query = [("external_customer_id", args.external_customer_id.clone())];
+        let invoice_query = [
+            ("external_customer_id", args.external_customer_id.clone()),
+            ("status", "finalized".to_string()),
+        ];
+
+        let (invoices, invoices_truncated) = match lago_get_all(
+            &self.http_client,
+            &config,
+            "/invoices",
+            &invoice_query,
+            "invoices",
+            FETCH_ALL_MAX_RECORDS,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                let error_message = format!("Failed to list customer invoices: {e}");
+                tracing::error!("{error_message}");
+                return Ok(error_result(error_message));
+            }
+        };
+
+        let mut invoiced_by_currency: BTreeMap<String, f64> = BTreeMap::new();
+        for invoice in &invoices {
+            let currency = invoice["currency"]
+                .as_str()
+                .unwrap_or("unknown")
+                .to_string();
+            *invoiced_by_currency.entry(currency).or_default() +=
+                as_f64(&invoice["total_amount_cents"]).unwrap_or(0.0);
+        }
+
+        let mut subscription_query = customer_query.to_vec();
+        subscription_query.push(("status[]", "active".to_string()));
+        let subscriptions = match lago_get_all(
+            &self.http_client,
+            &config,
+            "/subscriptions",
+            &subscription_query,
+            "subscriptions",
+            FETCH_ALL_MAX_RECORDS,
+        )
+        .await
+        {
+            Ok((subscriptions, _)) => subscriptions,
+            Err(e) => {
+                let error_message = format!("Failed to list customer subscriptions: {e}");
+                tracing::error!("{error_message}");
+                return Ok(error_result(error_message));
+            }
+        };
+
+        let mut mrr_by_currency: BTreeMap<String, f64> = BTreeMap::new();
+        let mut subscription_rows = Vec::with_capacity(subscriptions.len());
+
+        for subscription in &subscriptions {
+            let Some(plan_code) = subscription["plan_code"].as_str() else {
+                continue;
+            };
+
+            let plan = match lago_request(
+                &self.http_client,
+                &config,
+                reqwest::Method::GET,
+                &format!("/plans/{}", urlencoding::encode(plan_code)),
+                &[],
+                None,
+            )
+            .await
+            {
+                Ok(response) => response["plan"].clone(),
+                Err(e) => {
+                    tracing::warn!(plan_code = %plan_code, error = %e, "Failed to get plan");
+                    continue;
+                }
+            };
+
+            let amount_cents = as_f64(&plan["amount_cents"]).unwrap_or(0.0);
+            let monthly_cents = plan["interval"]
+                .as_str()
+                .and_then(intervals_per_month)
+                .map(|per_month| amount_cents * per_month)
+                .unwrap_or(0.0);
+            let currency = plan["amount_currency"]
+                .as_str()
+                .unwrap_or("unknown")
+                .to_string();
+
+            *mrr_by_currency.entry(currency.clone()).or_default() += monthly_cents;
+            subscription_rows.push(serde_json::json!({
+                "external_subscription_id": subscription["external_id"],
+                "plan_code": plan_code,
+                "interval": plan["interval"],
+                "plan_amount_cents": amount_cents.round() as i64,
+                "monthly_recurring_cents": monthly_cents.round() as i64,
+                "currency": currency,
+            }));
+        }
+
+        let now = Utc::now();
+        let tenure_months = parse_datetime(&customer["created_at"])
+            .map(|created_at| (now - created_at).num_days() as f64 / 30.44)
+            .unwrap_or(0.0);
+        let projection_months = args.projection_months.unwrap_or(12);
+
+        let mut currencies: Vec<&String> = invoiced_by_currency
+            .keys()
+            .chain(mrr_by_currency.keys())
+            .collect();
+        currencies.sort();
+        currencies.dedup();
+
+        let estimates: Vec<Value> = currencies
+            .into_iter()
+            .map(|currency| {
+                let invoiced = invoiced_by_currency.get(currency).copied().unwrap_or(0.0);
+                let mrr = mrr_by_currency.get(currency).copied().unwrap_or(0.0);
+
+                serde_json::json!({
+                    "currency": currency,
+                    "historical_invoiced_cents": invoiced.round() as i64,
+                    "average_monthly_invoiced_cents": (invoiced / tenure_months.max(1.0)).round() as i64,
+                    "current_mrr_cents": mrr.round() as i64,
+                    "projected_future_cents": (mrr * projection_months as f64).round() as i64,
+                    "ltv_estimate_cents": (invoiced + mrr * projection_months as f64).round() as i64,
+                })
+            })
+            .collect();
+
+        let result = serde_json::json!({
+            "external_customer_id": args.external_customer_id,
+            "customer_currency": customer["currency"],
+            "customer_since": customer["created_at"],
+            "tenure_months": (tenure_months * 10.0).round() / 10.0,
+            "projection_months": projection_months,
+            "finalized_invoices_count": invoices.len(),
+            "active_subscriptions": subscription_rows,
+            "estimates": estimates,
+            "method": "ltv_estimate = historical finalized invoice totals + current MRR * projection_months",
+            "truncated": invoices_truncated,
+        });
+
+        Ok(success_result(&result))
+    }
+
+    pub async fn get_dso(
+        &self,
+        Parameters(args): Parameters<GetDsoArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let window_days = args.window_days.unwrap_or(90).clamp(1, 365);
+        let now = Utc::now();
+        let window_start = now - Duration::days(window_days);
+        let previous_window_start = window_start - Duration::days(window_days);
+
+        let mut invoice_query = vec![
+            (
+                "issuing_date_from",
+                previous_window_start.date_naive().to_string(),
+            ),
+            ("issuing_date_to", now.date_naive().to_string()),
+            ("status", "finalized".to_string()),
+        ];
+        let mut payment_query = Vec::new();
+        if let Some(external_customer_id) = &args.external_customer_id {
+            invoice_query.push(("external_customer_id", external_customer_id.clone()));
+            payment_query.push(("external_customer_id", external_customer_id.clone()));
+        }
+        if let Some(currency) = &args.currency {
+            invoice_query.push(("currency", currency.clone()));
+        }
+
+        let (invoices, invoices_truncated) = match lago_get_all(
+            &self.http_client,
+            &config,
+            "/invoices",
+            &invoice_query,
+            "invoices",
+            FETCH_ALL_MAX_RECORDS,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                let error_message = format!("Failed to list invoices: {e}");
+                tracing::error!("{error_message}");
+                return Ok(error_result(error_message));
+            }
+        };
+
+        let (payments, payments_truncated) = match lago_get_all(
+            &self.http_client,
+            &config,
+            "/payments",
+            &payment_query,
+            "payments",
+            FETCH_ALL_MAX_RECORDS,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                let error_message = format!("Failed to list payments: {e}");
+                tracing::error!("{error_message}");
+                return Ok(error_result(error_message));
+            }
+        };
+
+        // invoice lago_id -> earliest successful payment date
+        let mut paid_at: BTreeMap<String, DateTime<Utc>> = BTreeMap::new();
+        for payment in &payments {
+            if payment["payment_status"].as_str() != Some("succeeded") {
+                continue;
+            }
+            let Some(created_at) = parse_datetime(&payment["created_at"]) else {
+                continue;
+            };
+
+            for invoice_id in payment["invoice_ids"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                paid_at
+                    .entry(invoice_id.to_string())
+                    .and_modify(|date| *date = (*date).min(created_at))
+                    .or_insert(created_at);
+            }
+        }
+
+        let current = dso_window(&invoices, &paid_at, window_start, now, now);
+        let previous = dso_window(
+            &invoices,
+            &paid_at,
+            previous_window_start,
+            window_start,
+            now,
+        );
+
+        let trend = match (current["dso_days"].as_f64(), previous["dso_days"].as_f64()) {
+            (Some(current_dso), Some(previous_dso)) if current_dso < previous_dso => "improving",
+            (Some(current_dso), Some(previous_dso)) if current_dso > previous_dso => "worsening",
+            (Some(_), Some(_)) => "stable",
+            _ => "insufficient_data",
+        };
+
+        let result = serde_json::json!({
+            "window_days": window_days,
+            "external_customer_id": args.external_customer_id,
+            "currency": args.currency,
+            "current_window": current,
+            "previous_window": previous,
+            "trend": trend,
+            "truncated": invoices_truncated || payments_truncated,
+        });
+
+        Ok(success_result(&result))
+    }
+
+    pub async fn report_expiring_coupons(
+        &self,
+        Parameters(args): Parameters<ReportExpiringCouponsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let days_ahead = args.days_ahead.unwrap_or(30).clamp(1, 365);
+        let now = Utc::now();
+        let horizon = now + Duration::days(days_ahead);
+
+        let (coupons, coupons_truncated) = match lago_get_all(
+            &self.http_client,
+            &config,
+            "/coupons",
+            &[],
+            "coupons",
+            FETCH_ALL_MAX_RECORDS,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                let error_message = format!("Failed to list coupons: {e}");
+                tracing::error!("{error_message}");
+                return Ok(error_result(error_message));
+            }
+        };
+
+        let mut expiring: Vec<(&Value, DateTime<Utc>)> = coupons
+            .iter()
+            .filter(|coupon| coupon["expiration"].as_str() == Some("time_limit"))
+            .filter_map(|coupon| {
+                let expiration_at = parse_datetime(&coupon["expiration_at"])?;
+                (expiration_at >= now && expiration_at <= horizon)
+                    .then_some((coupon, expiration_at))
+            })
+            .collect();
+        expiring.sort_by_key(|(_, expiration_at)| *expiration_at);
+
+        let mut rows = Vec::with_capacity(expiring.len());
+        let mut applied_truncated = false;
+
+        for (coupon, expiration_at) in expiring {
+            let Some(code) = coupon["code"].as_str() else {
+                continue;
+            };
+
+            let query = [
+                ("coupon_code[]", code.to_string()),
+                ("status", "active".to_string()),
+            ];
+            let applied_coupons = match lago_get_all(
+                &self.http_client,
+                &config,
+                "/applied_coupons",
+                &query,
+                "applied_coupons",
+                FETCH_ALL_MAX_RECORDS,
+            )
+            .await
+            {
+                Ok((applied_coupons, truncated)) => {
+                    applied_truncated |= truncated;
+                    applied_coupons
+                }
+                Err(e) => {
+                    tracing::warn!(coupon_code = %code, error = %e, "Failed to list applied coupons");
+                    Vec::new()
+                }
+            };
+
+            let mut customers: Vec<&str> = applied_coupons
+                .iter()
+                .filter_map(|applied_coupon| applied_coupon["external_customer_id"].as_str())
+                .collect();
+            customers.sort_unstable();
+            customers.dedup();
+
+            rows.push(serde_json::json!({
+                "code": code,
+                "name": coupon["name"],
+                "expiration_at": coupon["expiration_at"],
+                "days_until_expiration": (expiration_at - now).num_days(),
+                "coupon_type": coupon["coupon_type"],
+                "frequency": coupon["frequency"],
+                "active_applied_coupons_count": applied_coupons.len(),
+                "affected_customers_count": customers.len(),
+                "affected_customers": customers,
+            }));
+        }
+
+        let result = serde_json::json!({
+            "days_ahead": days_ahead,
+            "expiring_coupons_count": rows.len(),
+            "expiring_coupons": rows,
+            "truncated": coupons_truncated || applied_truncated,
+        });
+
+        Ok(success_result(&result))
+    }
+}
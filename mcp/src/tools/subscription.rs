@@ -12,7 +12,11 @@ use lago_types::{
     },
 };
 
-use crate::tools::{create_lago_client, error_result, success_result};
+use crate::outbound;
+use crate::tools::{
+    FETCH_ALL_MAX_RECORDS, create_lago_client, error_result, get_lago_api_config, lago_get_all,
+    lago_request, success_result,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ListSubscriptionsArgs {
@@ -24,6 +28,8 @@ pub struct ListSubscriptionsArgs {
     pub page: Option<i32>,
     /// Number of items per page (default: 20).
     pub per_page: Option<i32>,
+    /// Walk all pages and return every matching subscription (capped at 1000) instead of a single page.
+    pub fetch_all: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -106,12 +112,31 @@ pub struct DeleteSubscriptionArgs {
     pub status: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetSubscriptionLifetimeUsageArgs {
+    /// The external unique identifier of the subscription.
+    pub external_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UpdateSubscriptionLifetimeUsageArgs {
+    /// The external unique identifier of the subscription.
+    pub external_id: String,
+    /// Usage amount in cents billed outside of Lago (e.g., before migrating to Lago), added to
+    /// the lifetime usage compared against the progressive billing thresholds.
+    pub external_historical_usage_amount_cents: i64,
+}
+
 #[derive(Clone)]
-pub struct SubscriptionService;
+pub struct SubscriptionService {
+    http_client: reqwest::Client,
+}
 
 impl SubscriptionService {
     pub fn new() -> Self {
-        Self
+        Self {
+            http_client: reqwest::Client::new(),
+        }
     }
 
     fn parse_status(status_str: &str) -> Option<SubscriptionStatus> {
@@ -189,6 +214,10 @@ impl SubscriptionService {
         Parameters(args): Parameters<ListSubscriptionsArgs>,
         context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, rmcp::ErrorData> {
+        if args.fetch_all.unwrap_or(false) {
+            return self.list_all_subscriptions(&args, &context).await;
+        }
+
         let client = match create_lago_client(&context).await {
             Ok(client) => client,
             Err(error_result) => return Ok(error_result),
@@ -208,7 +237,7 @@ impl SubscriptionService {
             .with_filters(filters)
             .with_pagination(pagination);
 
-        match client.list_subscriptions(Some(request)).await {
+        match outbound::limited(client.list_subscriptions(Some(request))).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "subscriptions": response.subscriptions,
@@ -225,6 +254,52 @@ impl SubscriptionService {
         }
     }
 
+    /// Walk every page of `GET /subscriptions` for `fetch_all`.
+    async fn list_all_subscriptions(
+        &self,
+        args: &ListSubscriptionsArgs,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(plan_code) = &args.plan_code {
+            query.push(("plan_code", plan_code.clone()));
+        }
+        for status in args.status.iter().flatten() {
+            query.push(("status[]", status.clone()));
+        }
+
+        match lago_get_all(
+            &self.http_client,
+            &config,
+            "/subscriptions",
+            &query,
+            "subscriptions",
+            FETCH_ALL_MAX_RECORDS,
+        )
+        .await
+        {
+            Ok((subscriptions, truncated)) => {
+                let result = serde_json::json!({
+                    "subscriptions": subscriptions,
+                    "total_count": subscriptions.len(),
+                    "truncated": truncated,
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to list subscriptions: {e}");
+                tracing::error!("{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
     pub async fn get_subscription(
         &self,
         Parameters(args): Parameters<GetSubscriptionArgs>,
@@ -237,7 +312,7 @@ impl SubscriptionService {
 
         let request = GetSubscriptionRequest::new(args.external_id);
 
-        match client.get_subscription(request).await {
+        match outbound::limited(client.get_subscription(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "subscription": response.subscription,
@@ -277,7 +352,7 @@ impl SubscriptionService {
             .with_filters(filters)
             .with_pagination(pagination);
 
-        match client.list_customer_subscriptions(request).await {
+        match outbound::limited(client.list_customer_subscriptions(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "subscriptions": response.subscriptions,
@@ -329,7 +404,7 @@ impl SubscriptionService {
 
         let request = CreateSubscriptionRequest::new(input);
 
-        match client.create_subscription(request).await {
+        match outbound::limited(client.create_subscription(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "subscription": response.subscription,
@@ -375,7 +450,7 @@ impl SubscriptionService {
 
         let request = UpdateSubscriptionRequest::new(args.external_id, input);
 
-        match client.update_subscription(request).await {
+        match outbound::limited(client.update_subscription(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "subscription": response.subscription,
@@ -407,7 +482,7 @@ impl SubscriptionService {
             request = request.with_status(status);
         }
 
-        match client.delete_subscription(request).await {
+        match outbound::limited(client.delete_subscription(request)).await {
             Ok(response) => {
                 let result = serde_json::json!({
                     "subscription": response.subscription,
@@ -422,4 +497,70 @@ impl SubscriptionService {
             }
         }
     }
+
+    pub async fn get_subscription_lifetime_usage(
+        &self,
+        Parameters(args): Parameters<GetSubscriptionLifetimeUsageArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.send_lifetime_usage_request(reqwest::Method::GET, &args.external_id, None, &context)
+            .await
+    }
+
+    pub async fn update_subscription_lifetime_usage(
+        &self,
+        Parameters(args): Parameters<UpdateSubscriptionLifetimeUsageArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let body = serde_json::json!({
+            "lifetime_usage": {
+                "external_historical_usage_amount_cents": args.external_historical_usage_amount_cents,
+            }
+        });
+
+        self.send_lifetime_usage_request(
+            reqwest::Method::PUT,
+            &args.external_id,
+            Some(&body),
+            &context,
+        )
+        .await
+    }
+
+    async fn send_lifetime_usage_request(
+        &self,
+        method: reqwest::Method,
+        external_id: &str,
+        body: Option<&serde_json::Value>,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+        let action = if method == reqwest::Method::GET {
+            "get"
+        } else {
+            "update"
+        };
+        let path = format!(
+            "/subscriptions/{}/lifetime_usage",
+            urlencoding::encode(external_id)
+        );
+
+        match lago_request(&self.http_client, &config, method, &path, &[], body).await {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "lifetime_usage": response["lifetime_usage"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to {action} subscription lifetime usage: {e}");
+                tracing::error!(external_id = %external_id, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
 }
@@ -0,0 +1,255 @@
+use rmcp::{RoleServer, handler::server::tool::Parameters, model::*, service::RequestContext};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::tools::{error_result, get_lago_api_config, lago_request, success_result};
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ListTaxesArgs {
+    /// Page number for pagination (default: 1).
+    pub page: Option<i32>,
+    /// Number of items per page (default: 20).
+    pub per_page: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetTaxArgs {
+    /// The unique code of the tax.
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CreateTaxArgs {
+    /// Name of the tax (e.g., "French standard VAT").
+    pub name: String,
+    /// Unique code of the tax, used to reference it from plans, charges and customers.
+    pub code: String,
+    /// Rate of the tax in percent (e.g., "20.0").
+    pub rate: String,
+    /// Internal description of the tax.
+    pub description: Option<String>,
+    /// Apply the tax to every customer of the organization by default (default: false).
+    pub applied_to_organization: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UpdateTaxArgs {
+    /// The current code of the tax to update.
+    pub code: String,
+    /// New name of the tax.
+    pub name: Option<String>,
+    /// New unique code of the tax.
+    pub new_code: Option<String>,
+    /// New rate of the tax in percent (e.g., "20.0").
+    pub rate: Option<String>,
+    /// New internal description of the tax.
+    pub description: Option<String>,
+    /// Apply the tax to every customer of the organization by default.
+    pub applied_to_organization: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DeleteTaxArgs {
+    /// The unique code of the tax to delete.
+    pub code: String,
+}
+
+#[derive(Clone)]
+pub struct TaxService {
+    http_client: reqwest::Client,
+}
+
+impl TaxService {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn list_taxes(
+        &self,
+        Parameters(args): Parameters<ListTaxesArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(page) = args.page {
+            query.push(("page", page.to_string()));
+        }
+        if let Some(per_page) = args.per_page {
+            query.push(("per_page", per_page.to_string()));
+        }
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            "/taxes",
+            &query,
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "taxes": response["taxes"],
+                    "pagination": response["meta"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to list taxes: {e}");
+                tracing::error!("{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
+    pub async fn get_tax(
+        &self,
+        Parameters(args): Parameters<GetTaxArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.send_tax_request(reqwest::Method::GET, &args.code, None, "get", &context)
+            .await
+    }
+
+    pub async fn create_tax(
+        &self,
+        Parameters(args): Parameters<CreateTaxArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut tax = serde_json::json!({
+            "name": args.name,
+            "code": args.code,
+            "rate": args.rate,
+        });
+        if let Some(description) = args.description {
+            tax["description"] = Value::String(description);
+        }
+        if let Some(applied_to_organization) = args.applied_to_organization {
+            tax["applied_to_organization"] = Value::Bool(applied_to_organization);
+        }
+        let body = serde_json::json!({ "tax": tax });
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::POST,
+            "/taxes",
+            &[],
+            Some(&body),
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "tax": response["tax"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to create tax: {e}");
+                tracing::error!(code = %args.code, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
+    pub async fn update_tax(
+        &self,
+        Parameters(args): Parameters<UpdateTaxArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let mut tax = serde_json::Map::new();
+        if let Some(name) = args.name {
+            tax.insert("name".to_string(), Value::String(name));
+        }
+        if let Some(new_code) = args.new_code {
+            tax.insert("code".to_string(), Value::String(new_code));
+        }
+        if let Some(rate) = args.rate {
+            tax.insert("rate".to_string(), Value::String(rate));
+        }
+        if let Some(description) = args.description {
+            tax.insert("description".to_string(), Value::String(description));
+        }
+        if let Some(applied_to_organization) = args.applied_to_organization {
+            tax.insert(
+                "applied_to_organization".to_string(),
+                Value::Bool(applied_to_organization),
+            );
+        }
+
+        if tax.is_empty() {
+            return Ok(error_result("No tax fields to update were provided"));
+        }
+        let body = serde_json::json!({ "tax": tax });
+
+        self.send_tax_request(
+            reqwest::Method::PUT,
+            &args.code,
+            Some(&body),
+            "update",
+            &context,
+        )
+        .await
+    }
+
+    pub async fn delete_tax(
+        &self,
+        Parameters(args): Parameters<DeleteTaxArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        self.send_tax_request(
+            reqwest::Method::DELETE,
+            &args.code,
+            None,
+            "delete",
+            &context,
+        )
+        .await
+    }
+
+    /// Send a request to `/taxes/{code}` and return the tax it responds with.
+    async fn send_tax_request(
+        &self,
+        method: reqwest::Method,
+        code: &str,
+        body: Option<&Value>,
+        action: &str,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+        let path = format!("/taxes/{}", urlencoding::encode(code));
+
+        match lago_request(&self.http_client, &config, method, &path, &[], body).await {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "tax": response["tax"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to {action} tax: {e}");
+                tracing::error!(code = %code, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+}
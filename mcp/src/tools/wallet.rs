@@ -0,0 +1,172 @@
+use rmcp::{RoleServer, handler::server::tool::Parameters, model::*, service::RequestContext};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::tools::{error_result, get_lago_api_config, lago_request, success_result};
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CreateWalletTransactionArgs {
+    /// The Lago ID (UUID) of the wallet to credit.
+    pub wallet_id: String,
+    /// Number of paid credits to purchase. An invoice is issued for them (e.g., "100.0").
+    pub paid_credits: Option<String>,
+    /// Number of free credits to grant, settled right away without an invoice (e.g., "50.0").
+    pub granted_credits: Option<String>,
+    /// Number of credits to void from the wallet balance (e.g., "20.0").
+    pub voided_credits: Option<String>,
+    /// Only credit the paid credits once their invoice has been paid (default: false).
+    pub invoice_requires_successful_payment: Option<bool>,
+    /// Key-value metadata attached to the transaction, e.g. [{"key": "source", "value": "agent"}].
+    pub metadata: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ListWalletTransactionsArgs {
+    /// The Lago ID (UUID) of the wallet.
+    pub wallet_id: String,
+    /// Filter by status: "pending", "settled" or "failed".
+    pub status: Option<String>,
+    /// Filter by transaction status: "purchased", "granted", "voided" or "invoiced".
+    pub transaction_status: Option<String>,
+    /// Filter by transaction type: "inbound" (credits added) or "outbound" (credits consumed).
+    pub transaction_type: Option<String>,
+    /// Page number for pagination (default: 1).
+    pub page: Option<i32>,
+    /// Number of items per page (default: 20).
+    pub per_page: Option<i32>,
+}
+
+#[derive(Clone)]
+pub struct WalletService {
+    http_client: reqwest::Client,
+}
+
+impl WalletService {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn create_wallet_transaction(
+        &self,
+        Parameters(args): Parameters<CreateWalletTransactionArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        if args.paid_credits.is_none()
+            && args.granted_credits.is_none()
+            && args.voided_credits.is_none()
+        {
+            return Ok(error_result(
+                "At least one of paid_credits, granted_credits or voided_credits must be provided",
+            ));
+        }
+
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut wallet_transaction = serde_json::json!({ "wallet_id": args.wallet_id });
+        if let Some(paid_credits) = args.paid_credits {
+            wallet_transaction["paid_credits"] = Value::String(paid_credits);
+        }
+        if let Some(granted_credits) = args.granted_credits {
+            wallet_transaction["granted_credits"] = Value::String(granted_credits);
+        }
+        if let Some(voided_credits) = args.voided_credits {
+            wallet_transaction["voided_credits"] = Value::String(voided_credits);
+        }
+        if let Some(invoice_requires_successful_payment) = args.invoice_requires_successful_payment
+        {
+            wallet_transaction["invoice_requires_successful_payment"] =
+                Value::Bool(invoice_requires_successful_payment);
+        }
+        if let Some(metadata) = args.metadata {
+            wallet_transaction["metadata"] = metadata;
+        }
+        let body = serde_json::json!({ "wallet_transaction": wallet_transaction });
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::POST,
+            "/wallet_transactions",
+            &[],
+            Some(&body),
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "wallet_transactions": response["wallet_transactions"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to create wallet transaction: {e}");
+                tracing::error!(wallet_id = %args.wallet_id, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+
+    pub async fn list_wallet_transactions(
+        &self,
+        Parameters(args): Parameters<ListWalletTransactionsArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(status) = &args.status {
+            query.push(("status", status.clone()));
+        }
+        if let Some(transaction_status) = &args.transaction_status {
+            query.push(("transaction_status", transaction_status.clone()));
+        }
+        if let Some(transaction_type) = &args.transaction_type {
+            query.push(("transaction_type", transaction_type.clone()));
+        }
+        if let Some(page) = args.page {
+            query.push(("page", page.to_string()));
+        }
+        if let Some(per_page) = args.per_page {
+            query.push(("per_page", per_page.to_string()));
+        }
+
+        let path = format!(
+            "/wallets/{}/wallet_transactions",
+            urlencoding::encode(&args.wallet_id)
+        );
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            &path,
+            &query,
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "wallet_transactions": response["wallet_transactions"],
+                    "pagination": response["meta"],
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to list wallet transactions: {e}");
+                tracing::error!(wallet_id = %args.wallet_id, "{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+}
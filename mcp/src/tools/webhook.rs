@@ -0,0 +1,52 @@
+use rmcp::{RoleServer, model::*, service::RequestContext};
+
+use crate::tools::{error_result, get_lago_api_config, lago_request, success_result};
+
+#[derive(Clone)]
+pub struct WebhookService {
+    http_client: reqwest::Client,
+}
+
+impl WebhookService {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn get_webhook_public_key(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let config = match get_lago_api_config(&context).await {
+            Ok(config) => config,
+            Err(error_result) => return Ok(error_result),
+        };
+
+        match lago_request(
+            &self.http_client,
+            &config,
+            reqwest::Method::GET,
+            "/webhooks/json_public_key",
+            &[],
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                let result = serde_json::json!({
+                    "public_key": response["webhook"]["public_key"],
+                    "encoding": "base64",
+                    "signature_algorithm": "RS256",
+                });
+
+                Ok(success_result(&result))
+            }
+            Err(e) => {
+                let error_message = format!("Failed to get webhook public key: {e}");
+                tracing::error!("{error_message}");
+                Ok(error_result(error_message))
+            }
+        }
+    }
+}